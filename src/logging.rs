@@ -0,0 +1,35 @@
+//! Where the library's messages go: nowhere until a logger is set. The browser viewer sets one writing to the console.
+
+use std::sync::RwLock;
+
+type Logger = Box<dyn Fn(&str) + Send + Sync>;
+
+static LOGGER: RwLock<Option<Logger>> = RwLock::new(None);
+
+/// sends the messages to `logger` from now on, in place of the previous one
+pub fn set_logger<F>(logger: F) where F: Fn(&str) + Send + Sync + 'static{
+    *LOGGER.write().unwrap_or_else(|err| err.into_inner()) = Some(Box::new(logger));
+}
+
+pub fn log(message: &str){
+    if let Some(logger) = LOGGER.read().unwrap_or_else(|err| err.into_inner()).as_ref(){
+        logger(message);
+    }
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+    use std::sync::Mutex;
+
+    use crate::mesh::Mesh;
+
+    #[test]
+    fn messages_reach_the_logger(){
+        static MESSAGES: Mutex<Vec<String>> = Mutex::new(vec![]);
+        set_logger(|message| MESSAGES.lock().unwrap().push(message.to_string()));
+
+        Mesh::load_obj("v 0 0 0\nv 1 0 0\nv 0 1 0\nf 1 2 3\n").unwrap();
+        assert!(MESSAGES.lock().unwrap().iter().any(|message| message == "loaded 3v 1f"));
+    }
+}
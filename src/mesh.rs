@@ -1,36 +1,96 @@
-use nalgebra::{Vector3};
-use std::{f32::{INFINITY, NEG_INFINITY}, str::FromStr};
-use web_sys::{console};
+use nalgebra::{Matrix4, Point3, Vector3};
+use std::str::FromStr;
 use std::collections::HashMap;
 use std::collections::HashSet;
 
+use crate::logging::log;
+
+mod bounding;
+mod bvh;
+mod components;
+mod curvature;
+mod deviation;
+mod measurement;
+mod registration;
+mod selection;
+mod simplify;
+mod slicing;
+mod smoothing;
+mod subdivision;
+
+pub use bounding::{create_box_line_buffers, create_hull_line_buffers, create_sphere_line_buffers, BoundingSphere, OrientedBox};
+pub use bvh::{Bvh, SurfacePoint};
+pub use components::ComponentInfo;
+pub use curvature::CurvatureKind;
+pub use deviation::DeviationStats;
+pub use registration::{IcpMetric, IcpOptions, IcpReport};
+pub use measurement::MassProperties;
+pub use selection::SnapMode;
+pub use slicing::{create_section_cap_buffers, create_section_line_buffers, section_svg, Plane, Polyline};
+pub use smoothing::SmoothingMethod;
+pub use subdivision::MAX_SUBDIVISION_LEVEL;
+
+#[derive(Clone)]
 pub struct Vertex{
     pos: Vector3<f32>,
-    normal: Vector3<f32>
+    normal: Vector3<f32>,
+    color: Vector3<f32>
+}
+
+impl Vertex{
+    pub fn default_color() -> Vector3<f32>{
+        return Vector3::new(1.0, 1.0, 1.0);
+    }
+}
+
+/// point moved to the origin by `move_pivot_to_center`
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum PivotCenter{
+    BoundsCenter,
+    AreaCentroid,
+    VolumeCentroid
 }
 
 #[derive(Clone)]
 pub struct Face{
-    verts: Vec<usize>
+    verts: Vec<usize>,
+    hidden: bool // left out of the gl buffers
 }
 pub struct Mesh{
     verts: Vec<Vertex>,
     faces: Vec<Face>,
+    polygon_faces: Option<Vec<Face>>, // faces as loaded, kept when triangulation replaced them
     is_triangulated: bool,
     bb_min: Vector3<f32>,
     bb_max: Vector3<f32>
 }
 
-impl Mesh{
-    pub fn load_obj(obj_str: &String) -> Result<Mesh, String>{
-        let mut obj_vertices: Vec<Vector3<f32>> = vec![];
-        let mut obj_normals: Vec<Vector3<f32>> = vec![];
-        let mut obj_faces: Vec<Vec<(i32, i32, i32)>> = vec![];
+/// face of an OBJ file as (vertex, texture, normal) indices per corner
+type ObjFace = Vec<(i32, i32, i32)>;
 
-        let mut verts : Vec<Vertex> = vec![];
-        let mut faces : Vec<Face> = vec![];
+/// contents of an OBJ file, faces per group
+struct ObjData{
+    vertices: Vec<Vector3<f32>>,
+    normals: Vec<Vector3<f32>>,
+    groups: Vec<(String, Vec<ObjFace>)>,
+    has_normals: bool // faces refer to normals, all or none do
+}
+
+/// union-find lookup with path halving
+fn find_root(parents: &mut [usize], mut id: usize) -> usize{
+    while parents[id] != id{
+        parents[id] = parents[parents[id]];
+        id = parents[id];
+    }
+    return id;
+}
+
+impl Mesh{
+    /// Reads positions, normals and faces, the faces split by the group (`g`) or object (`o`) statement
+    /// they follow. Materials and smoothing groups are ignored.
+    fn parse_obj(obj_str: &str) -> Result<ObjData, String>{
+        let mut data = ObjData{vertices: vec![], normals: vec![], groups: vec![("default".to_string(), vec![])], has_normals: false};
 
-        let mut is_triangulated = true;
         let mut found_simple_face_def = false;
         let mut found_complex_face_def = false;
 
@@ -48,14 +108,14 @@ impl Mesh{
             match first_word {
                 "v" => 
                 {
-                    obj_vertices.push(Vector3::new(
+                    data.vertices.push(Vector3::new(
                             f32::from_str(words[1].trim()).map_err(|e| e.to_string())?,
                             f32::from_str(words[2].trim()).map_err(|e| e.to_string())?,
                             f32::from_str(words[3].trim()).map_err(|e| e.to_string())?
                     ));
                 },
                 "vn" =>{
-                    obj_normals.push(Vector3::new(
+                    data.normals.push(Vector3::new(
                             f32::from_str(words[1].trim()).map_err(|e| e.to_string())?,
                             f32::from_str(words[2].trim()).map_err(|e| e.to_string())?,
                             f32::from_str(words[3].trim()).map_err(|e| e.to_string())?
@@ -74,8 +134,8 @@ impl Mesh{
 
                             if parts.len() != 3{return Err("Invalid face definition".to_string())}
 
-                            let vert_index = i32::from_str(&parts[0]).unwrap()-1;
-                            let normal_index = i32::from_str(&parts[2]).unwrap()-1;
+                            let vert_index = i32::from_str(parts[0]).unwrap()-1;
+                            let normal_index = i32::from_str(parts[2]).unwrap()-1;
 
                             obj_face.push((vert_index, -1, normal_index));
                         }
@@ -85,12 +145,21 @@ impl Mesh{
                         found_simple_face_def = true;
 
                         for word in &words[1..] {
-                            let vert_index = i32::from_str(&word.trim()).unwrap()-1;
+                            let vert_index = i32::from_str(word.trim()).unwrap()-1;
                             obj_face.push((vert_index, -1, -1));
                         }
                     }
-                    obj_faces.push(obj_face);
+                    data.groups.last_mut().unwrap().1.push(obj_face);
+                },
+                "g" | "o" => {
+                    let name = words[1..].join(" ").trim().to_string();
+                    let name = if name.is_empty() {"default".to_string()} else {name};
+                    if data.groups.last().is_some_and(|group| group.1.is_empty()){ // nothing in the previous one
+                        data.groups.pop();
+                    }
+                    data.groups.push((name, vec![]));
                 },
+                "s" | "usemtl" | "mtllib" => {},
                 "" => {},
                 _ => {
                     return Err(format!("Unexpected character: {first_word}").to_string())
@@ -99,58 +168,128 @@ impl Mesh{
         }
 
         assert!(!(found_simple_face_def && found_complex_face_def));
+        data.has_normals = found_complex_face_def;
+        data.groups.retain(|group| !group.1.is_empty());
+
+        Ok(data)
+    }
+
+    /// Mesh of the given faces, with every parsed vertex or only the ones they use (in file order),
+    /// positions left where they are.
+    fn from_obj_faces(data: &ObjData, obj_faces: &[&Vec<(i32, i32, i32)>], keep_unused_verts: bool) -> Result<Mesh, String>{
+        let mut verts : Vec<Vertex> = vec![];
+        let mut faces : Vec<Face> = vec![];
+
+        let mut is_triangulated = true;
 
         // Transform obj_verts, obj_normals, and obj_faces into Vertex, and Face vectors
 
-        if found_simple_face_def{
-            for obj_vert in obj_vertices{
-                verts.push(Vertex { pos: obj_vert, normal: Vector3::new(0.0,0.0,0.0) });
+        if !data.has_normals{
+            let mut obj_vert_ids: Vec<Option<usize>> = vec![None; data.vertices.len()];
+            for obj_face in obj_faces{
+                for vert_uv_normal_def in obj_face.iter(){
+                    let obj_vert = obj_vert_ids.get_mut(vert_uv_normal_def.0 as usize).ok_or("Face refers to a missing vertex")?;
+                    *obj_vert = Some(0);
+                }
+            }
+            for (obj_id, obj_vert) in data.vertices.iter().enumerate(){
+                if keep_unused_verts || obj_vert_ids[obj_id].is_some(){
+                    obj_vert_ids[obj_id] = Some(verts.len());
+                    verts.push(Vertex { pos: *obj_vert, normal: Vector3::new(0.0,0.0,0.0), color: Vertex::default_color() });
+                }
             }
             for obj_face in obj_faces{
                 let mut temp_vert_ids : Vec<usize> = vec![];
-                for vert_uv_normal_def in obj_face{
-                    temp_vert_ids.push(vert_uv_normal_def.0 as usize);
+                for vert_uv_normal_def in obj_face.iter(){
+                    temp_vert_ids.push(obj_vert_ids[vert_uv_normal_def.0 as usize].unwrap());
                 }
                 if temp_vert_ids.len() > 3{is_triangulated = false;}
-                faces.push(Face{verts: temp_vert_ids.clone()});
+                faces.push(Face{verts: temp_vert_ids.clone(), hidden: false});
             }
-        }else if found_complex_face_def{
+        }else{
             let mut indexes_to_vert_ids: HashMap<(i32, i32, i32), usize> = HashMap::new();
 
             for obj_face in obj_faces{
                 let mut temp_vert_ids : Vec<usize> = vec![];
 
-                for vert_uv_normal_def in obj_face{
-                    if let Some(vert_id) = indexes_to_vert_ids.get(&vert_uv_normal_def){//already exists
-                        temp_vert_ids.push(vert_id.clone());
+                for vert_uv_normal_def in obj_face.iter(){
+                    if let Some(vert_id) = indexes_to_vert_ids.get(vert_uv_normal_def){//already exists
+                        temp_vert_ids.push(*vert_id);
                     }else{
-                        verts.push(Vertex { pos: obj_vertices[vert_uv_normal_def.0 as usize],
-                             normal: obj_normals[vert_uv_normal_def.2 as usize] });
-                        let new_vert_index =  usize::try_from(verts.len() - 1).expect("Exceeded vert limit");
-                        indexes_to_vert_ids.insert(vert_uv_normal_def, new_vert_index);
+                        verts.push(Vertex { pos: data.vertices[vert_uv_normal_def.0 as usize],
+                             normal: data.normals[vert_uv_normal_def.2 as usize], color: Vertex::default_color() });
+                        let new_vert_index = verts.len() - 1;
+                        indexes_to_vert_ids.insert(*vert_uv_normal_def, new_vert_index);
                         temp_vert_ids.push(new_vert_index);
                     }
                 }
 
                 if temp_vert_ids.len() > 3{is_triangulated = false;}
-                faces.push(Face{verts: temp_vert_ids.clone()});
+                faces.push(Face{verts: temp_vert_ids.clone(), hidden: false});
             }
         }
         
-        let mut mesh = Mesh{verts: verts, faces: faces, is_triangulated: is_triangulated,
+        let mut mesh = Mesh{verts: verts, faces: faces, polygon_faces: None, is_triangulated: is_triangulated,
             bb_min: Vector3::new(0.0,0.0,0.0), bb_max: Vector3::new(0.0,0.0,0.0)};
-        if found_simple_face_def{
+        if !data.has_normals{
             mesh.derrive_normals_from_faces()?;
         }
         mesh.triangulate_faces()?;
-        mesh.move_pivot_to_center();
-        
-        console::log_1(&format!("loaded {:?}v {:?}f", mesh.verts.len(), mesh.faces.len()).into());
-        console::log_1(&format!("was triangulated: {is_triangulated}").into());
-        console::log_1(&format!("had normals: {found_complex_face_def}").into());
+        (mesh.bb_min, mesh.bb_max) = mesh.compute_bounds();
+
+        log(&format!("loaded {:?}v {:?}f", mesh.verts.len(), mesh.faces.len()));
+        log(&format!("was triangulated: {is_triangulated}"));
+        log(&format!("had normals: {}", data.has_normals));
         Ok(mesh)
     }
 
+    /// the whole file as one mesh, centered on its bounds
+    pub fn load_obj(obj_str: &str) -> Result<Mesh, String>{
+        return Mesh::whole_obj(&Mesh::parse_obj(obj_str)?);
+    }
+
+    fn whole_obj(data: &ObjData) -> Result<Mesh, String>{
+        let obj_faces: Vec<&Vec<(i32, i32, i32)>> = data.groups.iter().flat_map(|group| &group.1).collect();
+
+        let mut mesh = Mesh::from_obj_faces(data, &obj_faces, true)?;
+        mesh.move_pivot_to_center(PivotCenter::BoundsCenter);
+        Ok(mesh)
+    }
+
+    /// One named mesh per group (`g`) or object (`o`) of the file, in file order. They are moved together
+    /// so their common bounds are centered, each keeps its place relative to the others.
+    /// A file with a single group, or no faces at all, gives one mesh the same as `load_obj`.
+    pub fn load_obj_groups(obj_str: &str) -> Result<Vec<(String, Mesh)>, String>{
+        let data = Mesh::parse_obj(obj_str)?;
+        if data.groups.len() <= 1{
+            let name = data.groups.first().map(|group| group.0.clone()).unwrap_or("default".to_string());
+            return Ok(vec![(name, Mesh::whole_obj(&data)?)]);
+        }
+
+        let mut groups: Vec<(String, Mesh)> = vec![];
+        for (name, obj_faces) in &data.groups{
+            let obj_faces: Vec<&Vec<(i32, i32, i32)>> = obj_faces.iter().collect();
+            groups.push((name.clone(), Mesh::from_obj_faces(&data, &obj_faces, false)?));
+        }
+
+        let bb_min = groups.iter().fold(Vector3::repeat(f32::INFINITY), |bb_min, (_, mesh)| bb_min.inf(&mesh.bb_min));
+        let bb_max = groups.iter().fold(Vector3::repeat(f32::NEG_INFINITY), |bb_max, (_, mesh)| bb_max.sup(&mesh.bb_max));
+        let offset = Matrix4::new_translation(&(-(bb_min + bb_max) / 2.0));
+        for (_, mesh) in &mut groups{
+            mesh.transform(&offset)?;
+        }
+        Ok(groups)
+    }
+
+    pub fn face_count(&self) -> usize{
+        return self.faces.len();
+    }
+
+    /// radius of the sphere around the pivot enclosing the bounding box
+    pub fn bounding_radius(&self) -> f32{
+        return self.bb_min.abs().sup(&self.bb_max.abs()).norm();
+    }
+
     pub fn create_primitive_buffers(&self) -> Result<(Vec<f32>, Vec<usize>), &str>{
         if !self.is_triangulated{
             return Err("Mesh is not triangulated");
@@ -168,9 +307,13 @@ impl Mesh{
             verts.push(vert.normal.x);
             verts.push(vert.normal.y);
             verts.push(vert.normal.z);
+
+            verts.push(vert.color.x);
+            verts.push(vert.color.y);
+            verts.push(vert.color.z);
         }
 
-        for face in &(self.faces){
+        for face in self.faces.iter().filter(|face| !face.hidden){
             for vert in &(face.verts){
                 indices.push(*vert);
             }
@@ -179,6 +322,20 @@ impl Mesh{
         Ok((verts, indices))
     }
 
+    /// smooth shaded buffers with one of `colors` per vertex in place of the vertex colors
+    pub fn create_primitive_buffers_colored(&self, colors: &[Vector3<f32>]) -> Result<(Vec<f32>, Vec<usize>), &str>{
+        if colors.len() != self.verts.len(){
+            return Err("Expected a color for every vertex");
+        }
+
+        let (mut verts, indices) = self.create_primitive_buffers()?;
+        for (vert, color) in verts.chunks_mut(9).zip(colors){
+            vert[6..9].copy_from_slice(color.as_slice());
+        }
+
+        Ok((verts, indices))
+    }
+
     pub fn create_primitive_buffers_wireframe(&self) -> Result<(Vec<f32>, Vec<usize>), &str>{
         if !self.is_triangulated{
             return Err("Mesh is not triangulated");
@@ -201,7 +358,7 @@ impl Mesh{
             verts.push(vert.pos.z);
         }
 
-        for face in &(self.faces){
+        for face in self.faces.iter().filter(|face| !face.hidden){
             if is_new_edge(face.verts[0], face.verts[1]){ indices.push(face.verts[0]); indices.push(face.verts[1]);}
             if is_new_edge(face.verts[1], face.verts[2]){ indices.push(face.verts[1]); indices.push(face.verts[2]);}
             if is_new_edge(face.verts[2], face.verts[0]){ indices.push(face.verts[2]); indices.push(face.verts[0]);}
@@ -210,9 +367,28 @@ impl Mesh{
         Ok((verts, indices))
     }
 
+    /// merges vertices sharing a position (split by normals),
+    /// returns the unique positions and the welded id of every vertex
+    fn weld_positions(&self) -> (Vec<Vector3<f32>>, Vec<usize>){
+        let mut welded_ids: HashMap<[u32; 3], usize> = HashMap::new();
+        let mut positions: Vec<Vector3<f32>> = vec![];
+        let mut remap: Vec<usize> = Vec::with_capacity(self.verts.len());
+
+        for vert in &self.verts{
+            let key = [vert.pos.x.to_bits(), vert.pos.y.to_bits(), vert.pos.z.to_bits()];
+            let id = *welded_ids.entry(key).or_insert_with(|| {
+                positions.push(vert.pos);
+                positions.len() - 1
+            });
+            remap.push(id);
+        }
+
+        return (positions, remap);
+    }
+
     fn compute_bounds(&self) -> (Vector3<f32>, Vector3<f32>){
-        let (mut min_x, mut min_y, mut min_z) =  (INFINITY, INFINITY, INFINITY);
-        let (mut max_x, mut max_y, mut max_z) =  (NEG_INFINITY, NEG_INFINITY, NEG_INFINITY);
+        let (mut min_x, mut min_y, mut min_z) =  (f32::INFINITY, f32::INFINITY, f32::INFINITY);
+        let (mut max_x, mut max_y, mut max_z) =  (f32::NEG_INFINITY, f32::NEG_INFINITY, f32::NEG_INFINITY);
 
         for vert in &(self.verts){
             min_x = min_x.min(vert.pos.x);
@@ -256,17 +432,42 @@ impl Mesh{
         Ok((verts, indices))
     }
 
-    pub fn move_pivot_to_center(&mut self) {
+    pub fn center(&self, pivot: PivotCenter) -> Vector3<f32>{
+        return match pivot {
+            PivotCenter::BoundsCenter => {
+                let (bb_min, bb_max) = self.compute_bounds();
+                (bb_max + bb_min) / 2.0
+            },
+            PivotCenter::AreaCentroid => self.area_centroid(),
+            PivotCenter::VolumeCentroid => self.volume_centroid()
+        };
+    }
+
+    pub fn move_pivot_to_center(&mut self, pivot: PivotCenter) {
         let (bb_min, bb_max) = self.compute_bounds();
-        let bb_center = (bb_max + bb_min) / 2.0;
+        let center = self.center(pivot);
 
          for vert in &mut (self.verts){
             // pos
-            vert.pos -= bb_center;
+            vert.pos -= center;
         }
 
-        self.bb_min = bb_min-bb_center;
-        self.bb_max = bb_max-bb_center;
+        self.bb_min = bb_min-center;
+        self.bb_max = bb_max-center;
+    }
+
+    /// moves the vertices by an affine transform, normals follow its inverse transpose
+    pub fn transform(&mut self, transform: &Matrix4<f32>) -> Result<(), String>{
+        let linear = transform.fixed_view::<3, 3>(0, 0).into_owned();
+        let normal_matrix = linear.try_inverse().ok_or("Transform is not invertible")?.transpose();
+
+        for vert in &mut self.verts{
+            vert.pos = transform.transform_point(&Point3::from(vert.pos)).coords;
+            vert.normal = (normal_matrix * vert.normal).try_normalize(0.0).unwrap_or(vert.normal);
+        }
+
+        (self.bb_min, self.bb_max) = self.compute_bounds();
+        Ok(())
     }
 
     pub fn triangulate_faces(&mut self) ->Result<(), &str>{
@@ -282,16 +483,16 @@ impl Mesh{
             }else{
                 let mut indices: Vec<usize> = vec![0, 0, 0];
 
-                for i in 0..face.verts.len()-1{
+                for i in 1..face.verts.len()-1{
                     indices[0] = face.verts[0];
                     indices[1] = face.verts[i];
                     indices[2] = face.verts[i+1];
-                    new_faces.push(Face{verts: indices.clone()});
+                    new_faces.push(Face{verts: indices.clone(), hidden: face.hidden});
                 }
             }
         }
 
-        self.faces = new_faces;
+        self.polygon_faces = Some(std::mem::replace(&mut self.faces, new_faces));
         self.is_triangulated = true;
         Ok(())
     }
@@ -316,38 +517,45 @@ impl Mesh{
             verts.push(0.0);
             verts.push(0.0);
             verts.push(0.0);
+            // color
+            verts.push(vert.color.x);
+            verts.push(vert.color.y);
+            verts.push(vert.color.z);
         }
 
-        let vert_attr_count = 6;
+        let vert_attr_count = 9;
 
-        for face in &(self.faces){ // assumes all faces are triangles
-            let v1 = self.verts[face.verts[0] as usize].pos;
-            let v2 = self.verts[face.verts[1] as usize].pos;
-            let v3 = self.verts[face.verts[2] as usize].pos;
+        for face in self.faces.iter().filter(|face| !face.hidden){ // assumes all faces are triangles
+            let v1 = self.verts[face.verts[0]].pos;
+            let v2 = self.verts[face.verts[1]].pos;
+            let v3 = self.verts[face.verts[2]].pos;
 
             let f_normal = (v2 - v1).cross(&(v3-v1)).normalize();
 
             let final_tri: (usize, usize, usize);
 
-            if is_used[face.verts[2] as usize]{ // duplicate vertex
-                    final_tri = (face.verts[0], face.verts[1], (verts.len() / vert_attr_count) as usize);// set to the last element, before pushing the vert!
+            if is_used[face.verts[2]]{ // duplicate vertex
+                    final_tri = (face.verts[0], face.verts[1], verts.len() / vert_attr_count);// set to the last element, before pushing the vert!
 
-                    verts.push(self.verts[face.verts[2] as usize].pos.x);
-                    verts.push(self.verts[face.verts[2] as usize].pos.y);
-                    verts.push(self.verts[face.verts[2] as usize].pos.z);
+                    verts.push(self.verts[face.verts[2]].pos.x);
+                    verts.push(self.verts[face.verts[2]].pos.y);
+                    verts.push(self.verts[face.verts[2]].pos.z);
                     verts.push(f_normal.x);
                     verts.push(f_normal.y);
                     verts.push(f_normal.z);
+                    verts.push(self.verts[face.verts[2]].color.x);
+                    verts.push(self.verts[face.verts[2]].color.y);
+                    verts.push(self.verts[face.verts[2]].color.z);
 
                     // console::log_1(&("duplicating").into());
                 // }
             }else{
-                is_used[face.verts[2] as usize] = true;
+                is_used[face.verts[2]] = true;
                 final_tri = (face.verts[0], face.verts[1], face.verts[2]);
                 // update desired normal
             }
             
-            let arr_index = (final_tri.2 as usize)*vert_attr_count;
+            let arr_index = final_tri.2*vert_attr_count;
             verts[arr_index+3] = f_normal.x;
             verts[arr_index+4] = f_normal.y;
             verts[arr_index+5] = f_normal.z;
@@ -367,21 +575,66 @@ impl Mesh{
         }
 
         for face in &(self.faces){
-            let v1 = self.verts[face.verts[0] as usize].pos;
-            let v2 = self.verts[face.verts[1] as usize].pos;
-            let v3 = self.verts[face.verts[2] as usize].pos;
+            let v1 = self.verts[face.verts[0]].pos;
+            let v2 = self.verts[face.verts[1]].pos;
+            let v3 = self.verts[face.verts[2]].pos;
 
             let f_normal = (v2 - v1).cross(&(v3-v1)).normalize();
 
             for vert in &(face.verts){
-                self.verts[*vert as usize].normal += f_normal;
+                self.verts[*vert].normal += f_normal;
             }
         }
 
         for vert in &mut self.verts{
-            vert.normal.normalize();
+            vert.normal.normalize_mut();
         }
 
         Ok(())
     }
 }
+
+#[cfg(test)]
+pub(crate) mod tests{
+    use super::*;
+
+    /// closed unit cube centered on the origin, faces counter-clockwise seen from outside
+    pub(crate) const UNIT_CUBE: &str = "v -0.5 -0.5 -0.5\nv 0.5 -0.5 -0.5\nv 0.5 0.5 -0.5\nv -0.5 0.5 -0.5\n\
+        v -0.5 -0.5 0.5\nv 0.5 -0.5 0.5\nv 0.5 0.5 0.5\nv -0.5 0.5 0.5\n\
+        f 1 4 3 2\nf 5 6 7 8\nf 1 2 6 5\nf 3 4 8 7\nf 1 5 8 4\nf 2 3 7 6\n";
+
+    /// the unit cube and, 5 along x, a cube twice its size
+    pub(crate) fn two_cubes_obj() -> String{
+        let mut obj = UNIT_CUBE.to_string();
+        for line in UNIT_CUBE.lines(){
+            let mut values = line.split_whitespace();
+            match values.next(){
+                Some("v") => {
+                    let pos: Vec<f32> = values.map(|value| value.parse::<f32>().unwrap() * 2.0).collect();
+                    obj.push_str(&format!("v {} {} {}\n", pos[0] + 5.0, pos[1], pos[2]));
+                },
+                Some("f") => {
+                    let ids: Vec<String> = values.map(|value| (value.parse::<usize>().unwrap() + 8).to_string()).collect();
+                    obj.push_str(&format!("f {}\n", ids.join(" ")));
+                },
+                _ => {}
+            }
+        }
+        return obj;
+    }
+
+    /// `two_cubes_obj` loaded. Loading centers them, moving both 2.75 to -x.
+    pub(crate) fn two_cubes() -> Mesh{
+        return Mesh::load_obj(&two_cubes_obj()).unwrap();
+    }
+
+    #[test]
+    fn polygons_split_into_a_fan_covering_them(){
+        // pentagon of area 3, a 2x1 rectangle with a roof of height 1
+        let mesh = Mesh::load_obj("v 0 0 0\nv 2 0 0\nv 2 1 0\nv 1 2 0\nv 0 1 0\nf 1 2 3 4 5\n").unwrap();
+        assert_eq!(mesh.faces.len(), 3);
+        assert!(mesh.faces.iter().all(|face| face.verts.len() == 3 && face.verts.iter().all(|vert| *vert < 5)));
+        assert!(mesh.faces.iter().all(|face| face.verts[0] != face.verts[1] && face.verts[1] != face.verts[2]));
+        assert!((mesh.surface_area() - 3.0).abs() < 1e-5);
+    }
+}
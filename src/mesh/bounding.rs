@@ -0,0 +1,538 @@
+use std::collections::{HashMap, HashSet};
+
+use nalgebra::{Matrix3, SymmetricEigen, Vector2, Vector3};
+
+use super::Mesh;
+
+/// closed convex triangle mesh around the vertices, faces wound counter-clockwise seen from outside
+#[derive(Clone, Debug)]
+pub struct ConvexHull{
+    pub points: Vec<Vector3<f32>>,
+    pub faces: Vec<[usize; 3]>
+}
+
+impl ConvexHull{
+    pub fn volume(&self) -> f32{
+        return self.faces.iter()
+            .map(|[a, b, c]| self.points[*a].dot(&self.points[*b].cross(&self.points[*c])) / 6.0)
+            .sum();
+    }
+}
+
+/// box with `axes` as its orthonormal local frame and `half_extents` along each of them
+#[derive(Clone, Copy, Debug)]
+pub struct OrientedBox{
+    pub center: Vector3<f32>,
+    pub axes: Matrix3<f32>, // columns
+    pub half_extents: Vector3<f32>
+}
+
+impl OrientedBox{
+    pub fn volume(&self) -> f32{
+        return 8.0 * self.half_extents.x * self.half_extents.y * self.half_extents.z;
+    }
+
+    /// corners in the same order as `Mesh::create_bb_primitive_buffers`, x being the most significant bit
+    pub fn corners(&self) -> [Vector3<f32>; 8]{
+        return std::array::from_fn(|i| {
+            let signs = Vector3::new(
+                if i & 4 != 0 {1.0} else {-1.0},
+                if i & 2 != 0 {1.0} else {-1.0},
+                if i & 1 != 0 {1.0} else {-1.0});
+            self.center + self.axes * self.half_extents.component_mul(&signs)
+        });
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct BoundingSphere{
+    pub center: Vector3<f32>,
+    pub radius: f32
+}
+
+impl BoundingSphere{
+    fn contains(&self, point: &Vector3<f32>) -> bool{
+        return (point - self.center).norm() <= self.radius * (1.0 + 1e-5) + 1e-7;
+    }
+}
+
+struct HullFace{
+    verts: [usize; 3],
+    normal: Vector3<f64>,
+    offset: f64,
+    outside: Vec<usize>, // points in front of the face not yet on the hull
+    is_alive: bool
+}
+
+impl HullFace{
+    fn new(points: &[Vector3<f64>], verts: [usize; 3]) -> HullFace{
+        let [a, b, c] = verts.map(|vert| points[vert]);
+        let normal = (b - a).cross(&(c - a)).try_normalize(0.0).unwrap_or(Vector3::zeros());
+        return HullFace{verts: verts, normal: normal, offset: normal.dot(&a), outside: vec![], is_alive: true};
+    }
+
+    fn distance(&self, point: &Vector3<f64>) -> f64{
+        return self.normal.dot(point) - self.offset;
+    }
+}
+
+/// quickhull over unique points, in double precision
+fn quickhull(points: &[Vector3<f64>]) -> Result<Vec<[usize; 3]>, String>{
+    if points.len() < 4{
+        return Err("A hull needs at least 4 points".to_string());
+    }
+
+    let extent = points.iter().fold(0.0f64, |extent, point| extent.max(point.abs().max()));
+    let epsilon = extent * 1e-9 * 3.0;
+
+    // initial tetrahedron from extreme points
+    let farthest_from = |distance: &dyn Fn(&Vector3<f64>) -> f64| -> usize {
+        (0..points.len()).max_by(|a, b| distance(&points[*a]).total_cmp(&distance(&points[*b]))).unwrap()
+    };
+    let a = farthest_from(&|point| point.x);
+    let b = farthest_from(&|point| (point - points[a]).norm());
+    let ab = points[b] - points[a];
+    let c = farthest_from(&|point| ab.cross(&(point - points[a])).norm());
+    let base_normal = ab.cross(&(points[c] - points[a]));
+    let d = farthest_from(&|point| base_normal.dot(&(point - points[a])).abs());
+
+    if ab.norm() <= epsilon || base_normal.norm() <= epsilon * ab.norm(){
+        return Err("Points are collinear".to_string());
+    }
+    if base_normal.normalize().dot(&(points[d] - points[a])).abs() <= epsilon{
+        return Err("Points are coplanar".to_string());
+    }
+
+    let mut faces: Vec<HullFace> = vec![];
+    let tetra = if base_normal.dot(&(points[d] - points[a])) < 0.0{
+        [[a, b, c], [a, d, b], [b, d, c], [c, d, a]]
+    }else{
+        [[a, c, b], [a, b, d], [b, c, d], [c, a, d]]
+    };
+    for verts in tetra{
+        faces.push(HullFace::new(points, verts));
+    }
+
+    let assign = |faces: &mut Vec<HullFace>, candidates: &[usize], point_ids: &mut dyn Iterator<Item = usize>| {
+        for point in point_ids{
+            let face = candidates.iter()
+                .map(|face_id| (*face_id, faces[*face_id].distance(&points[point])))
+                .filter(|(_, distance)| *distance > epsilon)
+                .max_by(|a, b| a.1.total_cmp(&b.1));
+            if let Some((face_id, _)) = face{
+                faces[face_id].outside.push(point);
+            }
+        }
+    };
+    let initial: Vec<usize> = (0..faces.len()).collect();
+    assign(&mut faces, &initial, &mut (0..points.len()).filter(|point| ![a, b, c, d].contains(point)));
+
+    // directed edge to the face it belongs to, the twin edge leads to the neighbour
+    let mut edge_faces: HashMap<(usize, usize), usize> = HashMap::new();
+    let add_edges = |edge_faces: &mut HashMap<(usize, usize), usize>, face_id: usize, verts: [usize; 3]| {
+        for i in 0..3{
+            edge_faces.insert((verts[i], verts[(i + 1) % 3]), face_id);
+        }
+    };
+    for (face_id, face) in faces.iter().enumerate(){
+        add_edges(&mut edge_faces, face_id, face.verts);
+    }
+
+    let mut pending: Vec<usize> = initial;
+    while let Some(face_id) = pending.pop(){
+        if !faces[face_id].is_alive || faces[face_id].outside.is_empty(){
+            continue;
+        }
+        let eye = *faces[face_id].outside.iter()
+            .max_by(|p, q| faces[face_id].distance(&points[**p]).total_cmp(&faces[face_id].distance(&points[**q])))
+            .unwrap();
+
+        // visible faces are connected, flood them from the one the eye point was assigned to
+        let mut visible: Vec<usize> = vec![face_id];
+        let mut is_visible: HashSet<usize> = HashSet::from([face_id]);
+        let mut horizon: Vec<(usize, usize)> = vec![];
+        let mut i = 0;
+        while i < visible.len(){
+            let verts = faces[visible[i]].verts;
+            for k in 0..3{
+                let (from, to) = (verts[k], verts[(k + 1) % 3]);
+                let neighbour = edge_faces[&(to, from)];
+                if is_visible.contains(&neighbour){
+                    continue;
+                }
+                if faces[neighbour].distance(&points[eye]) > epsilon{
+                    is_visible.insert(neighbour);
+                    visible.push(neighbour);
+                }else{
+                    horizon.push((from, to));
+                }
+            }
+            i += 1;
+        }
+        // a neighbour found visible after its edge was taken for the horizon
+        horizon.retain(|(from, to)| !is_visible.contains(&edge_faces[&(*to, *from)]));
+
+        let mut orphans: Vec<usize> = vec![];
+        for id in &visible{
+            faces[*id].is_alive = false;
+            orphans.append(&mut faces[*id].outside);
+        }
+
+        let first_new = faces.len();
+        for (from, to) in horizon{
+            let verts = [from, to, eye];
+            faces.push(HullFace::new(points, verts));
+            add_edges(&mut edge_faces, faces.len() - 1, verts);
+        }
+        let new_faces: Vec<usize> = (first_new..faces.len()).collect();
+        assign(&mut faces, &new_faces, &mut orphans.into_iter().filter(|point| *point != eye));
+        pending.extend(new_faces);
+    }
+
+    return Ok(faces.iter().filter(|face| face.is_alive).map(|face| face.verts).collect());
+}
+
+/// 2D convex hull by the monotone chain, counter-clockwise
+fn convex_hull_2d(points: &mut Vec<Vector2<f32>>) -> Vec<Vector2<f32>>{
+    points.sort_by(|a, b| a.x.total_cmp(&b.x).then(a.y.total_cmp(&b.y)));
+    points.dedup();
+    if points.len() < 3{
+        return points.clone();
+    }
+
+    let cross = |o: &Vector2<f32>, a: &Vector2<f32>, b: &Vector2<f32>| (a - o).perp(&(b - o));
+    let mut hull: Vec<Vector2<f32>> = vec![];
+    for pass in 0..2{
+        let start = hull.len();
+        let ordered: Box<dyn Iterator<Item = &Vector2<f32>>> = if pass == 0 {Box::new(points.iter())} else {Box::new(points.iter().rev())};
+        for point in ordered{
+            while hull.len() >= start + 2 && cross(&hull[hull.len() - 2], &hull[hull.len() - 1], point) <= 0.0{
+                hull.pop();
+            }
+            hull.push(*point);
+        }
+        hull.pop(); // the last point starts the other chain
+    }
+    return hull;
+}
+
+/// smallest area rectangle around a convex polygon by rotating calipers, as (area, edge direction, min, max)
+fn min_area_rectangle(hull: &[Vector2<f32>]) -> (f32, Vector2<f32>, Vector2<f32>, Vector2<f32>){
+    let mut best = (f32::INFINITY, Vector2::x(), Vector2::zeros(), Vector2::zeros());
+
+    // one side of the optimal rectangle is flush with a hull edge
+    for i in 0..hull.len().max(1){
+        let edge = hull[(i + 1) % hull.len()] - hull[i];
+        let Some(u) = edge.try_normalize(0.0).or(if hull.len() == 1 {Some(Vector2::x())} else {None}) else {
+            continue;
+        };
+        let v = Vector2::new(-u.y, u.x);
+
+        let (mut min, mut max) = (Vector2::repeat(f32::INFINITY), Vector2::repeat(f32::NEG_INFINITY));
+        for point in hull{
+            let local = Vector2::new(point.dot(&u), point.dot(&v));
+            min = min.inf(&local);
+            max = max.sup(&local);
+        }
+        let area = (max.x - min.x) * (max.y - min.y);
+        if area < best.0{
+            best = (area, u, min, max);
+        }
+    }
+    return best;
+}
+
+/// Welzl's minimal enclosing sphere, iterative with a deterministic shuffle
+fn minimal_sphere(points: &[Vector3<f32>]) -> BoundingSphere{
+    let mut points = points.to_vec();
+    let mut state: u32 = 0x9e3779b9;
+    for i in (1..points.len()).rev(){
+        state ^= state << 13;
+        state ^= state >> 17;
+        state ^= state << 5;
+        points.swap(i, state as usize % (i + 1));
+    }
+
+    let Some(first) = points.first() else {
+        return BoundingSphere{center: Vector3::zeros(), radius: 0.0};
+    };
+    let mut sphere = BoundingSphere{center: *first, radius: 0.0};
+
+    for i in 0..points.len(){
+        if sphere.contains(&points[i]){
+            continue;
+        }
+        sphere = BoundingSphere{center: points[i], radius: 0.0};
+        for j in 0..i{
+            if sphere.contains(&points[j]){
+                continue;
+            }
+            sphere = sphere_through(&[points[i], points[j]]);
+            for k in 0..j{
+                if sphere.contains(&points[k]){
+                    continue;
+                }
+                sphere = sphere_through(&[points[i], points[j], points[k]]);
+                for l in 0..k{
+                    if !sphere.contains(&points[l]){
+                        sphere = sphere_through(&[points[i], points[j], points[k], points[l]]);
+                    }
+                }
+            }
+        }
+    }
+    return sphere;
+}
+
+/// smallest sphere with all of 2 to 4 points on its surface, degenerate sets fall back to smaller subsets
+fn sphere_through(points: &[Vector3<f32>]) -> BoundingSphere{
+    let a = points[0];
+    let center = match points.len(){
+        2 => Some((a + points[1]) / 2.0),
+        3 => {
+            let (ab, ac) = (points[1] - a, points[2] - a);
+            let normal = ab.cross(&ac);
+            let denom = 2.0 * normal.norm_squared();
+            (denom > 0.0).then(|| a + (normal.cross(&ab) * ac.norm_squared() + ac.cross(&normal) * ab.norm_squared()) / denom)
+        },
+        _ => {
+            // equidistant from all four: 2 (p - a) · x = |p|² - |a|²
+            let rows = Matrix3::from_rows(&[
+                (points[1] - a).transpose() * 2.0,
+                (points[2] - a).transpose() * 2.0,
+                (points[3] - a).transpose() * 2.0]);
+            let rhs = Vector3::new(
+                points[1].norm_squared() - a.norm_squared(),
+                points[2].norm_squared() - a.norm_squared(),
+                points[3].norm_squared() - a.norm_squared());
+            rows.lu().solve(&rhs)
+        }
+    };
+
+    if let Some(center) = center.filter(|center| center.iter().all(|c| c.is_finite())){
+        let radius = points.iter().map(|point| (point - center).norm()).fold(0.0, f32::max);
+        return BoundingSphere{center: center, radius: radius};
+    }
+
+    // collinear or coplanar, the sphere of the farthest pair covers the rest
+    let mut best = BoundingSphere{center: a, radius: 0.0};
+    for i in 0..points.len(){
+        for j in i + 1..points.len(){
+            let candidate = sphere_through(&[points[i], points[j]]);
+            if candidate.radius > best.radius{
+                best = candidate;
+            }
+        }
+    }
+    return best;
+}
+
+impl Mesh{
+    fn unique_positions(&self) -> Vec<Vector3<f32>>{
+        let (positions, _) = self.weld_positions();
+        return positions;
+    }
+
+    pub fn convex_hull(&self) -> Result<ConvexHull, String>{
+        let positions = self.unique_positions();
+        let points: Vec<Vector3<f64>> = positions.iter().map(|pos| pos.cast::<f64>()).collect();
+        let faces = quickhull(&points)?;
+
+        // keep only the points on the hull
+        let mut new_ids: HashMap<usize, usize> = HashMap::new();
+        let mut hull_points = vec![];
+        let faces = faces.iter()
+            .map(|face| face.map(|vert| *new_ids.entry(vert).or_insert_with(|| {
+                hull_points.push(positions[vert]);
+                hull_points.len() - 1
+            })))
+            .collect();
+
+        return Ok(ConvexHull{points: hull_points, faces: faces});
+    }
+
+    /// Approximate minimum-volume box: the smallest one with a face flush with a face of the convex hull,
+    /// found by rotating calipers in each face plane, also trying the principal axes. The true minimum can
+    /// instead touch two hull edges with adjacent faces (O'Rourke), so this is a close fit but not always the smallest.
+    pub fn oriented_bounding_box(&self) -> Result<OrientedBox, String>{
+        let (points, candidate_normals) = match self.convex_hull(){
+            Ok(hull) => {
+                let normals: Vec<Vector3<f32>> = hull.faces.iter()
+                    .filter_map(|[a, b, c]| (hull.points[*b] - hull.points[*a]).cross(&(hull.points[*c] - hull.points[*a])).try_normalize(0.0))
+                    .collect();
+                (hull.points, normals)
+            },
+            Err(_) => (self.unique_positions(), vec![]) // flat meshes have no hull, their box is flat too
+        };
+        if points.is_empty(){
+            return Err("Mesh has no vertices".to_string());
+        }
+
+        let centroid = points.iter().sum::<Vector3<f32>>() / points.len() as f32;
+        let covariance = points.iter().map(|point| (point - centroid) * (point - centroid).transpose()).sum::<Matrix3<f32>>();
+        let principal = SymmetricEigen::new(covariance).eigenvectors;
+
+        let mut best: Option<OrientedBox> = None;
+        let mut tried: Vec<Vector3<f32>> = vec![];
+        for normal in principal.column_iter().map(|column| column.into_owned()).chain(candidate_normals){
+            if tried.iter().any(|other| other.dot(&normal).abs() > 0.99999){
+                continue; // parallel faces give the same box
+            }
+            tried.push(normal);
+
+            let u = normal.cross(&if normal.x.abs() < 0.9 {Vector3::x()} else {Vector3::y()}).normalize();
+            let v = normal.cross(&u);
+            let mut projected: Vec<Vector2<f32>> = points.iter().map(|point| Vector2::new(point.dot(&u), point.dot(&v))).collect();
+            let (_, direction, min, max) = min_area_rectangle(&convex_hull_2d(&mut projected));
+
+            let axis_x = u * direction.x + v * direction.y;
+            let axis_y = normal.cross(&axis_x);
+            let (min_z, max_z) = points.iter().fold((f32::INFINITY, f32::NEG_INFINITY), |(lo, hi), point| {
+                let z = point.dot(&normal);
+                (lo.min(z), hi.max(z))
+            });
+
+            let candidate = OrientedBox{
+                center: axis_x * (min.x + max.x) / 2.0 + axis_y * (min.y + max.y) / 2.0 + normal * (min_z + max_z) / 2.0,
+                axes: Matrix3::from_columns(&[axis_x, axis_y, normal]),
+                half_extents: Vector3::new(max.x - min.x, max.y - min.y, max_z - min_z) / 2.0
+            };
+            if best.is_none_or(|best| candidate.volume() < best.volume()){
+                best = Some(candidate);
+            }
+        }
+
+        return best.ok_or("No box orientation found".to_string());
+    }
+
+    pub fn bounding_sphere(&self) -> BoundingSphere{
+        // only hull points can touch the sphere
+        let points = match self.convex_hull(){
+            Ok(hull) => hull.points,
+            Err(_) => self.unique_positions()
+        };
+        return minimal_sphere(&points);
+    }
+}
+
+/// outline segments of an oriented box, as positions and line indices
+pub fn create_box_line_buffers(obb: &OrientedBox) -> (Vec<f32>, Vec<usize>){
+    let verts = obb.corners().iter().flat_map(|corner| corner.iter().cloned().collect::<Vec<f32>>()).collect();
+    let indices = vec![
+        0,1, 1,5, 5,4, 4,0,
+        2,3, 3,7, 7,6, 6,2,
+        0,2, 1,3, 5,7, 4,6
+    ];
+    return (verts, indices);
+}
+
+/// three great circles of the sphere
+pub fn create_sphere_line_buffers(sphere: &BoundingSphere, segments: usize) -> (Vec<f32>, Vec<usize>){
+    let mut verts = vec![];
+    let mut indices = vec![];
+
+    for (u, v) in [(Vector3::x(), Vector3::y()), (Vector3::y(), Vector3::z()), (Vector3::z(), Vector3::x())]{
+        let first = verts.len() / 3;
+        for i in 0..segments{
+            let angle = i as f32 / segments as f32 * std::f32::consts::TAU;
+            let point = sphere.center + (u * angle.cos() + v * angle.sin()) * sphere.radius;
+            verts.extend_from_slice(point.as_slice());
+            indices.push(first + i);
+            indices.push(first + (i + 1) % segments);
+        }
+    }
+    return (verts, indices);
+}
+
+/// unique edges of the hull
+pub fn create_hull_line_buffers(hull: &ConvexHull) -> (Vec<f32>, Vec<usize>){
+    let verts = hull.points.iter().flat_map(|point| point.iter().cloned().collect::<Vec<f32>>()).collect();
+    let mut edges: HashSet<(usize, usize)> = HashSet::new();
+    let mut indices = vec![];
+    for face in &hull.faces{
+        for i in 0..3{
+            let (a, b) = (face[i], face[(i + 1) % 3]);
+            if edges.insert((a.min(b), a.max(b))){
+                indices.push(a);
+                indices.push(b);
+            }
+        }
+    }
+    return (verts, indices);
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+    use nalgebra::{Matrix4, Rotation3};
+    use crate::mesh::tests::UNIT_CUBE;
+
+    // the teapot's hull takes long to fit boxes to in debug builds
+    const ASSETS: [&str; 2] = [
+        include_str!("../../assets/sphere.obj"),
+        include_str!("../../assets/gear.obj")
+    ];
+
+    /// every vertex within `tolerance` of the box
+    fn box_contains(obb: &OrientedBox, point: &Vector3<f32>, tolerance: f32) -> bool{
+        let local = obb.axes.transpose() * (point - obb.center);
+        return (local.abs() - obb.half_extents).max() <= tolerance;
+    }
+
+    #[test]
+    fn volumes_contain_every_vertex(){
+        for obj in ASSETS{
+            let mesh = Mesh::load_obj(obj).unwrap();
+            let tolerance = mesh.bounding_radius() * 1e-5;
+
+            let hull = mesh.convex_hull().unwrap();
+            let obb = mesh.oriented_bounding_box().unwrap();
+            let sphere = mesh.bounding_sphere();
+            let planes: Vec<(Vector3<f32>, f32)> = hull.faces.iter().map(|[a, b, c]| {
+                let (a, b, c) = (hull.points[*a], hull.points[*b], hull.points[*c]);
+                let normal = (b - a).cross(&(c - a)).normalize();
+                (normal, normal.dot(&a))
+            }).collect();
+            for vert in &mesh.verts{
+                assert!(planes.iter().all(|(normal, offset)| normal.dot(&vert.pos) - offset <= tolerance));
+                assert!(box_contains(&obb, &vert.pos, tolerance));
+                assert!((vert.pos - sphere.center).norm() <= sphere.radius + tolerance);
+            }
+
+            // each is at least as tight as the axis aligned box or its enclosing sphere
+            let extent = mesh.bb_max - mesh.bb_min;
+            assert!(obb.volume() <= extent.x * extent.y * extent.z * (1.0 + 1e-4));
+            assert!(sphere.radius <= mesh.bounding_radius() * (1.0 + 1e-4));
+            assert!(hull.volume() <= obb.volume() * (1.0 + 1e-4));
+        }
+    }
+
+    #[test]
+    fn rotated_cube_is_fit_exactly(){
+        let mut cube = Mesh::load_obj(UNIT_CUBE).unwrap();
+        let rotation = Rotation3::from_euler_angles(0.3, -0.7, 1.1);
+        cube.transform(&rotation.to_homogeneous().prepend_nonuniform_scaling(&Vector3::new(1.0, 2.0, 3.0))).unwrap();
+        cube.transform(&Matrix4::new_translation(&Vector3::new(1.0, 2.0, 3.0))).unwrap();
+
+        let hull = cube.convex_hull().unwrap();
+        assert_eq!(hull.points.len(), 8);
+        assert_eq!(hull.faces.len(), 12);
+        assert!((hull.volume() - 6.0).abs() < 1e-4);
+
+        let obb = cube.oriented_bounding_box().unwrap();
+        assert!((obb.volume() - 6.0).abs() < 1e-3, "volume {}", obb.volume());
+
+        // the sphere through opposite corners
+        let sphere = cube.bounding_sphere();
+        assert!((sphere.radius - 14f32.sqrt() / 2.0).abs() < 1e-4, "radius {}", sphere.radius);
+    }
+
+    #[test]
+    fn sphere_of_a_few_points(){
+        let sphere = minimal_sphere(&[Vector3::new(-1.0, 0.0, 0.0), Vector3::new(1.0, 0.0, 0.0), Vector3::new(0.0, 0.5, 0.0)]);
+        assert!(sphere.center.norm() < 1e-6 && (sphere.radius - 1.0).abs() < 1e-6);
+
+        let single = minimal_sphere(&[Vector3::new(1.0, 2.0, 3.0)]);
+        assert_eq!((single.center, single.radius), (Vector3::new(1.0, 2.0, 3.0), 0.0));
+    }
+}
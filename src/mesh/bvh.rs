@@ -0,0 +1,713 @@
+use nalgebra::{Matrix4, Point3, Vector3};
+
+use super::Mesh;
+
+/// leaves don't get split below this many triangles
+const MAX_LEAF_SIZE: usize = 4;
+/// centroid bins evaluated per axis when looking for the cheapest split
+const SAH_BIN_COUNT: usize = 12;
+/// cost of visiting a node relative to testing one triangle
+const TRAVERSAL_COST: f32 = 1.0;
+
+/// point on the mesh surface found by a query
+#[derive(Clone, Copy, Debug)]
+pub struct SurfacePoint{
+    pub face: usize,
+    pub position: Vector3<f32>,
+    pub barycentric: Vector3<f32>, // weights of the face's first, second and third vertex
+    pub normal: Vector3<f32>, // geometric normal of the face
+    pub distance: f32 // from the query origin
+}
+
+struct BvhTriangle{
+    face: usize,
+    verts: [usize; 3],
+    positions: [Vector3<f32>; 3]
+}
+
+impl BvhTriangle{
+    fn centroid(&self) -> Vector3<f32>{
+        return (self.positions[0] + self.positions[1] + self.positions[2]) / 3.0;
+    }
+
+    fn normal(&self) -> Vector3<f32>{
+        return triangle_normal(&self.positions);
+    }
+
+    /// corners moved by `transform`, as they are without one
+    fn transformed_positions(&self, transform: Option<&Matrix4<f32>>) -> [Vector3<f32>; 3]{
+        return match transform{
+            Some(transform) => self.positions.map(|pos| transform.transform_point(&Point3::from(pos)).coords),
+            None => self.positions
+        };
+    }
+}
+
+fn triangle_normal(positions: &[Vector3<f32>; 3]) -> Vector3<f32>{
+    let [a, b, c] = positions;
+    return (b - a).cross(&(c - a)).try_normalize(0.0).unwrap_or(Vector3::zeros());
+}
+
+#[derive(Clone, Copy)]
+struct Aabb{
+    min: Vector3<f32>,
+    max: Vector3<f32>
+}
+
+impl Aabb{
+    fn empty() -> Aabb{
+        return Aabb{min: Vector3::repeat(f32::INFINITY), max: Vector3::repeat(f32::NEG_INFINITY)};
+    }
+
+    /// true until something is grown into it
+    fn is_empty(&self) -> bool{
+        return self.min.x > self.max.x;
+    }
+
+    fn grow(&mut self, point: &Vector3<f32>){
+        self.min = self.min.inf(point);
+        self.max = self.max.sup(point);
+    }
+
+    fn merge(&mut self, other: &Aabb){
+        self.min = self.min.inf(&other.min);
+        self.max = self.max.sup(&other.max);
+    }
+
+    fn surface_area(&self) -> f32{
+        let extent = self.max - self.min;
+        if extent.x < 0.0{
+            return 0.0;
+        }
+        return 2.0 * (extent.x * extent.y + extent.y * extent.z + extent.z * extent.x);
+    }
+
+    fn overlaps(&self, other: &Aabb) -> bool{
+        return self.min.x <= other.max.x && self.max.x >= other.min.x
+            && self.min.y <= other.max.y && self.max.y >= other.min.y
+            && self.min.z <= other.max.z && self.max.z >= other.min.z;
+    }
+
+    /// box around the corners moved by `transform`, as it is without one
+    fn transformed(&self, transform: Option<&Matrix4<f32>>) -> Aabb{
+        let Some(transform) = transform else {
+            return *self;
+        };
+        let mut moved = Aabb::empty();
+        if self.is_empty(){
+            return moved;
+        }
+        for corner in 0..8{
+            let pick = |axis: usize| if corner & (1 << axis) == 0 {self.min[axis]} else {self.max[axis]};
+            moved.grow(&transform.transform_point(&Point3::new(pick(0), pick(1), pick(2))).coords);
+        }
+        return moved;
+    }
+
+    fn distance_squared(&self, point: &Vector3<f32>) -> f32{
+        let outside = (self.min - point).sup(&Vector3::zeros()).sup(&(point - self.max));
+        return outside.norm_squared();
+    }
+
+    /// entry distance of the ray into the box (slab test), None on a miss
+    fn ray_entry(&self, origin: &Vector3<f32>, inv_direction: &Vector3<f32>, max_distance: f32) -> Option<f32>{
+        if self.is_empty(){
+            return None;
+        }
+        let t0 = (self.min - origin).component_mul(inv_direction);
+        let t1 = (self.max - origin).component_mul(inv_direction);
+        let t_near = t0.inf(&t1).max().max(0.0);
+        let t_far = t0.sup(&t1).min().min(max_distance);
+        return if t_near <= t_far {Some(t_near)} else {None};
+    }
+}
+
+struct BvhNode{
+    bounds: Aabb,
+    first: usize, // first triangle for leaves, left child for inner nodes (right is left + 1)
+    count: usize // triangles in a leaf, 0 for inner nodes
+}
+
+/// Bounding volume hierarchy over the visible triangles of a mesh, built with the surface area heuristic.
+pub struct Bvh{
+    nodes: Vec<BvhNode>, // root first, none without visible triangles
+    triangles: Vec<BvhTriangle> // ordered so every leaf covers a contiguous range
+}
+
+impl Bvh{
+    pub fn build(mesh: &Mesh) -> Bvh{
+        let triangles: Vec<BvhTriangle> = mesh.faces.iter().enumerate()
+            .filter(|(_, face)| !face.hidden)
+            .map(|(face_id, face)| {
+                let verts = [face.verts[0], face.verts[1], face.verts[2]];
+                BvhTriangle{face: face_id, verts: verts, positions: verts.map(|v| mesh.verts[v].pos)}
+            })
+            .filter(|tri| tri.normal() != Vector3::zeros()) // degenerate triangles can't be hit
+            .collect();
+
+        let mut bvh = Bvh{nodes: vec![], triangles: triangles};
+        if bvh.triangles.is_empty(){
+            return bvh;
+        }
+        let triangle_count = bvh.triangles.len();
+        bvh.nodes.push(BvhNode{bounds: Aabb::empty(), first: 0, count: triangle_count});
+        bvh.update_bounds(0);
+        bvh.subdivide(0);
+        return bvh;
+    }
+
+    /// Follows changed positions by recomputing the node bounds, keeping the tree.
+    /// Falls back to a rebuild when the visible triangles differ.
+    pub fn update(&mut self, mesh: &Mesh){
+        let is_same_topology = self.triangles.iter().all(|tri| {
+            mesh.faces.get(tri.face).is_some_and(|face| !face.hidden && face.verts[..] == tri.verts[..])
+        }) && self.triangles.len() == mesh.faces.iter().filter(|face| !face.hidden).count();
+
+        if !is_same_topology{
+            *self = Bvh::build(mesh);
+            return;
+        }
+
+        for tri in &mut self.triangles{
+            tri.positions = tri.verts.map(|v| mesh.verts[v].pos);
+        }
+        // children always come after their parent
+        for node_id in (0..self.nodes.len()).rev(){
+            if self.nodes[node_id].count > 0{
+                self.update_bounds(node_id);
+            }else{
+                let left = self.nodes[node_id].first;
+                let mut bounds = self.nodes[left].bounds;
+                bounds.merge(&self.nodes[left + 1].bounds);
+                self.nodes[node_id].bounds = bounds;
+            }
+        }
+    }
+
+    fn update_bounds(&mut self, node_id: usize){
+        let node = &self.nodes[node_id];
+        let mut bounds = Aabb::empty();
+        for tri in &self.triangles[node.first..node.first + node.count]{
+            for pos in &tri.positions{
+                bounds.grow(pos);
+            }
+        }
+        self.nodes[node_id].bounds = bounds;
+    }
+
+    /// cheapest binned SAH split of a node as (axis, position, cost)
+    fn find_split(&self, node: &BvhNode) -> Option<(usize, f32, f32)>{
+        let tris = &self.triangles[node.first..node.first + node.count];
+
+        let mut centroid_bounds = Aabb::empty();
+        for tri in tris{
+            centroid_bounds.grow(&tri.centroid());
+        }
+
+        let mut best: Option<(usize, f32, f32)> = None;
+        for axis in 0..3{
+            let (lo, hi) = (centroid_bounds.min[axis], centroid_bounds.max[axis]);
+            if hi <= lo{
+                continue;
+            }
+            let scale = SAH_BIN_COUNT as f32 / (hi - lo);
+
+            let mut bins = [(Aabb::empty(), 0usize); SAH_BIN_COUNT];
+            for tri in tris{
+                let bin = (((tri.centroid()[axis] - lo) * scale) as usize).min(SAH_BIN_COUNT - 1);
+                bins[bin].1 += 1;
+                for pos in &tri.positions{
+                    bins[bin].0.grow(pos);
+                }
+            }
+
+            // sweep from both sides, then evaluate every plane between bins
+            let mut left_costs = [0.0; SAH_BIN_COUNT - 1];
+            let (mut left_bounds, mut left_count) = (Aabb::empty(), 0);
+            for i in 0..SAH_BIN_COUNT - 1{
+                left_bounds.merge(&bins[i].0);
+                left_count += bins[i].1;
+                left_costs[i] = left_bounds.surface_area() * left_count as f32;
+            }
+            let (mut right_bounds, mut right_count) = (Aabb::empty(), 0);
+            for i in (1..SAH_BIN_COUNT).rev(){
+                right_bounds.merge(&bins[i].0);
+                right_count += bins[i].1;
+                let cost = left_costs[i - 1] + right_bounds.surface_area() * right_count as f32;
+                if best.is_none_or(|(_, _, best_cost)| cost < best_cost){
+                    best = Some((axis, lo + i as f32 / scale, cost));
+                }
+            }
+        }
+
+        return best;
+    }
+
+    fn subdivide(&mut self, node_id: usize){
+        let (first, count, area) = {
+            let node = &self.nodes[node_id];
+            (node.first, node.count, node.bounds.surface_area())
+        };
+        if count <= MAX_LEAF_SIZE{
+            return;
+        }
+
+        let Some((axis, split, cost)) = self.find_split(&self.nodes[node_id]) else {
+            return; // all centroids in one spot
+        };
+        if cost / area.max(f32::EPSILON) + TRAVERSAL_COST >= count as f32{
+            return; // splitting costs more than testing all triangles
+        }
+
+        // partition the triangle range around the split plane
+        let (mut i, mut j) = (first, first + count);
+        while i < j{
+            if self.triangles[i].centroid()[axis] < split{
+                i += 1;
+            }else{
+                j -= 1;
+                self.triangles.swap(i, j);
+            }
+        }
+        let left_count = i - first;
+        if left_count == 0 || left_count == count{
+            return;
+        }
+
+        let left = self.nodes.len();
+        self.nodes.push(BvhNode{bounds: Aabb::empty(), first: first, count: left_count});
+        self.nodes.push(BvhNode{bounds: Aabb::empty(), first: i, count: count - left_count});
+        self.nodes[node_id].first = left;
+        self.nodes[node_id].count = 0;
+
+        self.update_bounds(left);
+        self.update_bounds(left + 1);
+        self.subdivide(left);
+        self.subdivide(left + 1);
+    }
+
+    /// id of the root node, none for an empty hierarchy
+    fn root(&self) -> Option<usize>{
+        return if self.nodes.is_empty() {None} else {Some(0)};
+    }
+
+    /// nearest hit along the ray within `max_distance`, `direction` doesn't need to be normalized
+    pub fn intersect_ray(&self, origin: &Vector3<f32>, direction: &Vector3<f32>, max_distance: f32) -> Option<SurfacePoint>{
+        let direction = direction.try_normalize(0.0)?;
+        let inv_direction = direction.map(|d| 1.0 / d);
+
+        let mut best: Option<SurfacePoint> = None;
+        let mut closest = max_distance;
+        let mut stack: Vec<usize> = self.root().into_iter().collect();
+
+        while let Some(node_id) = stack.pop(){
+            let node = &self.nodes[node_id];
+            if node.bounds.ray_entry(origin, &inv_direction, closest).is_none(){
+                continue;
+            }
+
+            if node.count > 0{
+                for tri in &self.triangles[node.first..node.first + node.count]{
+                    if let Some((distance, u, v)) = intersect_triangle(origin, &direction, &tri.positions)
+                        && distance < closest{
+                        closest = distance;
+                        best = Some(SurfacePoint{
+                            face: tri.face,
+                            position: origin + direction * distance,
+                            barycentric: Vector3::new(1.0 - u - v, u, v),
+                            normal: tri.normal(),
+                            distance: distance
+                        });
+                    }
+                }
+            }else{
+                // visit the nearer child first, so the farther one is culled more often
+                let (left, right) = (node.first, node.first + 1);
+                let left_entry = self.nodes[left].bounds.ray_entry(origin, &inv_direction, closest);
+                let right_entry = self.nodes[right].bounds.ray_entry(origin, &inv_direction, closest);
+                match (left_entry, right_entry){
+                    (Some(l), Some(r)) => {
+                        if l < r {stack.extend([right, left]);} else {stack.extend([left, right]);}
+                    },
+                    (Some(_), None) => stack.push(left),
+                    (None, Some(_)) => stack.push(right),
+                    (None, None) => {}
+                }
+            }
+        }
+
+        return best;
+    }
+
+    /// closest point on the surface within `max_distance` of `point`
+    pub fn closest_point(&self, point: &Vector3<f32>, max_distance: f32) -> Option<SurfacePoint>{
+        return self.closest_point_with(point, max_distance, None);
+    }
+
+    /// `closest_point` on the surface moved by `transform`, the point given and found in the moved space
+    pub fn closest_point_transformed(&self, point: &Vector3<f32>, max_distance: f32, transform: &Matrix4<f32>) -> Option<SurfacePoint>{
+        return self.closest_point_with(point, max_distance, Some(transform));
+    }
+
+    fn closest_point_with(&self, point: &Vector3<f32>, max_distance: f32, transform: Option<&Matrix4<f32>>) -> Option<SurfacePoint>{
+        let mut best: Option<SurfacePoint> = None;
+        let mut closest_squared = max_distance * max_distance;
+        let mut stack: Vec<usize> = self.root().into_iter().collect();
+
+        while let Some(node_id) = stack.pop(){
+            let node = &self.nodes[node_id];
+            let bounds = node.bounds.transformed(transform);
+            if bounds.is_empty() || bounds.distance_squared(point) > closest_squared{
+                continue;
+            }
+
+            if node.count > 0{
+                for tri in &self.triangles[node.first..node.first + node.count]{
+                    let positions = tri.transformed_positions(transform);
+                    let (position, barycentric) = closest_point_on_triangle(point, &positions);
+                    let distance_squared = (position - point).norm_squared();
+                    if distance_squared <= closest_squared{
+                        closest_squared = distance_squared;
+                        best = Some(SurfacePoint{
+                            face: tri.face,
+                            position: position,
+                            barycentric: barycentric,
+                            normal: triangle_normal(&positions),
+                            distance: distance_squared.sqrt()
+                        });
+                    }
+                }
+            }else{
+                let (left, right) = (node.first, node.first + 1);
+                let left_distance = self.nodes[left].bounds.transformed(transform).distance_squared(point);
+                let right_distance = self.nodes[right].bounds.transformed(transform).distance_squared(point);
+                if left_distance < right_distance {stack.extend([right, left]);} else {stack.extend([left, right]);}
+            }
+        }
+
+        return best;
+    }
+
+    /// faces with any part inside the box
+    pub fn faces_in_box(&self, bb_min: &Vector3<f32>, bb_max: &Vector3<f32>) -> Vec<usize>{
+        return self.faces_in_box_with(bb_min, bb_max, None);
+    }
+
+    /// `faces_in_box` of the surface moved by `transform`, the box given in the moved space
+    pub fn faces_in_transformed_box(&self, bb_min: &Vector3<f32>, bb_max: &Vector3<f32>, transform: &Matrix4<f32>) -> Vec<usize>{
+        return self.faces_in_box_with(bb_min, bb_max, Some(transform));
+    }
+
+    fn faces_in_box_with(&self, bb_min: &Vector3<f32>, bb_max: &Vector3<f32>, transform: Option<&Matrix4<f32>>) -> Vec<usize>{
+        let query = Aabb{min: *bb_min, max: *bb_max};
+        let mut faces = vec![];
+        let mut stack: Vec<usize> = self.root().into_iter().collect();
+
+        while let Some(node_id) = stack.pop(){
+            let node = &self.nodes[node_id];
+            if !node.bounds.transformed(transform).overlaps(&query){
+                continue;
+            }
+
+            if node.count > 0{
+                for tri in &self.triangles[node.first..node.first + node.count]{
+                    if triangle_overlaps_box(&tri.transformed_positions(transform), &query){
+                        faces.push(tri.face);
+                    }
+                }
+            }else{
+                stack.extend([node.first, node.first + 1]);
+            }
+        }
+
+        faces.sort_unstable();
+        return faces;
+    }
+}
+
+/// Möller–Trumbore, returns the distance and the barycentric u, v of the hit
+fn intersect_triangle(origin: &Vector3<f32>, direction: &Vector3<f32>, positions: &[Vector3<f32>; 3]) -> Option<(f32, f32, f32)>{
+    let [a, b, c] = positions;
+    let (edge1, edge2) = (b - a, c - a);
+
+    let p = direction.cross(&edge2);
+    let det = edge1.dot(&p);
+    if det.abs() < 1e-12{
+        return None; // parallel
+    }
+    let inv_det = 1.0 / det;
+
+    let s = origin - a;
+    let u = s.dot(&p) * inv_det;
+    if !(0.0..=1.0).contains(&u){
+        return None;
+    }
+
+    let q = s.cross(&edge1);
+    let v = direction.dot(&q) * inv_det;
+    if v < 0.0 || u + v > 1.0{
+        return None;
+    }
+
+    let distance = edge2.dot(&q) * inv_det;
+    return if distance >= 0.0 {Some((distance, u, v))} else {None};
+}
+
+/// closest point on a triangle and its barycentric coordinates, by the voronoi region of the point
+fn closest_point_on_triangle(point: &Vector3<f32>, positions: &[Vector3<f32>; 3]) -> (Vector3<f32>, Vector3<f32>){
+    let [a, b, c] = *positions;
+    let (ab, ac, ap) = (b - a, c - a, point - a);
+
+    let (d1, d2) = (ab.dot(&ap), ac.dot(&ap));
+    if d1 <= 0.0 && d2 <= 0.0{
+        return (a, Vector3::new(1.0, 0.0, 0.0));
+    }
+
+    let bp = point - b;
+    let (d3, d4) = (ab.dot(&bp), ac.dot(&bp));
+    if d3 >= 0.0 && d4 <= d3{
+        return (b, Vector3::new(0.0, 1.0, 0.0));
+    }
+
+    let vc = d1 * d4 - d3 * d2;
+    if vc <= 0.0 && d1 >= 0.0 && d3 <= 0.0{
+        let v = d1 / (d1 - d3);
+        return (a + ab * v, Vector3::new(1.0 - v, v, 0.0));
+    }
+
+    let cp = point - c;
+    let (d5, d6) = (ab.dot(&cp), ac.dot(&cp));
+    if d6 >= 0.0 && d5 <= d6{
+        return (c, Vector3::new(0.0, 0.0, 1.0));
+    }
+
+    let vb = d5 * d2 - d1 * d6;
+    if vb <= 0.0 && d2 >= 0.0 && d6 <= 0.0{
+        let w = d2 / (d2 - d6);
+        return (a + ac * w, Vector3::new(1.0 - w, 0.0, w));
+    }
+
+    let va = d3 * d6 - d5 * d4;
+    if va <= 0.0 && (d4 - d3) >= 0.0 && (d5 - d6) >= 0.0{
+        let w = (d4 - d3) / ((d4 - d3) + (d5 - d6));
+        return (b + (c - b) * w, Vector3::new(0.0, 1.0 - w, w));
+    }
+
+    let denom = 1.0 / (va + vb + vc);
+    let (v, w) = (vb * denom, vc * denom);
+    return (a + ab * v + ac * w, Vector3::new(1.0 - v - w, v, w));
+}
+
+/// separating axis test between a triangle and a box
+fn triangle_overlaps_box(positions: &[Vector3<f32>; 3], aabb: &Aabb) -> bool{
+    let center = (aabb.min + aabb.max) / 2.0;
+    let half = (aabb.max - aabb.min) / 2.0;
+    let verts = positions.map(|pos| pos - center);
+    let edges = [verts[1] - verts[0], verts[2] - verts[1], verts[0] - verts[2]];
+
+    let separated_on = |axis: Vector3<f32>| -> bool {
+        let projections = verts.map(|v| v.dot(&axis));
+        let radius = half.x * axis.x.abs() + half.y * axis.y.abs() + half.z * axis.z.abs();
+        let (lo, hi) = (projections.iter().cloned().fold(f32::INFINITY, f32::min),
+            projections.iter().cloned().fold(f32::NEG_INFINITY, f32::max));
+        return lo > radius || hi < -radius;
+    };
+
+    // box face normals, triangle normal, then edge cross products
+    for axis in [Vector3::x(), Vector3::y(), Vector3::z(), edges[0].cross(&edges[1])]{
+        if separated_on(axis){
+            return false;
+        }
+    }
+    for edge in &edges{
+        for box_axis in [Vector3::x(), Vector3::y(), Vector3::z()]{
+            let axis = edge.cross(&box_axis);
+            if axis.norm_squared() > 0.0 && separated_on(axis){
+                return false;
+            }
+        }
+    }
+    return true;
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+
+    const ASSETS: [&str; 3] = [
+        include_str!("../../assets/teapot.obj"),
+        include_str!("../../assets/sphere.obj"),
+        include_str!("../../assets/gear.obj")
+    ];
+
+    /// deterministic points in [-range, range]^3
+    fn sample_points(count: usize, range: f32) -> Vec<Vector3<f32>>{
+        let mut state: u32 = 12345;
+        let mut next = || {
+            state = state.wrapping_mul(1103515245).wrapping_add(12345);
+            ((state >> 8) as f32 / (1u32 << 24) as f32 * 2.0 - 1.0) * range
+        };
+        return (0..count).map(|_| Vector3::new(next(), next(), next())).collect();
+    }
+
+    fn load(obj: &str) -> Mesh{
+        return Mesh::load_obj(obj).unwrap();
+    }
+
+    fn brute_force_ray(bvh: &Bvh, origin: &Vector3<f32>, direction: &Vector3<f32>) -> Option<f32>{
+        let direction = direction.normalize();
+        return bvh.triangles.iter()
+            .filter_map(|tri| intersect_triangle(origin, &direction, &tri.positions).map(|hit| hit.0))
+            .min_by(|a, b| a.total_cmp(b));
+    }
+
+    #[test]
+    fn ray_hits_match_brute_force(){
+        for obj in ASSETS{
+            let mesh = load(obj);
+            let bvh = Bvh::build(&mesh);
+            let radius = mesh.bounding_radius();
+
+            for (origin, target) in sample_points(200, radius * 2.0).iter().zip(sample_points(200, radius * 0.5).iter().rev()){
+                let direction = target - origin;
+                let hit = bvh.intersect_ray(origin, &direction, f32::INFINITY).map(|hit| hit.distance);
+                let expected = brute_force_ray(&bvh, origin, &direction);
+                match (hit, expected){
+                    (Some(hit), Some(expected)) => assert!((hit - expected).abs() < 1e-4),
+                    (None, None) => {},
+                    _ => panic!("bvh hit {:?}, brute force {:?}", hit, expected)
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn ray_hit_reports_surface_point(){
+        let mesh = load(ASSETS[1]); // unit sphere
+        let bvh = Bvh::build(&mesh);
+
+        let hit = bvh.intersect_ray(&Vector3::new(0.0, 0.0, 5.0), &Vector3::new(0.0, 0.0, -1.0), f32::INFINITY).unwrap();
+        assert!((hit.distance - 4.0).abs() < 0.02);
+        assert!((hit.position.z - 1.0).abs() < 0.02);
+        assert!(hit.normal.z > 0.9);
+        assert!((hit.barycentric.sum() - 1.0).abs() < 1e-5);
+        assert!(hit.barycentric.iter().all(|w| *w >= 0.0));
+
+        assert!(bvh.intersect_ray(&Vector3::new(0.0, 0.0, 5.0), &Vector3::new(0.0, 0.0, 1.0), f32::INFINITY).is_none());
+        assert!(bvh.intersect_ray(&Vector3::new(0.0, 0.0, 5.0), &Vector3::new(0.0, 0.0, -1.0), 3.0).is_none());
+    }
+
+    #[test]
+    fn closest_points_match_brute_force(){
+        for obj in ASSETS{
+            let mesh = load(obj);
+            let bvh = Bvh::build(&mesh);
+
+            for point in sample_points(100, mesh.bounding_radius() * 1.5){
+                let found = bvh.closest_point(&point, f32::INFINITY).unwrap();
+                let expected = bvh.triangles.iter()
+                    .map(|tri| (closest_point_on_triangle(&point, &tri.positions).0 - point).norm())
+                    .fold(f32::INFINITY, f32::min);
+                assert!((found.distance - expected).abs() < 1e-4);
+                assert!((found.position - point).norm() - found.distance < 1e-4);
+            }
+        }
+    }
+
+    #[test]
+    fn box_queries_match_brute_force(){
+        for obj in ASSETS{
+            let mesh = load(obj);
+            let bvh = Bvh::build(&mesh);
+            let radius = mesh.bounding_radius();
+
+            for (center, extent) in sample_points(50, radius).iter().zip(sample_points(50, radius * 0.3)){
+                let (bb_min, bb_max) = (center - extent.abs(), center + extent.abs());
+                let query = Aabb{min: bb_min, max: bb_max};
+
+                let mut expected: Vec<usize> = bvh.triangles.iter()
+                    .filter(|tri| triangle_overlaps_box(&tri.positions, &query))
+                    .map(|tri| tri.face)
+                    .collect();
+                expected.sort_unstable();
+                assert_eq!(bvh.faces_in_box(&bb_min, &bb_max), expected);
+            }
+        }
+    }
+
+    #[test]
+    fn transformed_queries_match_a_moved_mesh(){
+        // sheared and scaled unevenly, closest points don't follow the transform
+        let transform = Matrix4::new(1.5, 0.4, 0.0, 2.0, 0.0, 0.5, 0.0, -1.0, 0.3, 0.0, 2.0, 0.5, 0.0, 0.0, 0.0, 1.0);
+        let mesh = load(ASSETS[0]);
+        let mut moved = load(ASSETS[0]);
+        moved.transform(&transform).unwrap();
+        let (bvh, moved_bvh) = (Bvh::build(&mesh), Bvh::build(&moved));
+        let radius = moved.bounding_radius();
+
+        for point in sample_points(50, radius * 1.5){
+            let found = bvh.closest_point_transformed(&point, f32::INFINITY, &transform).unwrap();
+            let expected = moved_bvh.closest_point(&point, f32::INFINITY).unwrap();
+            assert!((found.distance - expected.distance).abs() < 1e-3 * radius, "{} instead of {}", found.distance, expected.distance);
+            assert!((found.position - point).norm() - found.distance < 1e-3 * radius);
+        }
+
+        for (center, extent) in sample_points(30, radius).iter().zip(sample_points(30, radius * 0.3)){
+            let (bb_min, bb_max) = (center - extent.abs(), center + extent.abs());
+            assert_eq!(bvh.faces_in_transformed_box(&bb_min, &bb_max, &transform), moved_bvh.faces_in_box(&bb_min, &bb_max));
+        }
+    }
+
+    #[test]
+    fn box_overlap_needs_actual_contact(){
+        let tri = [Vector3::new(0.0, 0.0, 0.0), Vector3::new(1.0, 0.0, 0.0), Vector3::new(0.0, 1.0, 0.0)];
+        let inside = Aabb{min: Vector3::new(0.1, 0.1, -0.1), max: Vector3::new(0.2, 0.2, 0.1)};
+        let beyond_hypotenuse = Aabb{min: Vector3::new(0.8, 0.8, -0.1), max: Vector3::new(0.9, 0.9, 0.1)};
+        assert!(triangle_overlaps_box(&tri, &inside));
+        assert!(!triangle_overlaps_box(&tri, &beyond_hypotenuse));
+    }
+
+    #[test]
+    fn refit_follows_moved_vertices(){
+        let mut mesh = load(ASSETS[0]);
+        let mut bvh = Bvh::build(&mesh);
+        let node_count = bvh.nodes.len();
+
+        for vert in &mut mesh.verts{
+            vert.pos += Vector3::new(10.0, 0.0, 0.0);
+        }
+        bvh.update(&mesh);
+        assert_eq!(bvh.nodes.len(), node_count, "same topology should refit, not rebuild");
+
+        let hit = bvh.intersect_ray(&Vector3::new(10.0, 0.0, 20.0), &Vector3::new(0.0, 0.0, -1.0), f32::INFINITY);
+        let rebuilt = Bvh::build(&mesh).intersect_ray(&Vector3::new(10.0, 0.0, 20.0), &Vector3::new(0.0, 0.0, -1.0), f32::INFINITY);
+        assert!((hit.unwrap().distance - rebuilt.unwrap().distance).abs() < 1e-5);
+    }
+
+    #[test]
+    fn hidden_faces_trigger_rebuild(){
+        let mut mesh = load(ASSETS[0]);
+        let mut bvh = Bvh::build(&mesh);
+        let triangle_count = bvh.triangles.len();
+
+        mesh.isolate_components(&[0]).unwrap();
+        bvh.update(&mesh);
+        assert!(bvh.triangles.len() < triangle_count);
+        assert_eq!(bvh.triangles.len(), mesh.faces.iter().filter(|face| !face.hidden).count());
+    }
+
+    #[test]
+    fn hidden_mesh_has_nothing_to_hit(){
+        let mut mesh = load(ASSETS[1]);
+        for face in &mut mesh.faces{
+            face.hidden = true;
+        }
+        let mut bvh = Bvh::build(&mesh);
+        bvh.update(&mesh);
+
+        assert!(bvh.intersect_ray(&Vector3::new(0.0, 0.0, 5.0), &Vector3::new(0.0, 0.0, -1.0), f32::INFINITY).is_none());
+        assert!(bvh.closest_point(&Vector3::zeros(), f32::INFINITY).is_none());
+        assert!(bvh.faces_in_box(&Vector3::repeat(-2.0), &Vector3::repeat(2.0)).is_empty());
+        assert!(Aabb::empty().ray_entry(&Vector3::zeros(), &Vector3::repeat(1.0), f32::INFINITY).is_none());
+    }
+}
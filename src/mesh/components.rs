@@ -0,0 +1,223 @@
+use std::collections::HashMap;
+
+use nalgebra::Vector3;
+
+use super::{find_root, Mesh};
+
+/// summary of one connected shell of the mesh
+#[derive(Clone, Debug)]
+pub struct ComponentInfo{
+    pub face_count: usize,
+    pub area: f32,
+    pub bb_min: Vector3<f32>,
+    pub bb_max: Vector3<f32>,
+    pub hidden: bool // all of its faces are hidden
+}
+
+/// marks vertices not used by any face
+const NO_COMPONENT: usize = usize::MAX;
+
+/// errors on the first id past the `count` components
+fn check_component_ids(components: &[usize], count: usize) -> Result<(), String>{
+    if let Some(id) = components.iter().find(|id| **id >= count){
+        return Err(format!("No component {}", id));
+    }
+    return Ok(());
+}
+
+impl Mesh{
+    /// Component id of every vertex, components touching through split vertices count as one.
+    /// Ids are ordered by face count, 0 being the largest.
+    pub(super) fn component_labels(&self) -> (Vec<usize>, usize){
+        let (positions, remap) = self.weld_positions();
+
+        let mut parents: Vec<usize> = (0..positions.len()).collect();
+        for face in &self.faces{
+            for vert in &face.verts[1..]{
+                let (root_a, root_b) = (find_root(&mut parents, remap[face.verts[0]]), find_root(&mut parents, remap[*vert]));
+                parents[root_a] = root_b;
+            }
+        }
+
+        let mut root_face_counts: HashMap<usize, usize> = HashMap::new();
+        let mut roots_in_order: Vec<usize> = vec![]; // first appearance, keeps equal sized components stable
+        for face in &self.faces{
+            let root = find_root(&mut parents, remap[face.verts[0]]);
+            let count = root_face_counts.entry(root).or_insert(0);
+            if *count == 0{
+                roots_in_order.push(root);
+            }
+            *count += 1;
+        }
+        roots_in_order.sort_by_key(|root| std::cmp::Reverse(root_face_counts[root]));
+
+        let root_labels: HashMap<usize, usize> = roots_in_order.iter().enumerate().map(|(label, root)| (*root, label)).collect();
+
+        let labels = remap.iter()
+            .map(|welded_id| *root_labels.get(&find_root(&mut parents, *welded_id)).unwrap_or(&NO_COMPONENT))
+            .collect();
+
+        return (labels, roots_in_order.len());
+    }
+
+    pub fn connected_components(&self) -> Vec<ComponentInfo>{
+        let (labels, count) = self.component_labels();
+
+        let mut components = vec![ComponentInfo{face_count: 0, area: 0.0,
+            bb_min: Vector3::repeat(f32::INFINITY), bb_max: Vector3::repeat(f32::NEG_INFINITY), hidden: true}; count];
+
+        for face in &self.faces{
+            let component = &mut components[labels[face.verts[0]]];
+            let (a, b, c) = (self.verts[face.verts[0]].pos, self.verts[face.verts[1]].pos, self.verts[face.verts[2]].pos);
+
+            component.face_count += 1;
+            component.area += (b - a).cross(&(c - a)).norm() / 2.0;
+            component.hidden &= face.hidden;
+            for pos in [a, b, c]{
+                component.bb_min = component.bb_min.inf(&pos);
+                component.bb_max = component.bb_max.sup(&pos);
+            }
+        }
+
+        return components;
+    }
+
+    /// component id of every face and the number of components
+    pub fn face_components(&self) -> (Vec<usize>, usize){
+        let (labels, count) = self.component_labels();
+        return (self.faces.iter().map(|face| labels[face.verts[0]]).collect(), count);
+    }
+
+    pub fn set_components_hidden(&mut self, components: &[usize], hidden: bool) -> Result<(), String>{
+        let (labels, count) = self.component_labels();
+        check_component_ids(components, count)?;
+
+        let polygon_faces = self.polygon_faces.iter_mut().flatten();
+        for face in self.faces.iter_mut().chain(polygon_faces){
+            if components.contains(&labels[face.verts[0]]){
+                face.hidden = hidden;
+            }
+        }
+        return Ok(());
+    }
+
+    /// shows only the given components
+    pub fn isolate_components(&mut self, components: &[usize]) -> Result<(), String>{
+        let (labels, count) = self.component_labels();
+        check_component_ids(components, count)?;
+
+        let polygon_faces = self.polygon_faces.iter_mut().flatten();
+        for face in self.faces.iter_mut().chain(polygon_faces){
+            face.hidden = !components.contains(&labels[face.verts[0]]);
+        }
+        return Ok(());
+    }
+
+    pub fn set_component_color(&mut self, component: usize, color: Vector3<f32>) -> Result<(), String>{
+        let (labels, count) = self.component_labels();
+        check_component_ids(&[component], count)?;
+
+        for (vert, label) in self.verts.iter_mut().zip(labels){
+            if label == component{
+                vert.color = color;
+            }
+        }
+        return Ok(());
+    }
+
+    pub fn remove_components(&mut self, components: &[usize]) -> Result<(), String>{
+        let (labels, count) = self.component_labels();
+        check_component_ids(components, count)?;
+        self.retain_faces(|face_vert| !components.contains(&labels[face_vert]));
+        return Ok(());
+    }
+
+    /// keeps the faces whose first vertex passes, drops the vertices no face uses anymore
+    fn retain_faces<F>(&mut self, keep: F) where F: Fn(usize) -> bool{
+        self.faces.retain(|face| keep(face.verts[0]));
+        if let Some(polygon_faces) = &mut self.polygon_faces{
+            polygon_faces.retain(|face| keep(face.verts[0]));
+        }
+
+        let mut new_ids: Vec<Option<usize>> = vec![None; self.verts.len()];
+        let mut next_id = 0;
+        let polygon_faces = self.polygon_faces.iter().flatten();
+        for face in self.faces.iter().chain(polygon_faces){
+            for vert in &face.verts{
+                if new_ids[*vert].is_none(){
+                    new_ids[*vert] = Some(next_id);
+                    next_id += 1;
+                }
+            }
+        }
+
+        let mut verts = std::mem::take(&mut self.verts).into_iter().enumerate()
+            .filter_map(|(id, vert)| new_ids[id].map(|new_id| (new_id, vert)))
+            .collect::<Vec<_>>();
+        verts.sort_by_key(|(new_id, _)| *new_id);
+        self.verts = verts.into_iter().map(|(_, vert)| vert).collect();
+
+        let polygon_faces = self.polygon_faces.iter_mut().flatten();
+        for face in self.faces.iter_mut().chain(polygon_faces){
+            for vert in face.verts.iter_mut(){
+                *vert = new_ids[*vert].expect("vertex of a kept face");
+            }
+        }
+
+        if !self.verts.is_empty(){
+            (self.bb_min, self.bb_max) = self.compute_bounds();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+    use crate::mesh::tests::{two_cubes, UNIT_CUBE};
+
+    #[test]
+    fn unknown_component_ids_are_rejected(){
+        let mut mesh = Mesh::load_obj(UNIT_CUBE).unwrap();
+        assert_eq!(mesh.connected_components().len(), 1);
+
+        assert_eq!(mesh.isolate_components(&[1]), Err("No component 1".to_string()));
+        assert!(mesh.set_components_hidden(&[0, 3], true).is_err());
+        assert!(mesh.set_component_color(2, Vector3::zeros()).is_err());
+        assert!(mesh.remove_components(&[1]).is_err());
+        assert!(mesh.faces.iter().all(|face| !face.hidden));
+        assert_eq!(mesh.face_count(), 12);
+
+        mesh.isolate_components(&[0]).unwrap();
+        assert!(mesh.faces.iter().all(|face| !face.hidden));
+    }
+
+    #[test]
+    fn disjoint_shells_are_separate_components(){
+        let mut mesh = two_cubes();
+        let components = mesh.connected_components();
+        assert_eq!(components.len(), 2);
+        // equal face counts keep the order they appear in
+        assert_eq!((components[0].face_count, components[1].face_count), (12, 12));
+        assert!((components[0].area - 6.0).abs() < 1e-5 && (components[1].area - 24.0).abs() < 1e-4);
+        let shift = Vector3::new(2.75, 0.0, 0.0);
+        assert_eq!((components[0].bb_min, components[0].bb_max), (Vector3::repeat(-0.5) - shift, Vector3::repeat(0.5) - shift));
+        let big_bounds = (Vector3::new(4.0, -1.0, -1.0) - shift, Vector3::new(6.0, 1.0, 1.0) - shift);
+        assert_eq!((components[1].bb_min, components[1].bb_max), big_bounds);
+
+        let hidden = |mesh: &Mesh| mesh.connected_components().iter().map(|component| component.hidden).collect::<Vec<bool>>();
+        mesh.set_components_hidden(&[1], true).unwrap();
+        assert_eq!(hidden(&mesh), vec![false, true]);
+        mesh.isolate_components(&[1]).unwrap();
+        assert_eq!(hidden(&mesh), vec![true, false]);
+        mesh.set_components_hidden(&[0, 1], false).unwrap();
+        assert_eq!(hidden(&mesh), vec![false, false]);
+
+        mesh.remove_components(&[0]).unwrap();
+        assert_eq!((mesh.vert_count(), mesh.face_count()), (8, 12));
+        assert!(mesh.faces.iter().chain(mesh.polygon_faces.iter().flatten()).all(|face| face.verts.iter().all(|vert| *vert < 8)));
+        assert_eq!((mesh.bb_min, mesh.bb_max), big_bounds);
+        let components = mesh.connected_components();
+        assert_eq!(components.len(), 1);
+        assert!((components[0].area - 24.0).abs() < 1e-4);
+    }
+}
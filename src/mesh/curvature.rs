@@ -0,0 +1,200 @@
+use std::collections::HashMap;
+use std::f32::consts::{PI, TAU};
+
+use nalgebra::{Matrix3, Vector3};
+
+use super::Mesh;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum CurvatureKind{
+    Mean,
+    Gaussian,
+    Maximum, // larger principal curvature
+    Minimum // smaller principal curvature
+}
+
+/// Curvature at a vertex, positive where the surface bends away from its normal like a sphere.
+/// Directions are unit tangents, zero where there are too few neighbors to tell.
+#[derive(Clone, Copy, Debug)]
+pub struct VertexCurvature{
+    pub mean: f32,
+    pub gaussian: f32,
+    pub max: f32,
+    pub min: f32,
+    pub max_direction: Vector3<f32>,
+    pub min_direction: Vector3<f32>
+}
+
+impl VertexCurvature{
+    pub fn value(&self, kind: CurvatureKind) -> f32{
+        return match kind{
+            CurvatureKind::Mean => self.mean,
+            CurvatureKind::Gaussian => self.gaussian,
+            CurvatureKind::Maximum => self.max,
+            CurvatureKind::Minimum => self.min
+        };
+    }
+}
+
+/// angle at corner `a` of the triangle and its cotangent
+fn corner_angle(a: &Vector3<f32>, b: &Vector3<f32>, c: &Vector3<f32>) -> (f32, f32){
+    let (ab, ac) = (b - a, c - a);
+    let (cos_len, sin_len) = (ab.dot(&ac), ab.cross(&ac).norm());
+    let cot = if sin_len > 0.0 {cos_len / sin_len} else {0.0};
+    return (sin_len.atan2(cos_len), cot);
+}
+
+/// Least squares fit of the second fundamental form to the normal curvatures along the edges,
+/// as (max, min, max direction). None with fewer than three usable edges.
+fn fit_curvature_tensor(pos: &Vector3<f32>, normal: &Vector3<f32>, neighbors: &[Vector3<f32>]) -> Option<(f32, f32, Vector3<f32>)>{
+    let u = normal.cross(&Vector3::x()).try_normalize(1e-3).unwrap_or_else(|| normal.cross(&Vector3::y()).normalize());
+    let v = normal.cross(&u);
+
+    let mut normal_matrix = Matrix3::<f32>::zeros();
+    let mut rhs = Vector3::<f32>::zeros();
+    let mut edge_count = 0;
+    for neighbor in neighbors{
+        let edge = neighbor - pos;
+        let tangent = edge - normal * normal.dot(&edge);
+        let Some(tangent) = tangent.try_normalize(1e-12) else {continue};
+
+        // osculating circle through both ends, tangent to the surface at `pos`
+        let curvature = -2.0 * normal.dot(&edge) / edge.norm_squared();
+        let (x, y) = (tangent.dot(&u), tangent.dot(&v));
+        let row = Vector3::new(x * x, 2.0 * x * y, y * y);
+        normal_matrix += row * row.transpose();
+        rhs += row * curvature;
+        edge_count += 1;
+    }
+    if edge_count < 3{
+        return None;
+    }
+
+    let [a, b, c]: [f32; 3] = normal_matrix.lu().solve(&rhs)?.into();
+    let half_sum = (a + c) / 2.0;
+    let half_diff = ((a - c) * (a - c) / 4.0 + b * b).sqrt();
+    let angle = (2.0 * b).atan2(a - c) / 2.0;
+    return Some((half_sum + half_diff, half_sum - half_diff, u * angle.cos() + v * angle.sin()));
+}
+
+impl Mesh{
+    /// Per-vertex curvature of the visible surface, split vertices get the same values.
+    /// Mean and Gaussian curvature are the discrete operators of Meyer et al. (cotangent Laplacian
+    /// and angle deficit over the mixed Voronoi area), principal directions come from a fitted
+    /// curvature tensor. On the boundary the angle deficit is meaningless and the tensor gives everything.
+    pub fn curvature(&self) -> Result<Vec<VertexCurvature>, String>{
+        if !self.is_triangulated{
+            return Err("Mesh is not triangulated".to_string());
+        }
+
+        let (positions, remap) = self.weld_positions();
+        let triangles: Vec<[usize; 3]> = self.faces.iter()
+            .filter(|face| !face.hidden)
+            .map(|face| [remap[face.verts[0]], remap[face.verts[1]], remap[face.verts[2]]])
+            .filter(|tri| tri[0] != tri[1] && tri[1] != tri[2] && tri[2] != tri[0])
+            .collect();
+
+        let count = positions.len();
+        let mut areas = vec![0.0f32; count];
+        let mut laplacians = vec![Vector3::<f32>::zeros(); count];
+        let mut angle_sums = vec![0.0f32; count];
+        let mut normals = vec![Vector3::<f32>::zeros(); count];
+        let mut neighbors: Vec<Vec<usize>> = vec![vec![]; count];
+        let mut edge_counts: HashMap<(usize, usize), u32> = HashMap::new();
+
+        for tri in &triangles{
+            let corners = [positions[tri[0]], positions[tri[1]], positions[tri[2]]];
+            let cross = (corners[1] - corners[0]).cross(&(corners[2] - corners[0]));
+            let area = cross.norm() / 2.0;
+            if area <= 0.0{
+                continue;
+            }
+
+            let angles: Vec<(f32, f32)> = (0..3).map(|i| corner_angle(&corners[i], &corners[(i + 1) % 3], &corners[(i + 2) % 3])).collect();
+            let obtuse_corner = (0..3).find(|i| angles[*i].0 > PI / 2.0);
+
+            for i in 0..3{
+                let (a, b, c) = (tri[i], tri[(i + 1) % 3], tri[(i + 2) % 3]);
+                let (angle, cot) = angles[i];
+
+                // the angle at a weights the opposite edge
+                let edge = positions[b] - positions[c];
+                laplacians[b] += edge * cot / 2.0;
+                laplacians[c] -= edge * cot / 2.0;
+
+                areas[a] += match obtuse_corner{
+                    None => ((positions[b] - positions[a]).norm_squared() * angles[(i + 2) % 3].1
+                        + (positions[c] - positions[a]).norm_squared() * angles[(i + 1) % 3].1) / 8.0,
+                    Some(obtuse) if obtuse == i => area / 2.0,
+                    Some(_) => area / 4.0
+                };
+                angle_sums[a] += angle;
+                normals[a] += cross; // area weighted
+
+                if !neighbors[a].contains(&b){
+                    neighbors[a].push(b);
+                    neighbors[b].push(a);
+                }
+                *edge_counts.entry((a.min(b), a.max(b))).or_insert(0) += 1;
+            }
+        }
+
+        let mut is_boundary = vec![false; count];
+        for ((a, b), edge_count) in edge_counts{
+            if edge_count == 1{
+                is_boundary[a] = true;
+                is_boundary[b] = true;
+            }
+        }
+
+        let welded: Vec<VertexCurvature> = (0..count).map(|id| {
+            let mut result = VertexCurvature{mean: 0.0, gaussian: 0.0, max: 0.0, min: 0.0,
+                max_direction: Vector3::zeros(), min_direction: Vector3::zeros()};
+            let Some(normal) = normals[id].try_normalize(0.0) else {return result}; // not on any triangle
+
+            let neighbor_positions: Vec<Vector3<f32>> = neighbors[id].iter().map(|n| positions[*n]).collect();
+            let tensor = fit_curvature_tensor(&positions[id], &normal, &neighbor_positions);
+
+            if is_boundary[id] || areas[id] <= 0.0{
+                if let Some((max, min, _)) = tensor{
+                    (result.max, result.min) = (max, min);
+                    (result.mean, result.gaussian) = ((max + min) / 2.0, max * min);
+                }
+            }else{
+                result.mean = laplacians[id].dot(&normal) / (2.0 * areas[id]);
+                result.gaussian = (TAU - angle_sums[id]) / areas[id];
+                let spread = (result.mean * result.mean - result.gaussian).max(0.0).sqrt();
+                (result.max, result.min) = (result.mean + spread, result.mean - spread);
+            }
+
+            if let Some((_, _, max_direction)) = tensor{
+                result.max_direction = max_direction;
+                result.min_direction = normal.cross(&max_direction);
+            }
+            result
+        }).collect();
+
+        return Ok(remap.iter().map(|id| welded[*id]).collect());
+    }
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+    use nalgebra::Matrix4;
+
+    #[test]
+    fn sphere_curvature_matches_its_radius(){
+        for scale in [1.0, 2.5]{
+            let mut sphere = Mesh::load_obj(include_str!("../../assets/sphere.obj")).unwrap();
+            sphere.transform(&Matrix4::new_scaling(scale)).unwrap();
+            let radius = sphere.verts.iter().map(|vert| vert.pos.norm()).sum::<f32>() / sphere.verts.len() as f32;
+
+            let curvature = sphere.curvature().unwrap();
+            let mean_error = curvature.iter().map(|c| (c.mean * radius - 1.0).abs()).fold(0.0, f32::max);
+            let gaussian_error = curvature.iter().map(|c| (c.gaussian * radius * radius - 1.0).abs()).fold(0.0, f32::max);
+            assert!(mean_error < 0.02, "mean curvature off by {}", mean_error);
+            assert!(gaussian_error < 0.03, "gaussian curvature off by {}", gaussian_error);
+        }
+    }
+}
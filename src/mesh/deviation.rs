@@ -0,0 +1,76 @@
+use nalgebra::{Matrix4, Point3};
+
+use super::{Bvh, Mesh};
+
+/// summary of signed distances, in model units
+#[derive(Clone, Copy, Debug)]
+pub struct DeviationStats{
+    pub min: f32,
+    pub max: f32,
+    pub mean: f32,
+    pub rms: f32
+}
+
+impl DeviationStats{
+    pub fn new(distances: &[f32]) -> DeviationStats{
+        let finite: Vec<f32> = distances.iter().cloned().filter(|distance| distance.is_finite()).collect();
+        if finite.is_empty(){
+            return DeviationStats{min: 0.0, max: 0.0, mean: 0.0, rms: 0.0};
+        }
+
+        let count = finite.len() as f64;
+        return DeviationStats{
+            min: finite.iter().cloned().fold(f32::INFINITY, f32::min),
+            max: finite.iter().cloned().fold(f32::NEG_INFINITY, f32::max),
+            mean: (finite.iter().map(|distance| *distance as f64).sum::<f64>() / count) as f32,
+            rms: (finite.iter().map(|distance| (*distance as f64).powi(2)).sum::<f64>() / count).sqrt() as f32
+        };
+    }
+}
+
+impl Mesh{
+    /// Per-vertex distance to the closest point of the surface indexed by `reference`, with the mesh placed
+    /// by the model matrix `transform`. Positive in front of the closest face, NaN when the reference is empty.
+    /// Near edges the closest face decides the sign, so it can flip on sharp concave corners.
+    pub fn signed_distances(&self, reference: &Bvh, transform: &Matrix4<f32>) -> Vec<f32>{
+        return self.verts.iter().map(|vert| {
+            let pos = transform.transform_point(&Point3::from(vert.pos)).coords;
+            match reference.closest_point(&pos, f32::INFINITY){
+                Some(closest) => if (pos - closest.position).dot(&closest.normal) < 0.0 {-closest.distance} else {closest.distance},
+                None => f32::NAN
+            }
+        }).collect();
+    }
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+    use crate::mesh::tests::UNIT_CUBE;
+
+    #[test]
+    fn distances_are_positive_outside_and_negative_inside(){
+        let cube = Mesh::load_obj(UNIT_CUBE).unwrap();
+        let reference = Bvh::build(&cube);
+
+        // corners of a grown cube lie off the reference corners, those of a shrunk one inside its faces
+        let outside = cube.signed_distances(&reference, &Matrix4::new_scaling(2.0));
+        assert!(outside.iter().all(|distance| (distance - 0.75f32.sqrt()).abs() < 1e-5), "{:?}", outside);
+        let inside = cube.signed_distances(&reference, &Matrix4::new_scaling(0.5));
+        assert!(inside.iter().all(|distance| (distance + 0.25).abs() < 1e-5), "{:?}", inside);
+    }
+
+    #[test]
+    fn empty_reference_gives_nan(){
+        // only degenerate faces, nothing the hierarchy can index
+        let flat = Mesh::load_obj("v 0 0 0\nv 1 0 0\nv 2 0 0\nf 1 2 3\n").unwrap();
+        let reference = Bvh::build(&flat);
+        let cube = Mesh::load_obj(UNIT_CUBE).unwrap();
+
+        let distances = cube.signed_distances(&reference, &Matrix4::identity());
+        assert_eq!(distances.len(), 8);
+        assert!(distances.iter().all(|distance| distance.is_nan()));
+        let stats = DeviationStats::new(&distances);
+        assert_eq!((stats.min, stats.max, stats.rms), (0.0, 0.0, 0.0));
+    }
+}
@@ -0,0 +1,168 @@
+use std::collections::HashMap;
+
+use nalgebra::{Matrix3, Vector3};
+
+use super::Mesh;
+
+/// Geometric properties of the mesh surface and of the solid it encloses (unit density).
+/// Volume quantities only make sense when `is_closed` is set.
+#[derive(Clone, Copy, Debug)]
+pub struct MassProperties{
+    pub surface_area: f32,
+    pub volume: f32, // signed, negative for inward facing shells
+    pub is_closed: bool,
+    pub area_centroid: Vector3<f32>,
+    pub volume_centroid: Vector3<f32>,
+    pub inertia_tensor: Matrix3<f32> // about the volume centroid
+}
+
+impl Mesh{
+    fn triangle_positions(&self) -> impl Iterator<Item = [Vector3<f64>; 3]> + '_{
+        return self.faces.iter().map(|face| [
+            self.verts[face.verts[0]].pos.cast::<f64>(),
+            self.verts[face.verts[1]].pos.cast::<f64>(),
+            self.verts[face.verts[2]].pos.cast::<f64>()
+        ]);
+    }
+
+    pub fn surface_area(&self) -> f32{
+        return self.triangle_positions()
+            .map(|[a, b, c]| (b - a).cross(&(c - a)).norm() / 2.0)
+            .sum::<f64>() as f32;
+    }
+
+    /// sum of signed tetrahedra spanned by the origin and each triangle
+    pub fn signed_volume(&self) -> f32{
+        return self.triangle_positions()
+            .map(|[a, b, c]| a.dot(&b.cross(&c)) / 6.0)
+            .sum::<f64>() as f32;
+    }
+
+    /// every edge (with split vertices welded) is shared by exactly two faces
+    pub fn is_closed(&self) -> bool{
+        let (_, remap) = self.weld_positions();
+        let mut edge_counts: HashMap<(usize, usize), u32> = HashMap::new();
+
+        for face in &self.faces{
+            let tri = [remap[face.verts[0]], remap[face.verts[1]], remap[face.verts[2]]];
+            if tri[0] == tri[1] || tri[1] == tri[2] || tri[2] == tri[0]{
+                continue; // degenerate, doesn't bound anything
+            }
+            for i in 0..3{
+                let (a, b) = (tri[i], tri[(i + 1) % 3]);
+                *edge_counts.entry((a.min(b), a.max(b))).or_insert(0) += 1;
+            }
+        }
+
+        return !edge_counts.is_empty() && edge_counts.values().all(|count| *count == 2);
+    }
+
+    pub fn area_centroid(&self) -> Vector3<f32>{
+        let mut weighted_sum = Vector3::<f64>::zeros();
+        let mut total_area = 0.0;
+
+        for [a, b, c] in self.triangle_positions(){
+            let area = (b - a).cross(&(c - a)).norm() / 2.0;
+            weighted_sum += (a + b + c) / 3.0 * area;
+            total_area += area;
+        }
+
+        if total_area == 0.0{
+            return Vector3::zeros();
+        }
+        return (weighted_sum / total_area).cast::<f32>();
+    }
+
+    pub fn volume_centroid(&self) -> Vector3<f32>{
+        let mut weighted_sum = Vector3::<f64>::zeros();
+        let mut total_volume = 0.0;
+
+        for [a, b, c] in self.triangle_positions(){
+            let volume = a.dot(&b.cross(&c)) / 6.0;
+            weighted_sum += (a + b + c) / 4.0 * volume; // the fourth tetrahedron corner is the origin
+            total_volume += volume;
+        }
+
+        if total_volume == 0.0{
+            return Vector3::zeros();
+        }
+        return (weighted_sum / total_volume).cast::<f32>();
+    }
+
+    /// inertia tensor of the enclosed solid at unit density, taken about the volume centroid
+    pub fn inertia_tensor(&self) -> Matrix3<f32>{
+        // covariance of the canonical tetrahedron (0, x, y, z), scaled by the determinant of each tetrahedron
+        let canonical = Matrix3::<f64>::new(
+            2.0, 1.0, 1.0,
+            1.0, 2.0, 1.0,
+            1.0, 1.0, 2.0) / 120.0;
+
+        let mut covariance = Matrix3::<f64>::zeros();
+        let mut mass = 0.0;
+        let mut weighted_sum = Vector3::<f64>::zeros();
+
+        for [a, b, c] in self.triangle_positions(){
+            let tetra = Matrix3::from_columns(&[a, b, c]);
+            let det = tetra.determinant();
+            covariance += tetra * canonical * tetra.transpose() * det;
+            mass += det / 6.0;
+            weighted_sum += (a + b + c) / 4.0 * (det / 6.0);
+        }
+
+        if mass == 0.0{
+            return Matrix3::zeros();
+        }
+
+        // parallel axis shift from the origin to the centroid
+        let centroid = weighted_sum / mass;
+        let covariance = covariance - centroid * centroid.transpose() * mass;
+
+        let inertia = Matrix3::identity() * covariance.trace() - covariance;
+        return inertia.cast::<f32>();
+    }
+
+    pub fn mass_properties(&self) -> MassProperties{
+        return MassProperties{
+            surface_area: self.surface_area(),
+            volume: self.signed_volume(),
+            is_closed: self.is_closed(),
+            area_centroid: self.area_centroid(),
+            volume_centroid: self.volume_centroid(),
+            inertia_tensor: self.inertia_tensor()
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+    use nalgebra::Matrix4;
+    use crate::mesh::tests::UNIT_CUBE;
+
+    #[test]
+    fn unit_cube_properties(){
+        let cube = Mesh::load_obj(UNIT_CUBE).unwrap();
+        let properties = cube.mass_properties();
+
+        assert!(properties.is_closed);
+        assert!((properties.surface_area - 6.0).abs() < 1e-5);
+        assert!((properties.volume - 1.0).abs() < 1e-5);
+        assert!(properties.area_centroid.norm() < 1e-5);
+        assert!(properties.volume_centroid.norm() < 1e-5);
+        // a unit cube of unit mass has m (1 + 1) / 12 about each axis
+        assert!((properties.inertia_tensor - Matrix3::identity() / 6.0).amax() < 1e-5, "{}", properties.inertia_tensor);
+    }
+
+    #[test]
+    fn moved_cube_keeps_its_inertia_about_the_centroid(){
+        let mut cube = Mesh::load_obj(UNIT_CUBE).unwrap();
+        cube.transform(&Matrix4::new_translation(&Vector3::new(3.0, -2.0, 1.0))).unwrap();
+
+        assert!((cube.volume_centroid() - Vector3::new(3.0, -2.0, 1.0)).norm() < 1e-5);
+        assert!((cube.signed_volume() - 1.0).abs() < 1e-4);
+        assert!((cube.inertia_tensor() - Matrix3::identity() / 6.0).amax() < 1e-4);
+
+        cube.faces.pop();
+        assert!(!cube.is_closed());
+    }
+}
@@ -0,0 +1,281 @@
+use nalgebra::{Matrix3, Matrix4, Point3, Rotation3, SMatrix, SVector, Translation3, Vector3};
+
+use super::{Bvh, Mesh};
+
+/// source points paired per iteration, larger meshes are subsampled
+const MAX_ICP_SAMPLES: usize = 5000;
+
+/// pairs closer than this fraction of the sample extent are never outliers, once nearly
+/// everything matches the median drops to rounding noise
+const MIN_OUTLIER_DISTANCE: f64 = 1e-3;
+
+/// an RMS distance below this fraction of the sample extent is a perfect fit, f32 positions resolve no finer.
+/// Without it exact matches keep improving by a steady fraction and never count as converged
+const MIN_RMS: f64 = 1e-6;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum IcpMetric{
+    PointToPoint, // distances to the closest points, robust from far off
+    PointToPlane // distances to the tangent planes there, converges faster on smooth surfaces
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct IcpOptions{
+    pub metric: IcpMetric,
+    pub allow_scale: bool, // uniform scale on top of the rigid motion
+    pub max_iterations: u32,
+    pub tolerance: f32, // stops once the RMS distance improves by less than this fraction
+    pub max_distance: f32, // points farther than this from the target are left out
+    pub outlier_factor: Option<f32> // pairs farther apart than this many times the median are left out
+}
+
+impl IcpOptions{
+    pub fn new() -> IcpOptions{
+        return IcpOptions{metric: IcpMetric::PointToPoint, allow_scale: false, max_iterations: 50, tolerance: 1e-5,
+            max_distance: f32::INFINITY, outlier_factor: Some(3.0)};
+    }
+}
+
+impl Default for IcpOptions{
+    fn default() -> IcpOptions{
+        return IcpOptions::new();
+    }
+}
+
+/// outcome of `Mesh::align_icp`
+#[derive(Clone, Copy, Debug)]
+pub struct IcpReport{
+    pub transform: Matrix4<f32>, // model matrix putting the mesh onto the target
+    pub iterations: u32, // updates made
+    pub converged: bool, // false when stopped by the iteration limit or too few pairs
+    pub rms: f32, // distance between the pairs kept at `transform`
+    pub inlier_fraction: f32 // of the sampled points, at `transform`
+}
+
+/// point of the moved mesh and the closest target point with its face normal
+struct Pair{
+    source: Vector3<f64>,
+    target: Vector3<f64>,
+    normal: Vector3<f64>
+}
+
+/// Least squares rigid motion, scaled too with `allow_scale`, taking each source point onto its target (Umeyama).
+fn fit_point_to_point(pairs: &[Pair], allow_scale: bool) -> Matrix4<f64>{
+    let count = pairs.len() as f64;
+    let source_centroid = pairs.iter().map(|pair| pair.source).sum::<Vector3<f64>>() / count;
+    let target_centroid = pairs.iter().map(|pair| pair.target).sum::<Vector3<f64>>() / count;
+
+    let covariance = pairs.iter()
+        .map(|pair| (pair.source - source_centroid) * (pair.target - target_centroid).transpose())
+        .sum::<Matrix3<f64>>();
+    let svd = covariance.svd(true, true);
+    let (u, v_t) = (svd.u.unwrap(), svd.v_t.unwrap());
+
+    // flip the weakest axis rather than return a reflection
+    let mut signs = Vector3::<f64>::repeat(1.0);
+    if (v_t.transpose() * u.transpose()).determinant() < 0.0{
+        signs[svd.singular_values.imin()] = -1.0;
+    }
+    let rotation = v_t.transpose() * Matrix3::from_diagonal(&signs) * u.transpose();
+
+    let mut scale = 1.0;
+    if allow_scale{
+        let spread = pairs.iter().map(|pair| (pair.source - source_centroid).norm_squared()).sum::<f64>();
+        if spread > 0.0{
+            scale = svd.singular_values.dot(&signs) / spread;
+        }
+    }
+
+    let translation = target_centroid - rotation * source_centroid * scale;
+    let mut transform = (rotation * scale).to_homogeneous();
+    transform.fixed_view_mut::<3, 1>(0, 3).copy_from(&translation);
+    return transform;
+}
+
+/// One Gauss-Newton step on the distances to the target tangent planes, rotation linearized
+/// around the source centroid. The seventh unknown is the relative scale change.
+fn fit_point_to_plane(pairs: &[Pair], allow_scale: bool) -> Option<Matrix4<f64>>{
+    let centroid = pairs.iter().map(|pair| pair.source).sum::<Vector3<f64>>() / pairs.len() as f64;
+
+    let mut normal_matrix = SMatrix::<f64, 7, 7>::zeros();
+    let mut rhs = SVector::<f64, 7>::zeros();
+    for pair in pairs{
+        let arm = pair.source - centroid;
+        let torque = arm.cross(&pair.normal);
+        let scaling = if allow_scale {arm.dot(&pair.normal)} else {0.0};
+        let row = SVector::<f64, 7>::from_column_slice(&[torque.x, torque.y, torque.z, pair.normal.x, pair.normal.y, pair.normal.z, scaling]);
+        normal_matrix += row * row.transpose();
+        rhs -= row * (pair.source - pair.target).dot(&pair.normal);
+    }
+    if !allow_scale{
+        normal_matrix[(6, 6)] = 1.0; // pins the scale change to zero
+    }
+
+    let step = normal_matrix.cholesky()?.solve(&rhs);
+    let rotation = Rotation3::new(Vector3::new(step[0], step[1], step[2]));
+    let translation = Vector3::new(step[3], step[4], step[5]);
+    let scale = 1.0 + step[6];
+
+    return Some(Translation3::from(centroid + translation).to_homogeneous()
+        * (rotation.matrix() * scale).to_homogeneous()
+        * Translation3::from(-centroid).to_homogeneous());
+}
+
+impl Mesh{
+    /// up to MAX_ICP_SAMPLES distinct positions, evenly spread over the vertex list
+    fn registration_samples(&self) -> Vec<Vector3<f64>>{
+        let (positions, _) = self.weld_positions();
+        let stride = positions.len().div_ceil(MAX_ICP_SAMPLES).max(1);
+        return positions.iter().step_by(stride).map(|pos| pos.cast::<f64>()).collect();
+    }
+
+    /// Model matrix moving this mesh onto the surface indexed by `target` by iterative closest points,
+    /// refining the rough alignment `initial`. Converges to the nearest local fit, so `initial` has to be close.
+    pub fn align_icp(&self, target: &Bvh, initial: &Matrix4<f32>, options: &IcpOptions) -> Result<IcpReport, String>{
+        let samples = self.registration_samples();
+        let extent = samples.iter().fold(Vector3::repeat(f64::NEG_INFINITY), |max, sample| max.sup(sample))
+            - samples.iter().fold(Vector3::repeat(f64::INFINITY), |min, sample| min.inf(sample));
+        let min_outlier_distance = extent.norm() * MIN_OUTLIER_DISTANCE;
+        let min_pair_count = match options.metric{
+            IcpMetric::PointToPoint => 3,
+            IcpMetric::PointToPlane => 7
+        };
+
+        let mut transform = initial.cast::<f64>();
+        let mut report = IcpReport{transform: *initial, iterations: 0, converged: false, rms: f32::INFINITY, inlier_fraction: 0.0};
+        let mut last_rms = f64::INFINITY;
+
+        for iteration in 0..=options.max_iterations{
+            let mut pairs: Vec<Pair> = samples.iter().filter_map(|sample| {
+                let moved = transform.transform_point(&Point3::from(*sample)).coords;
+                target.closest_point(&moved.cast::<f32>(), options.max_distance).map(|closest| Pair{
+                    source: moved,
+                    target: closest.position.cast::<f64>(),
+                    normal: closest.normal.cast::<f64>()
+                })
+            }).collect();
+
+            if let Some(outlier_factor) = options.outlier_factor{
+                let mut distances: Vec<f64> = pairs.iter().map(|pair| (pair.target - pair.source).norm()).collect();
+                if !distances.is_empty(){
+                    let middle = distances.len() / 2;
+                    let median = *distances.select_nth_unstable_by(middle, f64::total_cmp).1;
+                    let max_distance = (median * outlier_factor as f64).max(min_outlier_distance);
+                    pairs.retain(|pair| (pair.target - pair.source).norm() <= max_distance);
+                }
+            }
+
+            if pairs.len() < min_pair_count{
+                if iteration == 0{
+                    return Err(format!("Only {} point pairs within reach of the target, need {}", pairs.len(), min_pair_count));
+                }
+                break;
+            }
+
+            let rms = (pairs.iter().map(|pair| (pair.target - pair.source).norm_squared()).sum::<f64>() / pairs.len() as f64).sqrt();
+            let has_converged = rms <= extent.norm() * MIN_RMS || (last_rms.is_finite() && last_rms - rms <= last_rms * options.tolerance as f64);
+            if rms < last_rms{ // a last step making things worse is dropped
+                report.transform = transform.cast::<f32>();
+                report.rms = rms as f32;
+                report.inlier_fraction = pairs.len() as f32 / samples.len() as f32;
+            }
+            if has_converged{
+                report.converged = true;
+                break;
+            }
+            if iteration == options.max_iterations{
+                break;
+            }
+            last_rms = rms;
+
+            let step = match options.metric{
+                IcpMetric::PointToPoint => fit_point_to_point(&pairs, options.allow_scale),
+                IcpMetric::PointToPlane => fit_point_to_plane(&pairs, options.allow_scale).ok_or("Point to plane fit is degenerate")?
+            };
+            transform = step * transform;
+            report.iterations += 1;
+        }
+
+        return Ok(report);
+    }
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+
+    fn gear() -> Mesh{
+        return Mesh::load_obj(include_str!("../../assets/gear.obj")).unwrap();
+    }
+
+    /// gear moved by `transform` as the target, aligned back from the untouched one
+    fn align_to_moved(transform: &Matrix4<f32>, options: &IcpOptions) -> IcpReport{
+        let source = gear();
+        let mut target = gear();
+        target.transform(transform).unwrap();
+        return source.align_icp(&Bvh::build(&target), &Matrix4::identity(), options).unwrap();
+    }
+
+    #[test]
+    fn recovers_a_rigid_motion_with_both_metrics(){
+        let rotation = Rotation3::from_euler_angles(0.05, -0.08, 0.1);
+        let motion = Translation3::new(0.05, -0.03, 0.04).to_homogeneous() * rotation.to_homogeneous();
+
+        for metric in [IcpMetric::PointToPoint, IcpMetric::PointToPlane]{
+            let mut options = IcpOptions::new();
+            options.metric = metric;
+            options.outlier_factor = None; // the surfaces match exactly, every pair is right
+            let report = align_to_moved(&motion, &options);
+
+            assert!(report.converged, "{:?} did not converge: {:?}", metric, report);
+            assert!((report.transform - motion).amax() < 1e-4, "{:?} found {}", metric, report.transform);
+            assert_eq!(report.inlier_fraction, 1.0);
+        }
+
+        // point to point closes in slowly, a loose tolerance stops once steps stop halving the distance
+        let mut options = IcpOptions::new();
+        let report = align_to_moved(&motion, &options);
+        options.tolerance = 0.5;
+        let rough = align_to_moved(&motion, &options);
+        assert!(rough.converged && rough.iterations < report.iterations);
+        assert!(rough.rms > report.rms);
+    }
+
+    #[test]
+    fn recovers_scale_only_when_allowed(){
+        let motion = Translation3::new(0.05, 0.0, -0.05).to_homogeneous() * Matrix4::new_scaling(1.1);
+
+        for metric in [IcpMetric::PointToPoint, IcpMetric::PointToPlane]{
+            let mut options = IcpOptions::new();
+            options.metric = metric;
+            options.allow_scale = true;
+            options.max_iterations = 500; // point to point takes long to settle on the scale
+            let report = align_to_moved(&motion, &options);
+            assert!(report.converged, "{:?} did not converge: {:?}", metric, report);
+            assert!((report.transform - motion).amax() < 1e-3, "{:?} found {}", metric, report.transform);
+
+            options.allow_scale = false;
+            let rigid = align_to_moved(&motion, &options);
+            let scale = rigid.transform.fixed_view::<3, 3>(0, 0).determinant().cbrt();
+            assert!((scale - 1.0).abs() < 1e-4);
+            assert!(rigid.rms > report.rms);
+        }
+    }
+
+    #[test]
+    fn too_few_pairs_is_an_error(){
+        let source = gear();
+        let mut hidden = gear();
+        for face in &mut hidden.faces{
+            face.hidden = true;
+        }
+        let result = source.align_icp(&Bvh::build(&hidden), &Matrix4::identity(), &IcpOptions::new());
+        assert!(result.is_err_and(|error| error.starts_with("Only 0 point pairs")));
+
+        // nothing of the target within reach
+        let mut options = IcpOptions::new();
+        options.max_distance = 0.01;
+        let far = Translation3::new(10.0, 0.0, 0.0).to_homogeneous();
+        assert!(source.align_icp(&Bvh::build(&gear()), &far, &options).is_err());
+    }
+}
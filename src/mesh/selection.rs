@@ -0,0 +1,156 @@
+use std::collections::HashSet;
+
+use nalgebra::{Matrix4, Point2, Point3, Vector3};
+
+use super::{Mesh, SurfacePoint};
+
+/// what a picked point snaps to
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SnapMode{
+    Vertex, // nearest corner of the hit face
+    EdgeMidpoint, // nearest edge midpoint of the hit face
+    Surface // the hit itself
+}
+
+impl Mesh{
+    pub fn vert_count(&self) -> usize{
+        return self.verts.len();
+    }
+
+    /// all faces of the connected parts containing any of `faces`
+    pub fn part_faces(&self, faces: &[usize]) -> Vec<usize>{
+        let (labels, _) = self.component_labels();
+        let parts: HashSet<usize> = faces.iter().map(|face| labels[self.faces[*face].verts[0]]).collect();
+
+        return self.faces.iter().enumerate()
+            .filter(|(_, face)| parts.contains(&labels[face.verts[0]]))
+            .map(|(id, _)| id)
+            .collect();
+    }
+
+    /// the corner of `face` with the largest barycentric weight
+    pub fn nearest_face_corner(&self, face: usize, barycentric: &Vector3<f32>) -> usize{
+        return self.faces[face].verts[barycentric.imax()];
+    }
+
+    pub fn snap_point(&self, hit: &SurfacePoint, snap: SnapMode) -> Vector3<f32>{
+        let corners: Vec<Vector3<f32>> = self.faces[hit.face].verts.iter().map(|vert| self.verts[*vert].pos).collect();
+
+        return match snap{
+            SnapMode::Vertex => corners[hit.barycentric.imax()],
+            SnapMode::EdgeMidpoint => (0..corners.len())
+                .map(|i| (corners[i] + corners[(i + 1) % corners.len()]) / 2.0)
+                .min_by(|a, b| (a - hit.position).norm_squared().total_cmp(&(b - hit.position).norm_squared()))
+                .unwrap_or(hit.position),
+            SnapMode::Surface => hit.position
+        };
+    }
+
+    /// `vert` and its copies split off by normals or seams
+    pub fn coincident_vertices(&self, vert: usize) -> Vec<usize>{
+        let pos = self.verts[vert].pos;
+        return self.verts.iter().enumerate()
+            .filter(|(_, other)| other.pos == pos)
+            .map(|(id, _)| id)
+            .collect();
+    }
+
+    /// Visible faces whose centroid projects into the rectangle, given in normalized device coordinates.
+    /// Occlusion is ignored, faces behind others get selected too.
+    pub fn faces_in_screen_rect(&self, model_view_projection: &Matrix4<f32>, rect_min: &Point2<f32>, rect_max: &Point2<f32>) -> Vec<usize>{
+        return self.faces.iter().enumerate()
+            .filter(|(_, face)| !face.hidden)
+            .filter(|(_, face)| {
+                let centroid = face.verts.iter().map(|vert| self.verts[*vert].pos).sum::<Vector3<f32>>() / face.verts.len() as f32;
+                projects_into_rect(model_view_projection, &centroid, rect_min, rect_max)
+            })
+            .map(|(id, _)| id)
+            .collect();
+    }
+
+    /// vertices of visible faces projecting into the rectangle, see `faces_in_screen_rect`
+    pub fn vertices_in_screen_rect(&self, model_view_projection: &Matrix4<f32>, rect_min: &Point2<f32>, rect_max: &Point2<f32>) -> Vec<usize>{
+        let is_visible = self.visible_vertices();
+        return self.verts.iter().enumerate()
+            .filter(|(id, vert)| is_visible[*id] && projects_into_rect(model_view_projection, &vert.pos, rect_min, rect_max))
+            .map(|(id, _)| id)
+            .collect();
+    }
+
+    /// positions of the given faces as triangles, hidden ones left out
+    pub fn create_face_highlight_buffers(&self, faces: &[usize]) -> Result<(Vec<f32>, Vec<usize>), &str>{
+        if !self.is_triangulated{
+            return Err("Mesh is not triangulated");
+        }
+
+        let mut verts = vec![];
+        let mut indices = vec![];
+
+        for face in faces.iter().map(|face| &self.faces[*face]).filter(|face| !face.hidden){
+            for vert in &face.verts{
+                indices.push(verts.len() / 3);
+                verts.extend_from_slice(self.verts[*vert].pos.as_slice());
+            }
+        }
+
+        Ok((verts, indices))
+    }
+
+    /// positions of the given vertices as points, those only on hidden faces left out
+    pub fn create_vertex_highlight_buffers(&self, verts: &[usize]) -> Result<(Vec<f32>, Vec<usize>), &str>{
+        let is_visible = self.visible_vertices();
+        let mut positions = vec![];
+        for vert in verts.iter().filter(|vert| is_visible[**vert]){
+            positions.extend_from_slice(self.verts[*vert].pos.as_slice());
+        }
+
+        let count = positions.len() / 3;
+        Ok((positions, (0..count).collect()))
+    }
+
+    /// for each vertex whether a visible face uses it
+    fn visible_vertices(&self) -> Vec<bool>{
+        let mut is_visible = vec![false; self.verts.len()];
+        for face in self.faces.iter().filter(|face| !face.hidden){
+            for vert in &face.verts{
+                is_visible[*vert] = true;
+            }
+        }
+        return is_visible;
+    }
+}
+
+fn projects_into_rect(model_view_projection: &Matrix4<f32>, pos: &Vector3<f32>, rect_min: &Point2<f32>, rect_max: &Point2<f32>) -> bool{
+    let clip = model_view_projection * Point3::from(*pos).to_homogeneous();
+    if clip.w <= 0.0{
+        return false; // behind the camera
+    }
+    let (x, y) = (clip.x / clip.w, clip.y / clip.w);
+    return x >= rect_min.x && x <= rect_max.x && y >= rect_min.y && y <= rect_max.y;
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+    use crate::mesh::tests::UNIT_CUBE;
+
+    #[test]
+    fn highlights_leave_hidden_faces_out(){
+        let mut mesh = Mesh::load_obj(UNIT_CUBE).unwrap();
+        for (id, face) in mesh.faces.iter_mut().enumerate(){
+            face.hidden = id != 2 && id != 3; // all but the top
+        }
+        let only_hidden: Vec<usize> = (0..mesh.vert_count())
+            .filter(|vert| mesh.faces.iter().all(|face| face.hidden || !face.verts.contains(vert)))
+            .collect();
+        assert!(!only_hidden.is_empty());
+
+        let (verts, indices) = mesh.create_face_highlight_buffers(&[0, 1, 2]).unwrap();
+        assert_eq!((verts.len(), indices.len()), (9, 3));
+
+        let all: Vec<usize> = (0..mesh.vert_count()).collect();
+        let (positions, indices) = mesh.create_vertex_highlight_buffers(&all).unwrap();
+        assert_eq!(indices.len(), mesh.vert_count() - only_hidden.len());
+        assert_eq!(positions.len(), indices.len() * 3);
+    }
+}
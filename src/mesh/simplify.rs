@@ -0,0 +1,355 @@
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap, HashSet};
+
+use nalgebra::{Matrix4, Vector3, Vector4};
+
+use super::{Face, Mesh, Vertex};
+
+/// extra weight of the planes keeping open borders in place
+const BOUNDARY_WEIGHT: f64 = 1000.0;
+/// collapses turning a face normal more than this (cosine) are rejected
+const MIN_NORMAL_COS: f64 = 0.2;
+
+/// edge collapse waiting in the queue, ordered by the lowest cost first
+struct Collapse{
+    cost: f64,
+    v0: usize,
+    v1: usize,
+    target: Vector3<f64>,
+    stamps: (u32, u32) // vertex versions at the time of planning
+}
+
+impl PartialEq for Collapse{
+    fn eq(&self, other: &Self) -> bool {
+        self.cost == other.cost
+    }
+}
+
+impl Eq for Collapse{}
+
+impl PartialOrd for Collapse{
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Collapse{
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.cost.total_cmp(&self.cost) // reversed, BinaryHeap is a max-heap
+    }
+}
+
+/// working state of the quadric error metric simplification
+struct Simplifier{
+    positions: Vec<Vector3<f64>>,
+    colors: Vec<Vector3<f32>>,
+    quadrics: Vec<Matrix4<f64>>,
+    stamps: Vec<u32>,
+    vert_alive: Vec<bool>,
+    vert_faces: Vec<Vec<usize>>,
+    faces: Vec<[usize; 3]>,
+    face_alive: Vec<bool>,
+    alive_face_count: usize,
+    queue: BinaryHeap<Collapse>
+}
+
+fn plane_quadric(normal: &Vector3<f64>, point: &Vector3<f64>, weight: f64) -> Matrix4<f64>{
+    let plane = Vector4::new(normal.x, normal.y, normal.z, -normal.dot(point));
+    return plane * plane.transpose() * weight;
+}
+
+fn quadric_error(quadric: &Matrix4<f64>, pos: &Vector3<f64>) -> f64{
+    let v = pos.push(1.0);
+    return (v.transpose() * quadric * v)[(0, 0)];
+}
+
+impl Simplifier{
+    fn new(mesh: &Mesh) -> Simplifier{
+        // weld vertices sharing a position, so seams between normals don't tear apart
+        let (positions, remap) = mesh.weld_positions();
+        let positions: Vec<Vector3<f64>> = positions.iter().map(|pos| pos.cast::<f64>()).collect();
+
+        let mut colors: Vec<Vector3<f32>> = vec![Vertex::default_color(); positions.len()];
+        for (vert, welded_id) in mesh.verts.iter().zip(&remap).rev(){ // first vertex at a position wins
+            colors[*welded_id] = vert.color;
+        }
+
+        let mut faces: Vec<[usize; 3]> = vec![];
+        for face in mesh.faces.iter().filter(|face| !face.hidden){
+            let tri = [remap[face.verts[0]], remap[face.verts[1]], remap[face.verts[2]]];
+            if tri[0] != tri[1] && tri[1] != tri[2] && tri[2] != tri[0]{
+                faces.push(tri);
+            }
+        }
+
+        let vert_count = positions.len();
+        let mut simplifier = Simplifier{
+            positions: positions,
+            colors: colors,
+            quadrics: vec![Matrix4::zeros(); vert_count],
+            stamps: vec![0; vert_count],
+            vert_alive: vec![true; vert_count],
+            vert_faces: vec![vec![]; vert_count],
+            face_alive: vec![true; faces.len()],
+            alive_face_count: faces.len(),
+            faces: faces,
+            queue: BinaryHeap::new()
+        };
+
+        simplifier.init_quadrics();
+
+        let mut edges: HashSet<(usize, usize)> = HashSet::new();
+        for tri in &simplifier.faces{
+            for i in 0..3{
+                let (a, b) = (tri[i], tri[(i + 1) % 3]);
+                edges.insert((a.min(b), a.max(b)));
+            }
+        }
+        for (a, b) in edges{
+            simplifier.push_collapse(a, b);
+        }
+
+        return simplifier;
+    }
+
+    fn init_quadrics(&mut self){
+        let mut edge_faces: HashMap<(usize, usize), Vec<usize>> = HashMap::new();
+
+        for (face_id, tri) in self.faces.iter().enumerate(){
+            self.vert_faces[tri[0]].push(face_id);
+            self.vert_faces[tri[1]].push(face_id);
+            self.vert_faces[tri[2]].push(face_id);
+
+            let (p0, p1, p2) = (self.positions[tri[0]], self.positions[tri[1]], self.positions[tri[2]]);
+            let cross = (p1 - p0).cross(&(p2 - p0));
+            let area = cross.norm() / 2.0;
+            if area > 0.0{
+                let quadric = plane_quadric(&(cross / (area * 2.0)), &p0, area);
+                for vert in tri{
+                    self.quadrics[*vert] += quadric;
+                }
+            }
+
+            for i in 0..3{
+                let (a, b) = (tri[i], tri[(i + 1) % 3]);
+                edge_faces.entry((a.min(b), a.max(b))).or_default().push(face_id);
+            }
+        }
+
+        // open borders get a plane perpendicular to their face, so they don't shrink
+        for ((a, b), faces) in edge_faces{
+            if faces.len() != 1{
+                continue;
+            }
+            let tri = self.faces[faces[0]];
+            let (p0, p1, p2) = (self.positions[tri[0]], self.positions[tri[1]], self.positions[tri[2]]);
+            let face_normal = (p1 - p0).cross(&(p2 - p0));
+            let edge = self.positions[b] - self.positions[a];
+            let border_normal = edge.cross(&face_normal);
+            if border_normal.norm() == 0.0{
+                continue;
+            }
+            let quadric = plane_quadric(&border_normal.normalize(), &self.positions[a], BOUNDARY_WEIGHT * edge.norm_squared());
+            self.quadrics[a] += quadric;
+            self.quadrics[b] += quadric;
+        }
+    }
+
+    fn push_collapse(&mut self, v0: usize, v1: usize){
+        let quadric = self.quadrics[v0] + self.quadrics[v1];
+        let (p0, p1) = (self.positions[v0], self.positions[v1]);
+
+        // optimal position solves the quadric's gradient, otherwise pick the best of the edge points
+        let mut solver = quadric;
+        solver.set_row(3, &Vector4::new(0.0, 0.0, 0.0, 1.0).transpose());
+
+        let mut target = None;
+        if solver.determinant().abs() > 1e-12
+            && let Some(inverse) = solver.try_inverse(){
+            target = Some((inverse * Vector4::new(0.0, 0.0, 0.0, 1.0)).xyz());
+        }
+
+        let target = target.unwrap_or_else(|| {
+            let candidates = [p0, p1, (p0 + p1) / 2.0];
+            *candidates.iter()
+                .min_by(|a, b| quadric_error(&quadric, a).total_cmp(&quadric_error(&quadric, b)))
+                .unwrap()
+        });
+
+        self.queue.push(Collapse{
+            cost: quadric_error(&quadric, &target),
+            v0: v0,
+            v1: v1,
+            target: target,
+            stamps: (self.stamps[v0], self.stamps[v1])
+        });
+    }
+
+    fn neighbors(&self, vert: usize) -> HashSet<usize>{
+        let mut neighbors = HashSet::new();
+        for face_id in &self.vert_faces[vert]{
+            if self.face_alive[*face_id]{
+                for other in self.faces[*face_id]{
+                    if other != vert{
+                        neighbors.insert(other);
+                    }
+                }
+            }
+        }
+        return neighbors;
+    }
+
+    /// checks the collapse keeps the surface manifold and doesn't fold any face over
+    fn is_collapse_valid(&self, collapse: &Collapse) -> bool{
+        let (v0, v1) = (collapse.v0, collapse.v1);
+
+        let shared_faces = self.vert_faces[v0].iter()
+            .filter(|f| self.face_alive[**f] && self.faces[**f].contains(&v1))
+            .count();
+        let shared_neighbors = self.neighbors(v0).intersection(&self.neighbors(v1)).count();
+        if shared_faces == 0 || shared_neighbors != shared_faces{
+            return false;
+        }
+
+        for vert in [v0, v1]{
+            for face_id in &self.vert_faces[vert]{
+                if !self.face_alive[*face_id]{
+                    continue;
+                }
+                let tri = self.faces[*face_id];
+                if tri.contains(&v0) && tri.contains(&v1){
+                    continue; // removed by the collapse
+                }
+
+                let old = tri.map(|v| self.positions[v]);
+                let new = tri.map(|v| if v == v0 || v == v1 {collapse.target} else {self.positions[v]});
+
+                let old_normal = (old[1] - old[0]).cross(&(old[2] - old[0]));
+                let new_normal = (new[1] - new[0]).cross(&(new[2] - new[0]));
+                let (old_len, new_len) = (old_normal.norm(), new_normal.norm());
+                if new_len == 0.0{
+                    return false;
+                }
+                if old_len > 0.0 && old_normal.dot(&new_normal) / (old_len * new_len) < MIN_NORMAL_COS{
+                    return false;
+                }
+            }
+        }
+
+        return true;
+    }
+
+    fn collapse(&mut self, collapse: &Collapse){
+        let (v0, v1) = (collapse.v0, collapse.v1);
+
+        self.positions[v0] = collapse.target;
+        self.quadrics[v0] = self.quadrics[v0] + self.quadrics[v1];
+
+        let moved_faces = std::mem::take(&mut self.vert_faces[v1]);
+        for face_id in moved_faces{
+            if !self.face_alive[face_id]{
+                continue;
+            }
+            if self.faces[face_id].contains(&v0){
+                self.face_alive[face_id] = false;
+                self.alive_face_count -= 1;
+            }else{
+                for vert in self.faces[face_id].iter_mut(){
+                    if *vert == v1{
+                        *vert = v0;
+                    }
+                }
+                self.vert_faces[v0].push(face_id);
+            }
+        }
+
+        let face_alive = &self.face_alive;
+        self.vert_faces[v0].retain(|f| face_alive[*f]);
+        self.vert_alive[v1] = false;
+        self.stamps[v0] += 1;
+        self.stamps[v1] += 1;
+
+        for neighbor in self.neighbors(v0){
+            self.push_collapse(v0, neighbor);
+        }
+    }
+
+    fn run(&mut self, target_face_count: usize){
+        while self.alive_face_count > target_face_count{
+            let Some(collapse) = self.queue.pop() else {
+                break; // nothing more can be collapsed
+            };
+
+            let (v0, v1) = (collapse.v0, collapse.v1);
+            if !self.vert_alive[v0] || !self.vert_alive[v1]
+                || collapse.stamps != (self.stamps[v0], self.stamps[v1]){
+                continue; // stale entry
+            }
+
+            if self.is_collapse_valid(&collapse){
+                self.collapse(&collapse);
+            }
+        }
+    }
+
+    fn into_mesh(self) -> Mesh{
+        let mut new_ids: Vec<Option<usize>> = vec![None; self.positions.len()];
+        let mut verts: Vec<Vertex> = vec![];
+        let mut faces: Vec<Face> = vec![];
+
+        for (face_id, tri) in self.faces.iter().enumerate(){
+            if !self.face_alive[face_id]{
+                continue;
+            }
+            let mut face_verts = vec![];
+            for vert in tri{
+                let id = *new_ids[*vert].get_or_insert_with(|| {
+                    verts.push(Vertex{pos: self.positions[*vert].cast::<f32>(), normal: Vector3::zeros(), color: self.colors[*vert]});
+                    verts.len() - 1
+                });
+                face_verts.push(id);
+            }
+            faces.push(Face{verts: face_verts, hidden: false});
+        }
+
+        let mut mesh = Mesh{verts: verts, faces: faces, polygon_faces: None, is_triangulated: true,
+            bb_min: Vector3::zeros(), bb_max: Vector3::zeros()};
+        mesh.derrive_normals_from_faces().expect("simplified mesh is triangulated");
+        (mesh.bb_min, mesh.bb_max) = mesh.compute_bounds();
+        return mesh;
+    }
+}
+
+impl Mesh{
+    /// Reduces the mesh to around `target_face_count` triangles by quadric error edge collapses.
+    /// Vertex positions stay in the same space as the source, normals are re-derived.
+    pub fn simplify(&self, target_face_count: usize) -> Result<Mesh, String>{
+        if !self.is_triangulated{
+            return Err("Mesh is not triangulated".to_string());
+        }
+
+        let mut simplifier = Simplifier::new(self);
+        simplifier.run(target_face_count);
+        return Ok(simplifier.into_mesh());
+    }
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+
+    #[test]
+    fn simplified_sphere_reaches_target_and_stays_closed(){
+        let sphere = Mesh::load_obj(include_str!("../../assets/sphere.obj")).unwrap();
+        assert!(sphere.is_closed());
+
+        for target in [sphere.face_count() / 2, sphere.face_count() / 10]{
+            let simplified = sphere.simplify(target).unwrap();
+            assert!(simplified.face_count() <= target && simplified.face_count() + 2 >= target,
+                "{} faces for a target of {}", simplified.face_count(), target);
+            assert!(simplified.is_closed());
+            // still round, every vertex close to the unit sphere
+            assert!(simplified.verts.iter().all(|vert| (vert.pos.norm() - 1.0).abs() < 0.1));
+        }
+    }
+}
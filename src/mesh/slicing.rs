@@ -0,0 +1,288 @@
+use std::collections::HashMap;
+
+use nalgebra::Vector3;
+
+use super::Mesh;
+
+/// points with `normal · p = offset`, the normal side being positive
+#[derive(Clone, Copy, Debug)]
+pub struct Plane{
+    pub normal: Vector3<f32>,
+    pub offset: f32
+}
+
+impl Plane{
+    pub fn new(normal: Vector3<f32>, offset: f32) -> Result<Plane, String>{
+        let length = normal.norm();
+        if !(length > 0.0 && length.is_finite()){
+            return Err("Plane normal must not be zero".to_string());
+        }
+        return Ok(Plane{normal: normal / length, offset: offset / length});
+    }
+
+    pub fn signed_distance(&self, point: &Vector3<f32>) -> f32{
+        return self.normal.dot(point) - self.offset;
+    }
+
+    /// in-plane axes, x and y for a plane facing +z
+    pub fn basis(&self) -> (Vector3<f32>, Vector3<f32>){
+        let u = Vector3::y().cross(&self.normal).try_normalize(1e-6).unwrap_or(Vector3::x());
+        let v = self.normal.cross(&u);
+        return (u, v);
+    }
+}
+
+/// outline of a cross-section, closed polylines repeat no point
+#[derive(Clone, Debug)]
+pub struct Polyline{
+    pub points: Vec<Vector3<f32>>,
+    pub is_closed: bool
+}
+
+impl Mesh{
+    /// Intersects the visible triangles with the plane and chains the segments into polylines.
+    /// Closed meshes give closed loops, running counter-clockwise around material seen from the normal side.
+    pub fn slice(&self, plane: &Plane) -> Vec<Polyline>{
+        let (positions, remap) = self.weld_positions();
+        let distances: Vec<f32> = positions.iter().map(|pos| plane.signed_distance(pos)).collect();
+        return self.level_set(&positions, &remap, &distances);
+    }
+
+    /// Lines where the per-vertex `values` cross `level`, interpolated linearly along the edges.
+    /// Values of split vertices are averaged.
+    pub fn isolines(&self, values: &[f32], level: f32) -> Result<Vec<Polyline>, String>{
+        if values.len() != self.verts.len(){
+            return Err(format!("Expected {} values, got {}", self.verts.len(), values.len()));
+        }
+
+        let (positions, remap) = self.weld_positions();
+        let mut sums = vec![0.0f32; positions.len()];
+        let mut counts = vec![0u32; positions.len()];
+        for (value, welded_id) in values.iter().zip(&remap){
+            sums[*welded_id] += value;
+            counts[*welded_id] += 1;
+        }
+        let distances: Vec<f32> = sums.iter().zip(&counts).map(|(sum, count)| sum / *count as f32 - level).collect();
+        return Ok(self.level_set(&positions, &remap, &distances));
+    }
+
+    /// Zero crossings of `distances` (one per welded position) over the visible triangles, chained into polylines.
+    fn level_set(&self, positions: &[Vector3<f32>], remap: &[usize], distances: &[f32]) -> Vec<Polyline>{
+        // vertices exactly on the level count as positive, so every crossing is on an edge
+        let is_positive: Vec<bool> = distances.iter().map(|distance| *distance >= 0.0).collect();
+
+        let mut crossing_points: HashMap<(usize, usize), Vector3<f32>> = HashMap::new();
+        let mut crossing = |a: usize, b: usize| -> (usize, usize) {
+            let key = (a.min(b), a.max(b));
+            crossing_points.entry(key).or_insert_with(|| {
+                let (pos_a, pos_b) = (positions[key.0], positions[key.1]);
+                let (dist_a, dist_b) = (distances[key.0], distances[key.1]);
+                pos_a + (pos_b - pos_a) * (dist_a / (dist_a - dist_b))
+            });
+            key
+        };
+
+        // each segment goes from the edge leaving the positive side to the one entering it,
+        // neighbours share the edge in opposite directions, so the segments chain head to tail
+        let mut segments: Vec<((usize, usize), (usize, usize))> = vec![];
+        for face in self.faces.iter().filter(|face| !face.hidden){
+            let tri = [remap[face.verts[0]], remap[face.verts[1]], remap[face.verts[2]]];
+            if tri[0] == tri[1] || tri[1] == tri[2] || tri[2] == tri[0]{
+                continue; // degenerate
+            }
+            let (mut enter, mut exit) = (None, None);
+            for i in 0..3{
+                let (a, b) = (tri[i], tri[(i + 1) % 3]);
+                match (is_positive[a], is_positive[b]){
+                    (false, true) => enter = Some(crossing(a, b)),
+                    (true, false) => exit = Some(crossing(a, b)),
+                    _ => {}
+                }
+            }
+            if let (Some(enter), Some(exit)) = (enter, exit){
+                segments.push((exit, enter));
+            }
+        }
+
+        let mut segments_from: HashMap<(usize, usize), Vec<usize>> = HashMap::new();
+        let mut ends: HashMap<(usize, usize), usize> = HashMap::new();
+        for (id, (start, end)) in segments.iter().enumerate(){
+            segments_from.entry(*start).or_default().push(id);
+            *ends.entry(*end).or_insert(0) += 1;
+        }
+
+        let mut is_used = vec![false; segments.len()];
+        let mut polylines: Vec<Polyline> = vec![];
+
+        // open chains first, from starts nothing leads into, then whatever is left are loops
+        let heads: Vec<usize> = (0..segments.len()).filter(|id| !ends.contains_key(&segments[*id].0)).collect();
+        for first in heads.into_iter().chain(0..segments.len()){
+            if is_used[first]{
+                continue;
+            }
+
+            let start = segments[first].0;
+            let mut keys = vec![start];
+            let mut current = first;
+            let mut is_closed = false;
+            loop{
+                is_used[current] = true;
+                let end = segments[current].1;
+                if end == start{
+                    is_closed = true;
+                    break;
+                }
+                keys.push(end);
+
+                let next = segments_from.get(&end).and_then(|ids| ids.iter().find(|id| !is_used[**id]));
+                match next{
+                    Some(next) => current = *next,
+                    None => break // boundary of an open mesh
+                }
+            }
+
+            polylines.push(Polyline{points: keys.iter().map(|key| crossing_points[key]).collect(), is_closed: is_closed});
+        }
+
+        return polylines;
+    }
+}
+
+/// contour lines as positions and line indices
+pub fn create_section_line_buffers(polylines: &[Polyline]) -> (Vec<f32>, Vec<usize>){
+    let mut verts = vec![];
+    let mut indices = vec![];
+
+    for polyline in polylines{
+        let first = verts.len() / 3;
+        for point in &polyline.points{
+            verts.extend_from_slice(point.as_slice());
+        }
+        let count = polyline.points.len();
+        let segment_count = if polyline.is_closed {count} else {count.saturating_sub(1)};
+        for i in 0..segment_count{
+            indices.push(first + i);
+            indices.push(first + (i + 1) % count);
+        }
+    }
+
+    return (verts, indices);
+}
+
+/// Triangle fans over the closed loops. Overlapping fans don't form the section by themselves,
+/// they are meant for an even-odd stencil fill.
+pub fn create_section_cap_buffers(polylines: &[Polyline]) -> (Vec<f32>, Vec<usize>){
+    let mut verts = vec![];
+    let mut indices = vec![];
+
+    for polyline in polylines.iter().filter(|polyline| polyline.is_closed && polyline.points.len() >= 3){
+        let first = verts.len() / 3;
+        for point in &polyline.points{
+            verts.extend_from_slice(point.as_slice());
+        }
+        for i in 1..polyline.points.len() - 1{
+            indices.extend_from_slice(&[first, first + i, first + i + 1]);
+        }
+    }
+
+    return (verts, indices);
+}
+
+/// Cross-section as SVG, in model units on the plane's axes, closed loops filled even-odd.
+pub fn section_svg(polylines: &[Polyline], plane: &Plane) -> String{
+    let (u, v) = plane.basis();
+    // svg's y axis points down
+    let to_2d = |point: &Vector3<f32>| (point.dot(&u), -point.dot(&v));
+
+    let (mut min_x, mut min_y, mut max_x, mut max_y) = (f32::INFINITY, f32::INFINITY, f32::NEG_INFINITY, f32::NEG_INFINITY);
+    for point in polylines.iter().flat_map(|polyline| polyline.points.iter()){
+        let (x, y) = to_2d(point);
+        (min_x, min_y, max_x, max_y) = (min_x.min(x), min_y.min(y), max_x.max(x), max_y.max(y));
+    }
+    if min_x > max_x{
+        (min_x, min_y, max_x, max_y) = (0.0, 0.0, 0.0, 0.0);
+    }
+    let margin = (max_x - min_x).max(max_y - min_y).max(1e-3) * 0.05;
+    let stroke_width = margin / 10.0;
+
+    let mut closed_path = String::new();
+    let mut open_paths = String::new();
+    for polyline in polylines.iter().filter(|polyline| !polyline.points.is_empty()){
+        let mut path = String::new();
+        for (i, point) in polyline.points.iter().enumerate(){
+            let (x, y) = to_2d(point);
+            path.push_str(&format!("{}{} {} ", if i == 0 {"M"} else {"L"}, x, y));
+        }
+
+        if polyline.is_closed{
+            closed_path.push_str(&path);
+            closed_path.push_str("Z ");
+        }else{
+            open_paths.push_str(&format!("  <path d=\"{}\" fill=\"none\" stroke=\"red\" stroke-width=\"{}\"/>\n", path.trim_end(), stroke_width));
+        }
+    }
+
+    return format!("<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"{} {} {} {}\">\n\
+        \x20 <path d=\"{}\" fill=\"lightgray\" fill-rule=\"evenodd\" stroke=\"black\" stroke-width=\"{}\"/>\n\
+        {}</svg>\n",
+        min_x - margin, min_y - margin, max_x - min_x + 2.0 * margin, max_y - min_y + 2.0 * margin,
+        closed_path.trim_end(), stroke_width, open_paths);
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+    use crate::mesh::tests::UNIT_CUBE;
+
+    /// twice the signed area of the polygon seen from +z, positive counter-clockwise
+    fn shoelace(points: &[Vector3<f32>]) -> f32{
+        return (0..points.len()).map(|i| {
+            let (a, b) = (points[i], points[(i + 1) % points.len()]);
+            a.x * b.y - b.x * a.y
+        }).sum();
+    }
+
+    #[test]
+    fn cube_cut_through_the_middle_is_one_square(){
+        let cube = Mesh::load_obj(UNIT_CUBE).unwrap();
+        let plane = Plane::new(Vector3::z(), 0.0).unwrap();
+
+        let polylines = cube.slice(&plane);
+        assert_eq!(polylines.len(), 1);
+        let square = &polylines[0];
+        assert!(square.is_closed);
+        assert!(square.points.iter().all(|point| point.z.abs() < 1e-6 && (point.x.abs().max(point.y.abs()) - 0.5).abs() < 1e-6));
+        // side diagonals add points along the edges, the corners are all there once
+        let corner_count = square.points.iter().filter(|point| point.x.abs() > 0.5 - 1e-6 && point.y.abs() > 0.5 - 1e-6).count();
+        assert_eq!(corner_count, 4);
+        assert!((shoelace(&square.points) / 2.0 - 1.0).abs() < 1e-5, "area {}", shoelace(&square.points) / 2.0);
+
+        let svg = section_svg(&polylines, &plane);
+        assert!(svg.starts_with("<svg"));
+        assert!(svg.contains("<path d=\"M") && svg.contains(" Z\""), "{}", svg);
+        assert!(!svg.contains("fill=\"none\""), "no open polylines expected: {}", svg);
+    }
+
+    #[test]
+    fn plane_missing_the_mesh_gives_nothing(){
+        let cube = Mesh::load_obj(UNIT_CUBE).unwrap();
+        assert!(cube.slice(&Plane::new(Vector3::x(), 2.0).unwrap()).is_empty());
+        assert!(Plane::new(Vector3::zeros(), 1.0).is_err());
+    }
+
+    #[test]
+    fn isolines_of_a_linear_field_lie_on_its_plane(){
+        let sphere = Mesh::load_obj(include_str!("../../assets/sphere.obj")).unwrap();
+        let field = |pos: &Vector3<f32>| pos.x + 2.0 * pos.y - 0.5 * pos.z;
+        let values: Vec<f32> = sphere.verts.iter().map(|vert| field(&vert.pos)).collect();
+
+        for level in [-1.0, 0.0, 0.3]{
+            let isolines = sphere.isolines(&values, level).unwrap();
+            assert_eq!(isolines.len(), 1);
+            assert!(isolines[0].is_closed);
+            assert!(isolines[0].points.len() > 10);
+            assert!(isolines[0].points.iter().all(|point| (field(point) - level).abs() < 1e-5));
+        }
+        assert!(sphere.isolines(&values[1..], 0.0).is_err());
+    }
+}
@@ -0,0 +1,171 @@
+use std::collections::HashMap;
+
+use nalgebra::Vector3;
+
+use super::Mesh;
+
+/// pass-band frequency of the Taubin filter, sets the inflating μ step from λ
+const TAUBIN_PASS_BAND: f32 = 0.1;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SmoothingMethod{
+    Laplacian, // uniform umbrella weights
+    Cotangent, // cotangent weights, moves vertices along the surface less
+    Taubin // alternating λ/μ steps, keeps the volume
+}
+
+/// neighbors of every vertex with their weights
+type Adjacency = Vec<Vec<(usize, f32)>>;
+
+fn uniform_adjacency(vert_count: usize, triangles: &[[usize; 3]]) -> Adjacency{
+    let mut adjacency: Adjacency = vec![vec![]; vert_count];
+    for tri in triangles{
+        for i in 0..3{
+            let (a, b) = (tri[i], tri[(i + 1) % 3]);
+            if !adjacency[a].iter().any(|(n, _)| *n == b){
+                adjacency[a].push((b, 1.0));
+                adjacency[b].push((a, 1.0));
+            }
+        }
+    }
+    return adjacency;
+}
+
+fn cotangent_adjacency(positions: &[Vector3<f32>], triangles: &[[usize; 3]]) -> Adjacency{
+    let mut edge_weights: HashMap<(usize, usize), f32> = HashMap::new();
+
+    for tri in triangles{
+        for i in 0..3{
+            // the angle at corner i weights the opposite edge
+            let (a, b, c) = (tri[i], tri[(i + 1) % 3], tri[(i + 2) % 3]);
+            let (ab, ac) = (positions[b] - positions[a], positions[c] - positions[a]);
+            let sin_len = ab.cross(&ac).norm();
+            let cot = if sin_len > 0.0 {ab.dot(&ac) / sin_len} else {0.0};
+            *edge_weights.entry((b.min(c), b.max(c))).or_insert(0.0) += cot / 2.0;
+        }
+    }
+
+    let mut adjacency: Adjacency = vec![vec![]; positions.len()];
+    for ((a, b), weight) in edge_weights{
+        let weight = weight.max(0.0); // obtuse triangles give negative weights, which make the filter unstable
+        adjacency[a].push((b, weight));
+        adjacency[b].push((a, weight));
+    }
+    return adjacency;
+}
+
+/// moves every free vertex by `factor` towards the weighted average of its neighbors
+fn relax(positions: &[Vector3<f32>], adjacency: &Adjacency, factor: f32, pinned: &[bool]) -> Vec<Vector3<f32>>{
+    return positions.iter().enumerate().map(|(id, pos)| {
+        if pinned[id]{
+            return *pos;
+        }
+
+        let mut sum = Vector3::zeros();
+        let mut total_weight = 0.0;
+        for (neighbor, weight) in &adjacency[id]{
+            sum += positions[*neighbor] * *weight;
+            total_weight += weight;
+        }
+
+        if total_weight <= 0.0{
+            return *pos;
+        }
+        pos + (sum / total_weight - pos) * factor
+    }).collect();
+}
+
+impl Mesh{
+    /// Smooths vertex positions over `iterations` steps, `strength` being the λ step size in (0, 1].
+    /// Normals and bounds are recomputed afterwards.
+    pub fn smooth(&mut self, method: SmoothingMethod, iterations: u32, strength: f32, pin_boundary: bool) -> Result<(), String>{
+        if !self.is_triangulated{
+            return Err("Mesh is not triangulated".to_string());
+        }
+        if !(strength > 0.0 && strength <= 1.0){
+            return Err(format!("Smoothing strength must be in (0, 1], got {strength}"));
+        }
+
+        // weld split vertices, so seams move together
+        let (mut positions, remap) = self.weld_positions();
+
+        let triangles: Vec<[usize; 3]> = self.faces.iter()
+            .map(|face| [remap[face.verts[0]], remap[face.verts[1]], remap[face.verts[2]]])
+            .filter(|tri| tri[0] != tri[1] && tri[1] != tri[2] && tri[2] != tri[0])
+            .collect();
+
+        let mut pinned = vec![false; positions.len()];
+        if pin_boundary{
+            let mut edge_counts: HashMap<(usize, usize), u32> = HashMap::new();
+            for tri in &triangles{
+                for i in 0..3{
+                    let (a, b) = (tri[i], tri[(i + 1) % 3]);
+                    *edge_counts.entry((a.min(b), a.max(b))).or_insert(0) += 1;
+                }
+            }
+            for ((a, b), count) in edge_counts{
+                if count == 1{
+                    pinned[a] = true;
+                    pinned[b] = true;
+                }
+            }
+        }
+
+        let uniform = uniform_adjacency(positions.len(), &triangles);
+        let taubin_mu = 1.0 / (TAUBIN_PASS_BAND - 1.0 / strength);
+
+        for _ in 0..iterations{
+            positions = match method{
+                SmoothingMethod::Laplacian => relax(&positions, &uniform, strength, &pinned),
+                SmoothingMethod::Cotangent => {
+                    let cotangent = cotangent_adjacency(&positions, &triangles);
+                    relax(&positions, &cotangent, strength, &pinned)
+                },
+                SmoothingMethod::Taubin => {
+                    let shrunk = relax(&positions, &uniform, strength, &pinned);
+                    relax(&shrunk, &uniform, taubin_mu, &pinned)
+                }
+            };
+        }
+
+        for (vert, welded_id) in self.verts.iter_mut().zip(remap){
+            vert.pos = positions[welded_id];
+        }
+
+        self.derrive_normals_from_faces()?;
+        (self.bb_min, self.bb_max) = self.compute_bounds();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+
+    fn sphere() -> Mesh{
+        return Mesh::load_obj(include_str!("../../assets/sphere.obj")).unwrap();
+    }
+
+    #[test]
+    fn taubin_keeps_the_volume_laplacian_shrinks(){
+        let volume = sphere().signed_volume();
+
+        let mut laplacian = sphere();
+        laplacian.smooth(SmoothingMethod::Laplacian, 20, 0.5, false).unwrap();
+        let mut taubin = sphere();
+        taubin.smooth(SmoothingMethod::Taubin, 20, 0.5, false).unwrap();
+
+        let (laplacian_ratio, taubin_ratio) = (laplacian.signed_volume() / volume, taubin.signed_volume() / volume);
+        assert!(laplacian_ratio < 0.8, "laplacian kept {}", laplacian_ratio);
+        assert!((taubin_ratio - 1.0).abs() < 0.05, "taubin kept {}", taubin_ratio);
+    }
+
+    #[test]
+    fn cotangent_smoothing_keeps_the_mesh_closed(){
+        let mut mesh = sphere();
+        mesh.smooth(SmoothingMethod::Cotangent, 5, 0.5, true).unwrap();
+        assert!(mesh.is_closed());
+        assert!(mesh.verts.iter().all(|vert| vert.pos.iter().all(|c| c.is_finite())));
+        assert!(mesh.smooth(SmoothingMethod::Taubin, 1, 0.0, false).is_err());
+    }
+}
@@ -0,0 +1,446 @@
+use std::collections::{HashMap, HashSet};
+use std::f32::consts::PI;
+
+use nalgebra::Vector3;
+
+use super::{find_root, Face, Mesh, Vertex};
+
+pub const MAX_SUBDIVISION_LEVEL: u32 = 4;
+
+/// welded polygon mesh the subdivision schemes operate on
+struct Cage{
+    positions: Vec<Vector3<f32>>,
+    colors: Vec<Vector3<f32>>, // interpolated linearly, unlike the positions
+    faces: Vec<Vec<usize>>,
+    hidden_faces: Vec<bool>,
+    origins: Vec<usize>, // face of the source mesh each face comes from
+    creases: HashSet<(usize, usize)> // sharp edges, kept as (min, max)
+}
+
+fn edge_key(a: usize, b: usize) -> (usize, usize){
+    return if a < b {(a, b)} else {(b, a)};
+}
+
+impl Cage{
+    /// welds the mesh by position and marks borders, normal seams and edges sharper than `crease_angle_deg` as creases
+    fn from_mesh(mesh: &Mesh, crease_angle_deg: f32) -> Cage{
+        let (positions, remap) = mesh.weld_positions();
+
+        let mut colors: Vec<Vector3<f32>> = vec![Vertex::default_color(); positions.len()];
+        for (vert, welded_id) in mesh.verts.iter().zip(&remap).rev(){ // first vertex at a position wins
+            colors[*welded_id] = vert.color;
+        }
+
+        let source_faces = mesh.polygon_faces.as_ref().unwrap_or(&mesh.faces);
+
+        let mut faces: Vec<Vec<usize>> = vec![];
+        let mut hidden_faces: Vec<bool> = vec![];
+        let mut origins: Vec<usize> = vec![];
+        let mut original_faces: Vec<Vec<usize>> = vec![]; // same faces with unwelded ids, to find normal seams
+        let mut first_triangle = 0; // of the polygon's fan in `mesh.faces`
+        for face in source_faces{
+            let mut welded: Vec<usize> = vec![];
+            let mut original: Vec<usize> = vec![];
+            for vert in &face.verts{
+                if welded.last() != Some(&remap[*vert]) && welded.first() != Some(&remap[*vert]){
+                    welded.push(remap[*vert]);
+                    original.push(*vert);
+                }
+            }
+            if welded.len() >= 3{
+                faces.push(welded);
+                hidden_faces.push(face.hidden);
+                origins.push(first_triangle);
+                original_faces.push(original);
+            }
+            first_triangle += if mesh.polygon_faces.is_some() {face.verts.len().saturating_sub(2)} else {1};
+        }
+
+        let mut cage = Cage{positions: positions, colors: colors, faces: faces, hidden_faces: hidden_faces, origins: origins,
+            creases: HashSet::new()};
+
+        let crease_cos = crease_angle_deg.to_radians().cos();
+        for (edge, edge_faces) in cage.edge_faces(){
+            if edge_faces.len() != 2{
+                cage.creases.insert(edge); // border or non-manifold
+                continue;
+            }
+
+            let (f0, f1) = (edge_faces[0], edge_faces[1]);
+            let n0 = cage.face_normal(f0);
+            let n1 = cage.face_normal(f1);
+            if n0.norm() > 0.0 && n1.norm() > 0.0 && n0.normalize().dot(&n1.normalize()) < crease_cos{
+                cage.creases.insert(edge);
+                continue;
+            }
+
+            let original_id = |face: usize, welded: usize| -> usize {
+                let corner = cage.faces[face].iter().position(|v| *v == welded).unwrap();
+                original_faces[face][corner]
+            };
+            if original_id(f0, edge.0) != original_id(f1, edge.0) || original_id(f0, edge.1) != original_id(f1, edge.1){
+                cage.creases.insert(edge);
+            }
+        }
+
+        return cage;
+    }
+
+    fn edge_faces(&self) -> HashMap<(usize, usize), Vec<usize>>{
+        let mut edge_faces: HashMap<(usize, usize), Vec<usize>> = HashMap::new();
+        for (face_id, face) in self.faces.iter().enumerate(){
+            for i in 0..face.len(){
+                edge_faces.entry(edge_key(face[i], face[(i + 1) % face.len()])).or_default().push(face_id);
+            }
+        }
+        return edge_faces;
+    }
+
+    /// area weighted normal (Newell's method), works for non planar polygons
+    fn face_normal(&self, face_id: usize) -> Vector3<f32>{
+        let face = &self.faces[face_id];
+        let mut normal = Vector3::zeros();
+        for i in 0..face.len(){
+            let (a, b) = (self.positions[face[i]], self.positions[face[(i + 1) % face.len()]]);
+            normal += a.cross(&b);
+        }
+        return normal / 2.0;
+    }
+
+    /// crease neighbors of each vertex, decides between the smooth, crease and corner rules
+    fn crease_neighbors(&self) -> Vec<Vec<usize>>{
+        let mut crease_neighbors: Vec<Vec<usize>> = vec![vec![]; self.positions.len()];
+        for (a, b) in &self.creases{
+            crease_neighbors[*a].push(*b);
+            crease_neighbors[*b].push(*a);
+        }
+        return crease_neighbors;
+    }
+
+    fn catmull_clark(&self) -> Cage{
+        let vert_count = self.positions.len();
+        let edge_faces = self.edge_faces();
+        let crease_neighbors = self.crease_neighbors();
+
+        let mut positions: Vec<Vector3<f32>> = self.positions.clone();
+        let mut colors: Vec<Vector3<f32>> = self.colors.clone();
+
+        // face points
+        let mut face_point_ids: Vec<usize> = vec![];
+        let mut face_point_sums: Vec<Vector3<f32>> = vec![Vector3::zeros(); vert_count];
+        let mut face_counts: Vec<usize> = vec![0; vert_count];
+        for face in &self.faces{
+            let face_point = face.iter().map(|v| self.positions[*v]).sum::<Vector3<f32>>() / face.len() as f32;
+            face_point_ids.push(positions.len());
+            positions.push(face_point);
+            colors.push(face.iter().map(|v| self.colors[*v]).sum::<Vector3<f32>>() / face.len() as f32);
+            for vert in face{
+                face_point_sums[*vert] += face_point;
+                face_counts[*vert] += 1;
+            }
+        }
+
+        // edge points
+        let mut edge_point_ids: HashMap<(usize, usize), usize> = HashMap::new();
+        let mut midpoint_sums: Vec<Vector3<f32>> = vec![Vector3::zeros(); vert_count];
+        let mut valences: Vec<usize> = vec![0; vert_count];
+        for (edge, faces) in &edge_faces{
+            let midpoint = (self.positions[edge.0] + self.positions[edge.1]) / 2.0;
+            let edge_point = if self.creases.contains(edge) || faces.len() != 2{
+                midpoint
+            }else{
+                (midpoint * 2.0 + positions[face_point_ids[faces[0]]] + positions[face_point_ids[faces[1]]]) / 4.0
+            };
+            edge_point_ids.insert(*edge, positions.len());
+            positions.push(edge_point);
+            colors.push((self.colors[edge.0] + self.colors[edge.1]) / 2.0);
+
+            for vert in [edge.0, edge.1]{
+                midpoint_sums[vert] += midpoint;
+                valences[vert] += 1;
+            }
+        }
+
+        // vertex points
+        for vert in 0..vert_count{
+            let p = self.positions[vert];
+            let creases = &crease_neighbors[vert];
+            let n = valences[vert] as f32;
+
+            positions[vert] = if creases.len() > 2{
+                p // corner
+            }else if creases.len() == 2{
+                (self.positions[creases[0]] + p * 6.0 + self.positions[creases[1]]) / 8.0
+            }else if valences[vert] < 3 || face_counts[vert] == 0{
+                p
+            }else{
+                let f = face_point_sums[vert] / face_counts[vert] as f32;
+                let r = midpoint_sums[vert] / n;
+                (f + r * 2.0 + p * (n - 3.0)) / n
+            };
+        }
+
+        let mut faces: Vec<Vec<usize>> = vec![];
+        let mut hidden_faces: Vec<bool> = vec![];
+        let mut origins: Vec<usize> = vec![];
+        for (face_id, face) in self.faces.iter().enumerate(){
+            let k = face.len();
+            hidden_faces.extend(std::iter::repeat_n(self.hidden_faces[face_id], k));
+            origins.extend(std::iter::repeat_n(self.origins[face_id], k));
+            for i in 0..k{
+                let (prev, cur, next) = (face[(i + k - 1) % k], face[i], face[(i + 1) % k]);
+                faces.push(vec![
+                    cur,
+                    edge_point_ids[&edge_key(cur, next)],
+                    face_point_ids[face_id],
+                    edge_point_ids[&edge_key(prev, cur)]
+                ]);
+            }
+        }
+
+        return Cage{positions: positions, colors: colors, faces: faces, hidden_faces: hidden_faces, origins: origins,
+            creases: self.split_creases(&edge_point_ids)};
+    }
+
+    fn loop_step(&self) -> Cage{
+        let vert_count = self.positions.len();
+        let edge_faces = self.edge_faces();
+        let crease_neighbors = self.crease_neighbors();
+
+        let mut positions: Vec<Vector3<f32>> = self.positions.clone();
+        let mut colors: Vec<Vector3<f32>> = self.colors.clone();
+        let mut neighbors: Vec<Vec<usize>> = vec![vec![]; vert_count];
+
+        // edge points
+        let mut edge_point_ids: HashMap<(usize, usize), usize> = HashMap::new();
+        for (edge, faces) in &edge_faces{
+            let (a, b) = (self.positions[edge.0], self.positions[edge.1]);
+            let edge_point = if self.creases.contains(edge) || faces.len() != 2{
+                (a + b) / 2.0
+            }else{
+                let opposite = |face_id: usize| -> Vector3<f32> {
+                    let other = self.faces[face_id].iter().find(|v| **v != edge.0 && **v != edge.1).unwrap();
+                    self.positions[*other]
+                };
+                (a + b) * 3.0 / 8.0 + (opposite(faces[0]) + opposite(faces[1])) / 8.0
+            };
+            edge_point_ids.insert(*edge, positions.len());
+            positions.push(edge_point);
+            colors.push((self.colors[edge.0] + self.colors[edge.1]) / 2.0);
+
+            neighbors[edge.0].push(edge.1);
+            neighbors[edge.1].push(edge.0);
+        }
+
+        // vertex points
+        for vert in 0..vert_count{
+            let p = self.positions[vert];
+            let creases = &crease_neighbors[vert];
+            let n = neighbors[vert].len();
+
+            positions[vert] = if creases.len() > 2{
+                p // corner
+            }else if creases.len() == 2{
+                p * 3.0 / 4.0 + (self.positions[creases[0]] + self.positions[creases[1]]) / 8.0
+            }else if n < 3{
+                p
+            }else{
+                let n = n as f32;
+                let beta = (5.0 / 8.0 - (3.0 / 8.0 + (2.0 * PI / n).cos() / 4.0).powi(2)) / n;
+                let neighbor_sum = neighbors[vert].iter().map(|v| self.positions[*v]).sum::<Vector3<f32>>();
+                p * (1.0 - n * beta) + neighbor_sum * beta
+            };
+        }
+
+        let mut faces: Vec<Vec<usize>> = vec![];
+        let mut hidden_faces: Vec<bool> = vec![];
+        let mut origins: Vec<usize> = vec![];
+        for (face_id, face) in self.faces.iter().enumerate(){
+            hidden_faces.extend([self.hidden_faces[face_id]; 4]);
+            origins.extend([self.origins[face_id]; 4]);
+            let (v0, v1, v2) = (face[0], face[1], face[2]);
+            let e01 = edge_point_ids[&edge_key(v0, v1)];
+            let e12 = edge_point_ids[&edge_key(v1, v2)];
+            let e20 = edge_point_ids[&edge_key(v2, v0)];
+
+            faces.push(vec![v0, e01, e20]);
+            faces.push(vec![v1, e12, e01]);
+            faces.push(vec![v2, e20, e12]);
+            faces.push(vec![e01, e12, e20]);
+        }
+
+        return Cage{positions: positions, colors: colors, faces: faces, hidden_faces: hidden_faces, origins: origins,
+            creases: self.split_creases(&edge_point_ids)};
+    }
+
+    /// each crease edge continues as the two halves around its edge point
+    fn split_creases(&self, edge_point_ids: &HashMap<(usize, usize), usize>) -> HashSet<(usize, usize)>{
+        let mut creases = HashSet::new();
+        for edge in &self.creases{
+            let edge_point = edge_point_ids[edge];
+            creases.insert(edge_key(edge.0, edge_point));
+            creases.insert(edge_key(edge_point, edge.1));
+        }
+        return creases;
+    }
+
+    /// Triangulates the cage, vertices get split along creases so smooth shading keeps them sharp.
+    /// Also gives the source face of each triangle.
+    fn into_mesh(self) -> (Mesh, Vec<usize>){
+        let mut triangles: Vec<[usize; 3]> = vec![];
+        let mut hidden_triangles: Vec<bool> = vec![];
+        let mut origins: Vec<usize> = vec![];
+        for (face_id, face) in self.faces.iter().enumerate(){
+            for i in 1..face.len() - 1{
+                triangles.push([face[0], face[i], face[i + 1]]);
+                hidden_triangles.push(self.hidden_faces[face_id]);
+                origins.push(self.origins[face_id]);
+            }
+        }
+
+        // union corners (triangle*3 + corner) sharing a vertex across smooth edges
+        let mut parents: Vec<usize> = (0..triangles.len() * 3).collect();
+        let mut edge_corners: HashMap<(usize, usize), Vec<(usize, usize)>> = HashMap::new(); // edge -> (corner of min, corner of max)
+        for (tri_id, tri) in triangles.iter().enumerate(){
+            for i in 0..3{
+                let j = (i + 1) % 3;
+                let (corner_a, corner_b) = (tri_id * 3 + i, tri_id * 3 + j);
+                let corners = if tri[i] < tri[j] {(corner_a, corner_b)} else {(corner_b, corner_a)};
+                edge_corners.entry(edge_key(tri[i], tri[j])).or_default().push(corners);
+            }
+        }
+        for (edge, corners) in &edge_corners{
+            if corners.len() != 2 || self.creases.contains(edge){
+                continue;
+            }
+            for (a, b) in [(corners[0].0, corners[1].0), (corners[0].1, corners[1].1)]{
+                let (root_a, root_b) = (find_root(&mut parents, a), find_root(&mut parents, b));
+                parents[root_a] = root_b;
+            }
+        }
+
+        let mut verts: Vec<Vertex> = vec![];
+        let mut faces: Vec<Face> = vec![];
+        let mut root_vert_ids: HashMap<usize, usize> = HashMap::new();
+
+        for (tri_id, tri) in triangles.iter().enumerate(){
+            let (p0, p1, p2) = (self.positions[tri[0]], self.positions[tri[1]], self.positions[tri[2]]);
+            let tri_normal = (p1 - p0).cross(&(p2 - p0));
+
+            let mut face_verts = vec![];
+            for (i, vert) in tri.iter().enumerate(){
+                let root = find_root(&mut parents, tri_id * 3 + i);
+                let id = *root_vert_ids.entry(root).or_insert_with(|| {
+                    verts.push(Vertex{pos: self.positions[*vert], normal: Vector3::zeros(), color: self.colors[*vert]});
+                    verts.len() - 1
+                });
+                verts[id].normal += tri_normal;
+                face_verts.push(id);
+            }
+            faces.push(Face{verts: face_verts, hidden: hidden_triangles[tri_id]});
+        }
+
+        for vert in &mut verts{
+            vert.normal = vert.normal.try_normalize(0.0).unwrap_or(Vector3::zeros());
+        }
+
+        let mut mesh = Mesh{verts: verts, faces: faces, polygon_faces: None, is_triangulated: true,
+            bb_min: Vector3::zeros(), bb_max: Vector3::zeros()};
+        (mesh.bb_min, mesh.bb_max) = mesh.compute_bounds();
+        return (mesh, origins);
+    }
+}
+
+impl Mesh{
+    /// Subdivides the polygons as loaded, Loop for all-triangle meshes and Catmull-Clark otherwise.
+    /// Borders, normal seams and edges sharper than `crease_angle_deg` are kept as creases.
+    /// Gives the subdivided mesh and for each of its faces the face of this mesh it comes from.
+    pub fn subdivide(&self, level: u32, crease_angle_deg: f32) -> Result<(Mesh, Vec<usize>), String>{
+        let polygons = self.polygon_faces.as_ref().unwrap_or(&self.faces);
+
+        if polygons.iter().all(|face| face.verts.len() == 3){
+            return self.subdivide_loop(level, crease_angle_deg);
+        }
+        return self.subdivide_catmull_clark(level, crease_angle_deg);
+    }
+
+    pub fn subdivide_loop(&self, level: u32, crease_angle_deg: f32) -> Result<(Mesh, Vec<usize>), String>{
+        if level > MAX_SUBDIVISION_LEVEL{
+            return Err(format!("Subdivision level {level} exceeds the limit of {MAX_SUBDIVISION_LEVEL}"));
+        }
+
+        let mut cage = Cage::from_mesh(self, crease_angle_deg);
+        if cage.faces.iter().any(|face| face.len() != 3){
+            return Err("Loop subdivision needs a triangle mesh".to_string());
+        }
+
+        for _ in 0..level{
+            cage = cage.loop_step();
+        }
+        return Ok(cage.into_mesh());
+    }
+
+    pub fn subdivide_catmull_clark(&self, level: u32, crease_angle_deg: f32) -> Result<(Mesh, Vec<usize>), String>{
+        if level > MAX_SUBDIVISION_LEVEL{
+            return Err(format!("Subdivision level {level} exceeds the limit of {MAX_SUBDIVISION_LEVEL}"));
+        }
+
+        let mut cage = Cage::from_mesh(self, crease_angle_deg);
+        for _ in 0..level{
+            cage = cage.catmull_clark();
+        }
+        return Ok(cage.into_mesh());
+    }
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+    use crate::mesh::tests::UNIT_CUBE;
+
+    #[test]
+    fn loop_quadruples_the_faces(){
+        let mut sphere = Mesh::load_obj(include_str!("../../assets/sphere.obj")).unwrap();
+        sphere.polygon_faces = None; // subdivide its triangulation, the file is mostly quads
+        for level in 1..=2{
+            let subdivided = sphere.subdivide_loop(level, 180.0).unwrap().0;
+            assert_eq!(subdivided.face_count(), sphere.face_count() * 4usize.pow(level));
+            assert!(subdivided.is_closed());
+            // smooth surface pulled slightly inside the control cage
+            assert!(subdivided.verts.iter().all(|vert| vert.pos.norm() <= 1.0 + 1e-4 && vert.pos.norm() > 0.97));
+        }
+        assert!(sphere.subdivide_loop(MAX_SUBDIVISION_LEVEL + 1, 180.0).is_err());
+    }
+
+    #[test]
+    fn catmull_clark_rounds_a_cube_unless_creased(){
+        let cube = Mesh::load_obj(UNIT_CUBE).unwrap();
+
+        let smooth = cube.subdivide(1, 180.0).unwrap().0;
+        assert_eq!(smooth.face_count(), 24 * 2); // quads, split in two triangles each
+        assert!(smooth.is_closed());
+        // the corners, sqrt(0.75) out, are pulled in to about the distance of the edge points
+        let farthest = smooth.verts.iter().map(|vert| vert.pos.norm()).fold(0.0, f32::max);
+        assert!(farthest < 0.6, "vertex left at {}", farthest);
+
+        // every edge is at 90 degrees, so all of them are creases and the box keeps its shape
+        let creased = cube.subdivide(2, 30.0).unwrap().0;
+        assert_eq!(creased.face_count(), 96 * 2);
+        assert!((creased.signed_volume() - 1.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn subdivided_faces_know_where_they_come_from(){
+        let cube = Mesh::load_obj(UNIT_CUBE).unwrap();
+        let (creased, origins) = cube.subdivide(1, 30.0).unwrap();
+        assert_eq!(origins.len(), creased.face_count());
+
+        let normal = |mesh: &Mesh, face: usize| {
+            let [a, b, c] = [0, 1, 2].map(|i| mesh.verts[mesh.faces[face].verts[i]].pos);
+            (b - a).cross(&(c - a)).normalize()
+        };
+        for (face, origin) in origins.iter().enumerate(){
+            assert!(normal(&creased, face).dot(&normal(&cube, *origin)) > 0.999);
+        }
+    }
+}
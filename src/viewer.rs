@@ -0,0 +1,3020 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::marker::PhantomData;
+
+use wasm_bindgen::prelude::*;
+use web_sys::{HtmlCanvasElement, WebGl2RenderingContext};
+use web_sys::{window, Response};
+use wasm_bindgen_futures::JsFuture;
+use nalgebra::{Matrix3, Matrix4, Point2, Point3, Rotation3, Translation3, UnitQuaternion, Vector2, Vector3};
+
+use crate::logging::{self, log};
+use crate::mesh::{self, create_box_line_buffers, CurvatureKind, DeviationStats, IcpMetric, IcpOptions, IcpReport, create_hull_line_buffers, create_sphere_line_buffers, BoundingSphere, OrientedBox, Bvh, ComponentInfo, MassProperties, Mesh, PivotCenter, SmoothingMethod, SnapMode, SurfacePoint, Plane, Polyline};
+
+mod shaders;
+mod colormap;
+use colormap::{fit_range, ColorMap};
+mod lod;
+mod selection;
+mod dimensions;
+mod scene;
+use scene::Scene;
+mod backend;
+use backend::{Backend, BufferId, Capability, CompareFunc, Face, Indices, Primitive, ProgramId, ProgramKind, StencilOp, UniformValue, VertexArrayId, VALUES_PER_INSTANCE, WebGlBackend};
+mod uniforms;
+#[cfg(test)]
+mod golden;
+use uniforms::{std140_layout, std140_values, FrameUniforms, LightUniforms, UniformBlock};
+use dimensions::{Dimension, DimensionKind, DimensionTool};
+use selection::{Selection, SelectionMode};
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum ShadingType{
+    Smooth,
+    Flat,
+    Wireframe,
+    FalseColor // smooth shading colored by per-vertex values
+}
+
+/// fraction of the values at either end left out of a fitted false color range
+const FALSE_COLOR_OUTLIER_FRACTION: f32 = 0.02;
+
+/// per-vertex values false color shading can show
+#[derive(Clone, PartialEq)]
+enum ScalarSource{
+    Curvature(CurvatureKind),
+    Field(String) // a scalar field set from js
+}
+
+/// what false color shading shows and how
+#[derive(Clone)]
+struct FalseColor{
+    source: ScalarSource,
+    color_map: ColorMap,
+    range: Option<(f32, f32)>, // fitted to the values when None
+    fitted_range: (f32, f32),
+    isoline_levels: Vec<f32>
+}
+
+impl FalseColor{
+    fn new() -> FalseColor{
+        return FalseColor{source: ScalarSource::Curvature(CurvatureKind::Mean), color_map: ColorMap::Diverging,
+            range: None, fitted_range: (0.0, 1.0), isoline_levels: vec![]};
+    }
+
+    fn range(&self) -> (f32, f32){
+        return self.range.unwrap_or(self.fitted_range);
+    }
+
+    /// shows other values, the range is fitted again
+    fn set_source(&mut self, source: ScalarSource){
+        if source != self.source{
+            self.source = source;
+            self.range = None;
+        }
+    }
+}
+
+/// bounding volume drawn when bounds are shown
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum BoundsKind{
+    Aabb,
+    Obb, // oriented box, close to the minimum volume one
+    Sphere,
+    Hull
+}
+
+struct RenderedMesh{
+    mesh: Mesh,
+    base_mesh: Option<Mesh>, // mesh as loaded, while `mesh` holds its subdivided version
+    base_faces: Vec<usize>, // face of `base_mesh` each face of the subdivided `mesh` comes from
+    subdivision_level: u32,
+    shading: ShadingType,
+    color: Vector3<f32>, // multiplies the vertex colors
+    instances: Vec<Instance>, // copies drawn instead of the mesh itself, when there are any
+    instance_buffer: Option<InstanceBuffer>,
+    false_color: FalseColor,
+    scalar_fields: BTreeMap<String, Vec<f32>>, // named per-vertex values of `mesh`
+    isoline_gl_buffers: Vec<GLBuffers>,
+    mesh_gl_buffers: Vec<GLBuffers>,
+    bounds_kind: BoundsKind,
+    bb_gl_buffers: Vec<GLBuffers>, // bounding volume gl buffers
+    lods: Vec<MeshLod>, // reduced levels of detail, coarser with each index
+    active_lod: usize, // 0 is full detail, i is lods[i-1]
+    bvh: Bvh, // over the full detail mesh, for picking and distance queries
+    selection: Selection,
+    selection_gl_buffers: Vec<GLBuffers>,
+    section: Option<Section> // contour mode, replaces the surface while set
+}
+
+/// world space plane cutting away the side its normal points to
+struct ClipPlane{
+    plane: Plane,
+    cap_gl_buffers: GLBuffers // quad on the plane, drawn where the cut opens a solid
+}
+
+/// mesh the displayed one is compared against, drawn as a wireframe
+struct Reference{
+    mesh: Mesh,
+    bvh: Bvh, // spatial index for the distance queries
+    gl_buffers: Vec<GLBuffers>
+}
+
+/// outline of the mesh cut by a plane
+struct Section{
+    plane: Plane,
+    is_capped: bool, // fills the inside of closed loops
+    polylines: Vec<Polyline>,
+    line_gl_buffers: Vec<GLBuffers>,
+    cap_gl_buffers: Vec<GLBuffers>
+}
+
+struct MeshLod{
+    mesh: Mesh,
+    gl_buffers: Vec<GLBuffers>
+}
+
+/// copy of a mesh drawn by instancing, placed relative to its node
+#[derive(Clone, Copy)]
+struct Instance{
+    transform: Matrix4<f32>,
+    normal_matrix: Matrix3<f32>, // inverse transpose of the transform's rotation and scale
+    color: Vector3<f32> // multiplies the mesh colors
+}
+
+impl Instance{
+    /// fails for a transform with non-finite values or one flattening the mesh
+    pub fn new(transform: Matrix4<f32>, color: Vector3<f32>) -> Result<Instance, String>{
+        if transform.iter().any(|value| !value.is_finite()){
+            return Err("Instance transform has non-finite values".to_string());
+        }
+        let normal_matrix = transform.fixed_view::<3, 3>(0, 0).try_inverse()
+            .filter(|inverse| inverse.iter().all(|value| value.is_finite()))
+            .ok_or("Instance transform is not invertible")?.transpose();
+        return Ok(Instance{transform: transform, normal_matrix: normal_matrix, color: color});
+    }
+}
+
+/// per-instance attributes, a column major matrix, a color and a normal matrix each
+struct InstanceBuffer{
+    buffer: BufferId,
+    count: i32
+}
+
+impl InstanceBuffer{
+    pub fn create(instances: &[Instance], gl: &dyn Backend) -> Result<InstanceBuffer, String>{
+        let mut values: Vec<f32> = Vec::with_capacity(instances.len() * VALUES_PER_INSTANCE);
+        for instance in instances{
+            values.extend_from_slice(instance.transform.as_slice());
+            values.extend_from_slice(instance.color.as_slice());
+            values.extend_from_slice(instance.normal_matrix.as_slice());
+        }
+
+        Ok(InstanceBuffer{buffer: gl.create_instance_buffer(&values)?, count: instances.len() as i32})
+    }
+
+    pub fn delete(&self, gl: &dyn Backend){
+        gl.delete_buffer(self.buffer);
+    }
+}
+
+/// buffer holding a uniform block, bound to the block's binding point for good
+struct UniformBuffer<B: UniformBlock>{
+    buffer: BufferId,
+    block: PhantomData<B>
+}
+
+impl<B: UniformBlock> UniformBuffer<B>{
+    pub fn create(gl: &dyn Backend) -> Result<UniformBuffer<B>, String>{
+        let (_, size) = std140_layout(B::MEMBERS);
+        Ok(UniformBuffer{buffer: gl.create_uniform_buffer(B::BINDING, size)?, block: PhantomData})
+    }
+
+    /// every program declaring the block reads the new values
+    pub fn upload(&self, gl: &dyn Backend, block: &B){
+        gl.update_uniform_buffer(self.buffer, &std140_values(block));
+    }
+}
+
+/// vertices and 16-bit indices of one chunk of a mesh split for small index types
+type Chunk = (Vec<f32>, Vec<u16>);
+
+/// vertex and element buffers with the vertex array reading them, set up once on creation
+struct GLBuffers{
+    vertex_array: VertexArrayId
+}
+
+impl GLBuffers{
+    pub fn delete(&self, gl: &dyn Backend){
+        gl.delete_vertex_array(self.vertex_array);
+    }
+
+    pub fn split_into_chunks(vertices: &[f32], indices: &[usize], 
+        values_per_vertex: usize, primitive_size: usize) -> Result<Vec<Chunk>, String>{
+        let mut chunks: Vec<Chunk> = vec![];
+
+        if vertices.len() / values_per_vertex <= u16::MAX as usize{ // no need for split, inside a limit
+            let indices_u16: Vec<u16> = indices.iter().map(|&i| i as u16).collect();
+            chunks.push((vertices.to_vec(), indices_u16));
+        }else{
+            let preferred_chunk_size = u16::MAX as usize;
+                
+            let mut chunk_verts: Vec<f32> = vec![];
+            let mut chunk_indices: Vec<u16> = vec![];
+            let mut vert_id_remap: HashMap<usize, u16> = HashMap::new();// maps to indeces in chunk_verts
+
+            for i in (0..indices.len()).step_by(primitive_size){ // go by primitives
+                let old_vert_ids = &indices[i..i + primitive_size];
+
+                for old_vert_id in old_vert_ids{
+                    // remap to new id
+                   let new_vert_id = *vert_id_remap.entry(*old_vert_id).or_insert_with(|| {
+                        let new_id = (chunk_verts.len() / values_per_vertex) as u16;
+                        let start = old_vert_id*values_per_vertex;
+                        chunk_verts.extend_from_slice(&vertices[start..(start + values_per_vertex)]);
+                        return  new_id;
+                    });
+                    chunk_indices.push(new_vert_id);
+                }
+
+                if chunk_verts.len() / values_per_vertex + 3 > preferred_chunk_size{ // split chunk
+                    chunks.push((std::mem::take(&mut chunk_verts), std::mem::take(&mut chunk_indices)));
+                    vert_id_remap.clear();
+                }
+            }
+
+            if !chunk_indices.is_empty(){//push last chunk
+                chunks.push((chunk_verts, chunk_indices));
+            }
+        }
+        
+        if chunks.len() > 1{
+            log(&format!("Split {}v {}f into chunks:", vertices.len()/values_per_vertex, indices.len()/3));
+            for chunk in &chunks{
+                log(&format!("{}v {}f", chunk.0.len() / values_per_vertex, chunk.1.len() / 3));
+            }  
+        }
+
+        return Ok(chunks);
+    }
+
+    /// Buffers for the primitives, with 16 bit indices when the vertices are few enough and 32 bit
+    /// ones otherwise. Only past the backend's largest index the mesh is split into 16 bit chunks.
+    pub fn create_indexed(vertices: &[f32], indices: &[usize], values_per_vertex: usize, primitive_size: usize,
+        gl: &dyn Backend) -> Result<Vec<GLBuffers>, String>{
+        let vertex_count = vertices.len() / values_per_vertex;
+        if vertex_count <= u16::MAX as usize{
+            let indices_u16: Vec<u16> = indices.iter().map(|&i| i as u16).collect();
+            return Ok(vec![GLBuffers::create(vertices, &indices_u16, values_per_vertex, gl)?]);
+        }
+
+        if vertex_count as u64 <= gl.max_element_index() as u64{
+            let indices_u32: Vec<u32> = indices.iter().map(|&i| i as u32).collect();
+            return Ok(vec![GLBuffers::create_u32(vertices, &indices_u32, values_per_vertex, gl)?]);
+        }
+
+        let mut chunks = vec![];
+        for chunk in GLBuffers::split_into_chunks(vertices, indices, values_per_vertex, primitive_size)?{
+            chunks.push(GLBuffers::create(&chunk.0, &chunk.1, values_per_vertex, gl)?);
+        }
+        return Ok(chunks);
+    }
+
+    /// vertices are positions, or positions, normals and colors with 9 values each
+    pub fn create(vertices: &[f32], indices: &[u16], values_per_vertex: usize, gl: &dyn Backend) -> Result<GLBuffers, String>{
+        Ok(GLBuffers{vertex_array: gl.create_vertex_array(vertices, values_per_vertex, Indices::U16(indices))?})
+    }
+
+    pub fn create_u32(vertices: &[f32], indices: &[u32], values_per_vertex: usize, gl: &dyn Backend) -> Result<GLBuffers, String>{
+        Ok(GLBuffers{vertex_array: gl.create_vertex_array(vertices, values_per_vertex, Indices::U32(indices))?})
+    }
+
+    /// makes the vertex array read the instance attributes from `instances`, or place the single white copy without
+    pub fn set_instance_buffer(&self, gl: &dyn Backend, instances: Option<&InstanceBuffer>){
+        gl.set_instance_buffer(self.vertex_array, instances.map(|instances| instances.buffer));
+    }
+
+    pub fn draw(&self, gl: &dyn Backend, primitive: Primitive){
+        gl.draw(self.vertex_array, primitive, None);
+    }
+
+    /// draws once per instance of the buffer given to `set_instance_buffer`
+    pub fn draw_instanced(&self, gl: &dyn Backend, primitive: Primitive, instances: &InstanceBuffer){
+        gl.draw(self.vertex_array, primitive, Some(instances.count));
+    }
+}
+
+impl RenderedMesh{
+    pub fn new(gl: &dyn Backend, mesh: Mesh, shading: ShadingType, false_color: FalseColor) -> Result<RenderedMesh, String>{
+        let bvh = Bvh::build(&mesh);
+        let selection = Selection::new(SelectionMode::Faces, &mesh);
+        let mut rendered_mesh = RenderedMesh { mesh: mesh, base_mesh: None, base_faces: vec![], subdivision_level: 0, shading: shading, color: Vector3::new(1.0, 1.0, 1.0),
+            instances: vec![], instance_buffer: None, false_color: false_color,
+            scalar_fields: BTreeMap::new(), isoline_gl_buffers: vec![],
+            mesh_gl_buffers: vec![], bounds_kind: BoundsKind::Aabb, bb_gl_buffers: vec![], lods: vec![], active_lod: 0, bvh: bvh,
+            selection: selection, selection_gl_buffers: vec![], section: None };
+
+        rendered_mesh.rebuild_lods()?;
+        rendered_mesh.reload_gl_buffers(gl)?;
+
+        return Ok(rendered_mesh);
+    }
+
+    /// "smooth", "flat", "wireframe" or "false_color"
+    pub fn change_shading(&mut self, gl: &dyn Backend, shading: &str) -> Result<(), String>{
+        let shading = match shading{
+            "smooth" => ShadingType::Smooth,
+            "flat" => ShadingType::Flat,
+            "wireframe" => ShadingType::Wireframe,
+            "false_color" => ShadingType::FalseColor,
+            _ => return Err(format!("Unrecognized shading: {}", shading))
+        };
+        if self.shading != shading{
+            self.shading = shading;
+            self.reload_gl_buffers(gl)?;
+        }
+        Ok(())
+    }
+
+    /// draws the mesh once per instance from now on, or on its own again without any
+    pub fn set_instances(&mut self, gl: &dyn Backend, instances: Vec<Instance>) -> Result<(), String>{
+        self.delete_instance_buffer(gl);
+        if !instances.is_empty(){
+            self.instance_buffer = Some(InstanceBuffer::create(&instances, gl)?);
+        }
+        self.instances = instances;
+        self.attach_instance_buffer(gl);
+        Ok(())
+    }
+
+    /// points the surface vertex arrays, every level of detail's, at the instance buffer
+    fn attach_instance_buffer(&self, gl: &dyn Backend){
+        for chunk in self.mesh_gl_buffers.iter().chain(self.lods.iter().flat_map(|lod| &lod.gl_buffers)){
+            chunk.set_instance_buffer(gl, self.instance_buffer.as_ref());
+        }
+    }
+
+    pub fn delete_instance_buffer(&mut self, gl: &dyn Backend){
+        if let Some(instance_buffer) = self.instance_buffer.take(){
+            instance_buffer.delete(gl);
+        }
+    }
+
+    pub fn delete_mesh_gl_buffers(&mut self, gl: &dyn Backend){
+        for chunk in &self.mesh_gl_buffers{
+            chunk.delete(gl);
+        }
+
+        self.mesh_gl_buffers.clear();
+
+        for lod in &mut self.lods{
+            for chunk in &lod.gl_buffers{
+                chunk.delete(gl);
+            }
+            lod.gl_buffers.clear();
+        }
+
+        for chunk in &self.bb_gl_buffers{
+            chunk.delete(gl);
+        }
+        self.bb_gl_buffers.clear();
+
+        for chunk in &self.isoline_gl_buffers{
+            chunk.delete(gl);
+        }
+        self.isoline_gl_buffers.clear();
+
+        for chunk in &self.selection_gl_buffers{
+            chunk.delete(gl);
+        }
+        self.selection_gl_buffers.clear();
+
+        if let Some(section) = &mut self.section{
+            for chunk in section.line_gl_buffers.iter().chain(&section.cap_gl_buffers){
+                chunk.delete(gl);
+            }
+            section.line_gl_buffers.clear();
+            section.cap_gl_buffers.clear();
+        }
+    }
+
+    /// regenerates everything derived from the full detail mesh after it was modified
+    pub fn mesh_changed(&mut self, gl: &dyn Backend) -> Result<(), String>{
+        self.bvh.update(&self.mesh);
+        self.selection.mesh_changed(&self.mesh);
+
+        let vert_count = self.mesh.vert_count();
+        self.scalar_fields.retain(|name, values| {
+            if values.len() != vert_count{
+                log(&format!("Dropped scalar field {}, the vertices changed", name));
+            }
+            values.len() == vert_count
+        });
+        if let ScalarSource::Field(name) = &self.false_color.source
+            && !self.scalar_fields.contains_key(name){
+            self.false_color.set_source(ScalarSource::Curvature(CurvatureKind::Mean));
+        }
+        self.rebuild_lods()?;
+        self.reload_gl_buffers(gl)
+    }
+
+    /// replaces the displayed mesh with the loaded one subdivided `level` times, 0 restores it
+    pub fn set_subdivision(&mut self, gl: &dyn Backend, level: u32, crease_angle_deg: f32) -> Result<(), String>{
+        if level == 0{
+            if let Some(base_mesh) = self.base_mesh.take(){
+                self.mesh = base_mesh;
+            }
+            self.base_faces.clear();
+        }else{
+            let base_mesh = self.base_mesh.as_ref().unwrap_or(&self.mesh);
+            let (subdivided, base_faces) = base_mesh.subdivide(level, crease_angle_deg)?;
+            self.base_faces = base_faces;
+            let previous = std::mem::replace(&mut self.mesh, subdivided);
+            if self.base_mesh.is_none(){
+                self.base_mesh = Some(previous);
+            }
+        }
+
+        self.subdivision_level = level;
+        self.mesh_changed(gl)
+    }
+
+    /// Ids in the loaded mesh of the given components of the displayed one, they differ while subdivided
+    /// since components are ordered by face count.
+    pub fn loaded_components(&self, components: &[usize]) -> Result<Vec<usize>, String>{
+        let (displayed, count) = self.mesh.face_components();
+        if let Some(id) = components.iter().find(|id| **id >= count){
+            return Err(format!("No component {}", id));
+        }
+        let Some(base_mesh) = &self.base_mesh else {
+            return Ok(components.to_vec());
+        };
+
+        let (loaded, _) = base_mesh.face_components();
+        let ids: BTreeSet<usize> = displayed.iter().zip(&self.base_faces)
+            .filter(|(component, _)| components.contains(component))
+            .map(|(_, base_face)| loaded[*base_face])
+            .collect();
+        return Ok(ids.into_iter().collect());
+    }
+
+    /// applies an edit to the loaded mesh, the current subdivision is redone on top of it
+    pub fn edit_mesh<F>(&mut self, gl: &dyn Backend, crease_angle_deg: f32, edit: F) -> Result<(), String>
+    where F: FnOnce(&mut Mesh) -> Result<(), String>{
+        match self.base_mesh.as_mut(){
+            Some(base_mesh) => edit(base_mesh)?,
+            None => edit(&mut self.mesh)?
+        }
+
+        if self.subdivision_level > 0{
+            let level = self.subdivision_level;
+            return self.set_subdivision(gl, level, crease_angle_deg);
+        }
+        self.mesh_changed(gl)
+    }
+
+    /// regenerates the reduced levels of detail from the full detail mesh
+    pub fn rebuild_lods(&mut self) -> Result<(), String>{
+        self.lods = lod::generate_lod_meshes(&self.mesh)?
+            .into_iter()
+            .map(|mesh| MeshLod{mesh: mesh, gl_buffers: vec![]})
+            .collect();
+        self.active_lod = self.active_lod.min(self.lods.len());
+
+        if !self.lods.is_empty(){
+            let face_counts: Vec<usize> = self.lods.iter().map(|lod| lod.mesh.face_count()).collect();
+            log(&format!("generated lods {:?}f", face_counts));
+        }
+
+        Ok(())
+    }
+
+    /// switches to the level of detail fitting the projected size of the mesh
+    pub fn update_lod(&mut self, screen_size: f32, is_lod_enabled: bool){
+        self.active_lod = if is_lod_enabled{
+            lod::select_lod(self.active_lod, self.lods.len() + 1, screen_size)
+        }else{
+            0
+        };
+    }
+
+    pub fn active_gl_buffers(&self) -> &Vec<GLBuffers>{
+        if self.active_lod == 0 || self.lods[self.active_lod - 1].gl_buffers.is_empty(){
+            return &self.mesh_gl_buffers;
+        }
+        return &self.lods[self.active_lod - 1].gl_buffers;
+    }
+
+    /// values false color shading shows on `mesh`, the displayed one or one of its levels of detail
+    fn scalar_values(&self, mesh: &Mesh) -> Result<Vec<f32>, String>{
+        return match &self.false_color.source{
+            ScalarSource::Curvature(kind) => Ok(mesh.curvature()?.iter().map(|curvature| curvature.value(*kind)).collect()),
+            ScalarSource::Field(name) => {
+                let values = self.scalar_fields.get(name).ok_or(format!("No scalar field named {}", name))?;
+                if values.len() != mesh.vert_count(){
+                    return Err(format!("Scalar field {} has {} values for {} vertices", name, values.len(), mesh.vert_count()));
+                }
+                Ok(values.clone())
+            }
+        };
+    }
+
+    fn create_mesh_gl_buffers(gl: &dyn Backend, mesh: &Mesh, shading: &ShadingType, false_colors: Option<&[Vector3<f32>]>) -> Result<Vec<GLBuffers>, String>{
+        let (vertices, indices) = match shading {
+            ShadingType::Flat => mesh.create_primitive_buffers_flatshaded()?,
+            ShadingType::Smooth => mesh.create_primitive_buffers()?,
+            ShadingType::Wireframe => mesh.create_primitive_buffers_wireframe()?,
+            ShadingType::FalseColor => mesh.create_primitive_buffers_colored(false_colors.ok_or("Missing false colors")?)?
+        };
+
+        let (values_per_vertex, primitive_size) = match shading {
+            ShadingType::Flat => (9, 3), // pos x,y,z + normal x,y,z + color r,g,b, triangles
+            ShadingType::Smooth => (9, 3), // pos + normal + color, triangles
+            ShadingType::Wireframe => (3, 2), // pos, lines
+            ShadingType::FalseColor => (9, 3) // as smooth
+        };
+
+        return GLBuffers::create_indexed(&vertices, &indices, values_per_vertex, primitive_size, gl);
+    }
+
+    /// outline of the bounding volume, the axis aligned box where the others can't be built
+    fn create_bounds_buffers(&self) -> Result<(Vec<f32>, Vec<usize>), String>{
+        let bounds = match self.bounds_kind{
+            BoundsKind::Aabb => None,
+            BoundsKind::Obb => Some(self.mesh.oriented_bounding_box().map(|obb| create_box_line_buffers(&obb))),
+            BoundsKind::Sphere => Some(Ok(create_sphere_line_buffers(&self.mesh.bounding_sphere(), 64))),
+            BoundsKind::Hull => Some(self.mesh.convex_hull().map(|hull| create_hull_line_buffers(&hull)))
+        };
+
+        match bounds{
+            Some(Ok(buffers)) => return Ok(buffers),
+            Some(Err(err)) => log(&format!("Showing the bounding box instead: {}", err)),
+            None => {}
+        }
+
+        let (vertices, indices) = self.mesh.create_bb_primitive_buffers()?;
+        return Ok((vertices, indices.iter().map(|&i| i as usize).collect()));
+    }
+
+    pub fn reload_bounds_gl_buffers(&mut self, gl: &dyn Backend) -> Result<(), String>{
+        for chunk in &self.bb_gl_buffers{
+            chunk.delete(gl);
+        }
+        self.bb_gl_buffers.clear();
+
+        let (vertices, indices) = self.create_bounds_buffers()?;
+        self.bb_gl_buffers.extend(GLBuffers::create_indexed(&vertices, &indices, 3, 2, gl)?);
+        Ok(())
+    }
+
+    pub fn reload_gl_buffers(&mut self, gl: &dyn Backend)-> Result<(), String> {
+        self.delete_mesh_gl_buffers(gl);
+
+        let mut false_colors = None;
+        if self.shading == ShadingType::FalseColor{
+            let values = self.scalar_values(&self.mesh)?;
+            if self.false_color.range.is_none(){ // levels of detail share the full detail range
+                self.false_color.fitted_range = fit_range(&values, FALSE_COLOR_OUTLIER_FRACTION);
+            }
+            false_colors = Some(self.false_color.color_map.map_values(&values, self.false_color.range()));
+
+            for level in &self.false_color.isoline_levels{
+                let (line_vertices, line_indices) = mesh::create_section_line_buffers(&self.mesh.isolines(&values, *level)?);
+                self.isoline_gl_buffers.extend(GLBuffers::create_indexed(&line_vertices, &line_indices, 3, 2, gl)?);
+            }
+        }
+
+        self.mesh_gl_buffers = RenderedMesh::create_mesh_gl_buffers(gl, &self.mesh, &self.shading, false_colors.as_deref())?;
+
+        for i in 0..self.lods.len(){
+            let mut lod_colors = None;
+            if self.shading == ShadingType::FalseColor{
+                match self.scalar_values(&self.lods[i].mesh){
+                    Ok(values) => lod_colors = Some(self.false_color.color_map.map_values(&values, self.false_color.range())),
+                    Err(_) => continue // fields only fit the full detail mesh, which then stays in view
+                }
+            }
+            self.lods[i].gl_buffers = RenderedMesh::create_mesh_gl_buffers(gl, &self.lods[i].mesh, &self.shading, lod_colors.as_deref())?;
+        }
+        self.attach_instance_buffer(gl);
+
+        self.reload_bounds_gl_buffers(gl)?;
+
+        self.reload_section(gl)?;
+        self.reload_selection_gl_buffers(gl)
+    }
+
+    /// enters contour mode for the plane, None goes back to the surface
+    pub fn set_section(&mut self, gl: &dyn Backend, plane: Option<Plane>, is_capped: bool) -> Result<(), String>{
+        if let Some(section) = self.section.take(){
+            for chunk in section.line_gl_buffers.iter().chain(&section.cap_gl_buffers){
+                chunk.delete(gl);
+            }
+        }
+
+        if let Some(plane) = plane{
+            self.section = Some(Section{plane: plane, is_capped: is_capped, polylines: vec![], line_gl_buffers: vec![], cap_gl_buffers: vec![]});
+        }
+        self.reload_section(gl)
+    }
+
+    /// slices the full detail mesh again and recreates the contour gl buffers
+    fn reload_section(&mut self, gl: &dyn Backend) -> Result<(), String>{
+        let Some(section) = &mut self.section else {
+            return Ok(());
+        };
+
+        for chunk in section.line_gl_buffers.iter().chain(&section.cap_gl_buffers){
+            chunk.delete(gl);
+        }
+        section.line_gl_buffers.clear();
+        section.cap_gl_buffers.clear();
+
+        section.polylines = self.mesh.slice(&section.plane);
+
+        let (line_vertices, line_indices) = mesh::create_section_line_buffers(&section.polylines);
+        if !line_indices.is_empty(){
+            section.line_gl_buffers.extend(GLBuffers::create_indexed(&line_vertices, &line_indices, 3, 2, gl)?);
+        }
+
+        let (cap_vertices, cap_indices) = mesh::create_section_cap_buffers(&section.polylines);
+        if section.is_capped && !cap_indices.is_empty(){
+            section.cap_gl_buffers.extend(GLBuffers::create_indexed(&cap_vertices, &cap_indices, 3, 3, gl)?);
+        }
+
+        Ok(())
+    }
+
+    /// highlight geometry of the selection, triangles for faces and points for vertices
+    pub fn reload_selection_gl_buffers(&mut self, gl: &dyn Backend) -> Result<(), String>{
+        for chunk in &self.selection_gl_buffers{
+            chunk.delete(gl);
+        }
+        self.selection_gl_buffers.clear();
+
+        let selected = self.selection.indices();
+        if selected.is_empty(){
+            return Ok(());
+        }
+
+        let ((vertices, indices), primitive_size) = match self.selection.mode(){
+            SelectionMode::Vertices => (self.mesh.create_vertex_highlight_buffers(&selected)?, 1),
+            SelectionMode::Faces | SelectionMode::Parts => (self.mesh.create_face_highlight_buffers(&selected)?, 3)
+        };
+
+        self.selection_gl_buffers.extend(GLBuffers::create_indexed(&vertices, &indices, 3, primitive_size, gl)?);
+
+        Ok(())
+    }
+}
+
+struct ShaderPrograms{
+    program_flat: ProgramId,
+    program_smooth: ProgramId,
+    program_lines: ProgramId
+}
+
+impl ShaderPrograms{
+    pub fn load_shaders(gl: &dyn Backend) -> Result<ShaderPrograms, String>{
+        Ok(ShaderPrograms { program_flat: gl.create_program(ProgramKind::Flat)?, program_smooth: gl.create_program(ProgramKind::Smooth)?,
+            program_lines: gl.create_program(ProgramKind::Lines)? })
+    }
+}
+
+/// measurements of the displayed mesh in model units, vectors and matrices as flat arrays
+#[wasm_bindgen]
+pub struct Measurements{
+    properties: MassProperties
+}
+
+#[wasm_bindgen]
+impl Measurements{
+    #[wasm_bindgen(getter)]
+    pub fn surface_area(&self) -> f32{
+        self.properties.surface_area
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn volume(&self) -> f32{
+        self.properties.volume
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn is_closed(&self) -> bool{
+        self.properties.is_closed
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn area_centroid(&self) -> Vec<f32>{
+        self.properties.area_centroid.as_slice().to_vec()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn volume_centroid(&self) -> Vec<f32>{
+        self.properties.volume_centroid.as_slice().to_vec()
+    }
+
+    /// row-major 3x3
+    #[wasm_bindgen(getter)]
+    pub fn inertia_tensor(&self) -> Vec<f32>{
+        self.properties.inertia_tensor.transpose().as_slice().to_vec()
+    }
+}
+
+/// tighter bounds of the displayed mesh in model units, vectors as flat [x, y, z] arrays
+#[wasm_bindgen]
+pub struct BoundingVolumes{
+    obb: OrientedBox,
+    sphere: BoundingSphere,
+    hull_point_count: usize,
+    hull_face_count: usize,
+    hull_volume: f32
+}
+
+#[wasm_bindgen]
+impl BoundingVolumes{
+    #[wasm_bindgen(getter)]
+    pub fn obb_center(&self) -> Vec<f32>{
+        self.obb.center.as_slice().to_vec()
+    }
+
+    /// the box's unit axes, one after the other
+    #[wasm_bindgen(getter)]
+    pub fn obb_axes(&self) -> Vec<f32>{
+        self.obb.axes.as_slice().to_vec()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn obb_half_extents(&self) -> Vec<f32>{
+        self.obb.half_extents.as_slice().to_vec()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn obb_volume(&self) -> f32{
+        self.obb.volume()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn sphere_center(&self) -> Vec<f32>{
+        self.sphere.center.as_slice().to_vec()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn sphere_radius(&self) -> f32{
+        self.sphere.radius
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn hull_point_count(&self) -> usize{
+        self.hull_point_count
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn hull_face_count(&self) -> usize{
+        self.hull_face_count
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn hull_volume(&self) -> f32{
+        self.hull_volume
+    }
+}
+
+/// signed distances of the displayed mesh to the reference, in model units
+#[wasm_bindgen]
+pub struct Deviation{
+    stats: DeviationStats
+}
+
+#[wasm_bindgen]
+impl Deviation{
+    #[wasm_bindgen(getter)]
+    pub fn min(&self) -> f32{
+        self.stats.min
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn max(&self) -> f32{
+        self.stats.max
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn mean(&self) -> f32{
+        self.stats.mean
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn rms(&self) -> f32{
+        self.stats.rms
+    }
+}
+
+/// summary of a node in the scene, transforms flat and column major
+#[wasm_bindgen]
+pub struct SceneObjectInfo{
+    id: u32,
+    name: String,
+    parent: Option<u32>,
+    shading: Option<ShadingType>, // None for groups
+    color: Vector3<f32>,
+    local: Matrix4<f32>,
+    world: Matrix4<f32>,
+    face_count: usize,
+    instance_count: usize
+}
+
+#[wasm_bindgen]
+impl SceneObjectInfo{
+    #[wasm_bindgen(getter)]
+    pub fn id(&self) -> u32{
+        self.id
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn name(&self) -> String{
+        self.name.clone()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn parent(&self) -> Option<u32>{
+        self.parent
+    }
+
+    /// false for groups, which only place their children
+    #[wasm_bindgen(getter)]
+    pub fn has_mesh(&self) -> bool{
+        self.shading.is_some()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn shading(&self) -> Option<String>{
+        self.shading.map(|shading| match shading{
+            ShadingType::Smooth => "smooth",
+            ShadingType::Flat => "flat",
+            ShadingType::Wireframe => "wireframe",
+            ShadingType::FalseColor => "false_color"
+        }.to_string())
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn color(&self) -> Vec<f32>{
+        self.color.as_slice().to_vec()
+    }
+
+    /// relative to the parent
+    #[wasm_bindgen(getter)]
+    pub fn transform(&self) -> Vec<f32>{
+        self.local.as_slice().to_vec()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn world_transform(&self) -> Vec<f32>{
+        self.world.as_slice().to_vec()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn face_count(&self) -> usize{
+        self.face_count
+    }
+
+    /// copies drawn by instancing, 0 when the mesh is drawn on its own
+    #[wasm_bindgen(getter)]
+    pub fn instance_count(&self) -> usize{
+        self.instance_count
+    }
+}
+
+/// outcome of aligning the loaded mesh to the reference
+#[wasm_bindgen]
+pub struct IcpResult{
+    report: IcpReport
+}
+
+#[wasm_bindgen]
+impl IcpResult{
+    #[wasm_bindgen(getter)]
+    pub fn iterations(&self) -> u32{
+        self.report.iterations
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn converged(&self) -> bool{
+        self.report.converged
+    }
+
+    /// distance between the paired points left at the end, in model units
+    #[wasm_bindgen(getter)]
+    pub fn rms(&self) -> f32{
+        self.report.rms
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn inlier_fraction(&self) -> f32{
+        self.report.inlier_fraction
+    }
+
+    /// resulting model matrix, column major
+    #[wasm_bindgen(getter)]
+    pub fn transform(&self) -> Vec<f32>{
+        self.report.transform.as_slice().to_vec()
+    }
+}
+
+/// one connected part of the displayed mesh, bounds as flat [x, y, z] arrays
+#[wasm_bindgen]
+pub struct Component{
+    info: ComponentInfo
+}
+
+#[wasm_bindgen]
+impl Component{
+    #[wasm_bindgen(getter)]
+    pub fn face_count(&self) -> usize{
+        self.info.face_count
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn area(&self) -> f32{
+        self.info.area
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn bb_min(&self) -> Vec<f32>{
+        self.info.bb_min.as_slice().to_vec()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn bb_max(&self) -> Vec<f32>{
+        self.info.bb_max.as_slice().to_vec()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn hidden(&self) -> bool{
+        self.info.hidden
+    }
+}
+
+/// point found on the displayed mesh, vectors as flat [x, y, z] arrays
+#[wasm_bindgen]
+pub struct SurfaceHit{
+    point: SurfacePoint
+}
+
+#[wasm_bindgen]
+impl SurfaceHit{
+    #[wasm_bindgen(getter)]
+    pub fn face(&self) -> usize{
+        self.point.face
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn position(&self) -> Vec<f32>{
+        self.point.position.as_slice().to_vec()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn barycentric(&self) -> Vec<f32>{
+        self.point.barycentric.as_slice().to_vec()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn normal(&self) -> Vec<f32>{
+        self.point.normal.as_slice().to_vec()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn distance(&self) -> f32{
+        self.point.distance
+    }
+}
+
+/// finished measurement in model units, angles in degrees, points as flat [x, y, z, ...] arrays
+#[wasm_bindgen]
+pub struct MeasuredDimension{
+    dimension: Dimension
+}
+
+#[wasm_bindgen]
+impl MeasuredDimension{
+    /// "distance", "angle" or "radius"
+    #[wasm_bindgen(getter)]
+    pub fn kind(&self) -> String{
+        self.dimension.kind.name().to_string()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn value(&self) -> f32{
+        self.dimension.value
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn points(&self) -> Vec<f32>{
+        self.dimension.points.iter().flat_map(|point| point.iter().cloned()).collect()
+    }
+
+    /// fitted circle center of a radius, empty for the other kinds
+    #[wasm_bindgen(getter)]
+    pub fn center(&self) -> Vec<f32>{
+        self.dimension.circle.map_or(vec![], |circle| circle.center.as_slice().to_vec())
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn label_anchor(&self) -> Vec<f32>{
+        self.dimension.label_anchor().as_slice().to_vec()
+    }
+}
+
+/// one outline of a section, points as a flat [x, y, z, ...] array
+#[wasm_bindgen]
+pub struct SectionPolyline{
+    polyline: Polyline
+}
+
+#[wasm_bindgen]
+impl SectionPolyline{
+    #[wasm_bindgen(getter)]
+    pub fn points(&self) -> Vec<f32>{
+        self.polyline.points.iter().flat_map(|point| point.iter().cloned()).collect()
+    }
+
+    /// open outlines come from holes in the mesh
+    #[wasm_bindgen(getter)]
+    pub fn is_closed(&self) -> bool{
+        self.polyline.is_closed
+    }
+}
+
+#[wasm_bindgen]
+pub struct Renderer {
+    gl: Box<dyn Backend>,
+    canvas : Option<HtmlCanvasElement>, // None when drawing off screen
+    programs: ShaderPrograms,
+    frame_uniforms: UniformBuffer<FrameUniforms>,
+    light_uniforms: UniformBuffer<LightUniforms>,
+    mouse_anchor: Point2<i32>,
+    is_mouse_down: bool,
+    is_bb_visible: bool,
+    is_lod_enabled: bool,
+    crease_angle_deg: f32,
+    scene: Scene,
+    dimension_tool: DimensionTool,
+    dimension_gl_buffers: Vec<GLBuffers>,
+    reference: Option<Reference>, // compared against the displayed mesh
+    is_reference_visible: bool,
+    icp_options: IcpOptions, // stopping and pair distance limits, the rest is given per alignment
+    clip_planes: Vec<Option<ClipPlane>>, // MAX_CLIP_PLANES slots
+    are_clip_caps_enabled: bool,
+    camera: Camera,
+    screen_dimensions: Vector2<i32>,
+    last_time_step: f32,
+    anim_time_counter: f32,
+    should_run_animation: bool
+}
+
+pub struct Camera {
+    pub position: Point3<f32>,
+    pub target: Point3<f32>,
+    pub up: Vector3<f32>,
+    angle_x_deg: f32,
+    angle_y_deg: f32,
+    zoom_level: f32,
+    from_target_direction: Vector3<f32>
+}
+
+impl Camera {
+    const MOUSE_SENSITIVITY: f32 = 0.33;
+    const FOV: f32 = 45.0f32.to_radians();
+
+    pub fn new(position: Point3<f32>, target: Point3<f32>, up: Vector3<f32>) -> Camera{
+        return Camera { position: position, target: target, up: up, angle_x_deg: 0.0, angle_y_deg: 0.0, zoom_level: 10.0,
+        from_target_direction: (position-target).normalize() }
+    }
+
+    pub fn projection_matrix(screen_dimensions: &Vector2<i32>) -> Matrix4<f32>{
+        let aspect_ratio = (screen_dimensions.x as f32) / (screen_dimensions.y as f32);
+        return Matrix4::new_perspective(aspect_ratio, Camera::FOV, 0.1, 100.0);
+    }
+
+    pub fn view_matrix(&self) -> Matrix4<f32> {
+        return Matrix4::look_at_rh(&self.position, &self.target, &self.up,);
+    }
+
+    /// World space ray through a pixel, starting on the near plane.
+    /// Pixels are counted from the top left corner of the canvas.
+    pub fn screen_ray(&self, screen_point: &Point2<f32>, screen_dimensions: &Vector2<i32>) -> (Point3<f32>, Vector3<f32>){
+        let ndc_x = screen_point.x / screen_dimensions.x as f32 * 2.0 - 1.0;
+        let ndc_y = 1.0 - screen_point.y / screen_dimensions.y as f32 * 2.0;
+
+        let inverse_view_projection = (Camera::projection_matrix(screen_dimensions) * self.view_matrix())
+            .try_inverse().unwrap_or(Matrix4::identity());
+        let near = inverse_view_projection.transform_point(&Point3::new(ndc_x, ndc_y, -1.0));
+        let far = inverse_view_projection.transform_point(&Point3::new(ndc_x, ndc_y, 1.0));
+
+        return (near, (far - near).normalize());
+    }
+
+    /// approximate on-screen diameter in pixels of a sphere of `radius` around the target
+    pub fn projected_size(&self, radius: f32, screen_dimensions: &Vector2<i32>) -> f32{
+        if self.zoom_level <= radius{
+            return f32::INFINITY; // camera inside the sphere
+        }
+        let visible_half_height = self.zoom_level * (Camera::FOV / 2.0).tan();
+        return radius / visible_half_height * screen_dimensions.y as f32;
+    }
+
+    fn update_position(&mut self){
+        self.position = self.target + self.from_target_direction * self.zoom_level;
+    }
+
+    pub fn mouse_scroll(&mut self, mouse_wheel : f32) {
+        self.zoom_level += mouse_wheel * 0.5;
+        self.zoom_level = self.zoom_level.clamp(0.0, 50.0);
+        self.update_position();
+    }
+
+    pub fn mouse_move(&mut self, mouse_move_vec: Vector2<f32>){
+        self.angle_x_deg += mouse_move_vec.y * Camera::MOUSE_SENSITIVITY;
+		self.angle_y_deg += mouse_move_vec.x * Camera::MOUSE_SENSITIVITY;
+
+		self.angle_x_deg = self.angle_x_deg.clamp( -90.0 + 0.1, 90.0 - 0.1);
+
+        let target_to_self_quaternion =  
+        UnitQuaternion::from_axis_angle(&Vector3::y_axis(), self.angle_y_deg.to_radians())
+        * UnitQuaternion::from_axis_angle(&Vector3::x_axis(), self.angle_x_deg.to_radians());
+
+        self.from_target_direction = target_to_self_quaternion * Vector3::z_axis().into_inner();
+
+        self.update_position();
+    }
+}
+
+impl Renderer {
+    const DEFAULT_CREASE_ANGLE_DEG: f32 = 45.0;
+    const SELECTION_COLOR: [f32; 3] = [1.0, 0.5, 0.0];
+    /// how much of the selection color is blended over the shaded surface
+    const SELECTION_OPACITY: f32 = 0.6;
+
+    /// Renderer drawing through `gl` into a width by height image, with no canvas to follow.
+    /// Render state the drawing relies on is set here, the rest is restored after each change.
+    pub(crate) fn with_backend(gl: Box<dyn Backend>, width: i32, height: i32) -> Result<Renderer, String>{
+        gl.set_enabled(Capability::CullFace, true);
+        gl.set_enabled(Capability::DepthTest, true);
+        gl.viewport(width, height);
+
+        let programs = ShaderPrograms::load_shaders(gl.as_ref())?;
+        let frame_uniforms = UniformBuffer::create(gl.as_ref())?;
+        let light_uniforms = UniformBuffer::create(gl.as_ref())?;
+
+        Ok(Renderer{
+            gl,
+            canvas: None,
+            programs: programs,
+            frame_uniforms: frame_uniforms,
+            light_uniforms: light_uniforms,
+            scene: Scene::new(),
+            dimension_tool: DimensionTool::new(),
+            dimension_gl_buffers: vec![],
+            reference: None,
+            is_reference_visible: true,
+            icp_options: IcpOptions::new(),
+            clip_planes: (0..Renderer::MAX_CLIP_PLANES).map(|_| None).collect(),
+            are_clip_caps_enabled: true,
+            mouse_anchor: Point2::new(0,0),
+            is_mouse_down: false,
+            is_bb_visible: false,
+            is_lod_enabled: true,
+            crease_angle_deg: Renderer::DEFAULT_CREASE_ANGLE_DEG,
+            camera : Camera::new(Point3::new(0.0, 0.0, 10.0), Point3::new(0.0,0.0,0.0), Vector3::new(0.0,1.0,0.0)),
+            screen_dimensions: Vector2::new(width, height),
+            last_time_step: 0.0,
+            anim_time_counter: 1.0,
+            should_run_animation: false,
+        })
+    }
+
+    /// model matrix of the active object
+    fn model_matrix(&self) -> Matrix4<f32>{
+        return self.scene.active_node().map(|node| node.world).unwrap_or(Matrix4::identity());
+    }
+
+    /// clipped away surfaces passed through before a pick gives up
+    const MAX_PICK_CLIPPED_HITS: usize = 64;
+
+    /// pick in the model space of `model`, the ray direction isn't normalized
+    fn pick_in_model_space(&self, rendered_mesh: &RenderedMesh, model: &Matrix4<f32>, x: i32, y: i32) -> Result<(Point3<f32>, Option<SurfacePoint>), String>{
+        let (origin, direction) = self.camera.screen_ray(&Point2::new(x as f32, y as f32), &self.screen_dimensions);
+
+        let inverse_model = model.try_inverse().ok_or("Model matrix is not invertible")?;
+        let model_origin = inverse_model.transform_point(&origin);
+        let model_direction = inverse_model.transform_vector(&direction);
+
+        // skip hits on parts cut away by the clip planes
+        let clip_planes = self.clip_planes_except(None);
+        let direction_length = model_direction.norm();
+        let mut start = 0.0;
+        for _ in 0..Renderer::MAX_PICK_CLIPPED_HITS{
+            let Some(mut hit) = rendered_mesh.bvh.intersect_ray(&(model_origin.coords + model_direction * (start / direction_length)), &model_direction, f32::INFINITY) else {
+                break;
+            };
+            hit.distance += start;
+
+            let world_position = model.transform_point(&Point3::from(hit.position)).coords;
+            if clip_planes.iter().all(|plane| plane.signed_distance(&world_position) <= 0.0){
+                return Ok((origin, Some(hit)));
+            }
+            start = hit.distance + hit.distance.max(1.0) * 1e-5;
+        }
+        return Ok((origin, None));
+    }
+
+    /// a hit found in the model space of `model` moved to world space, its distance measured from `origin`
+    fn hit_to_world(hit: &SurfacePoint, model: &Matrix4<f32>, origin: &Point3<f32>) -> SurfacePoint{
+        let normal_matrix = model.fixed_view::<3, 3>(0, 0).try_inverse().unwrap_or(Matrix3::identity()).transpose();
+        let position = model.transform_point(&Point3::from(hit.position));
+        return SurfacePoint{
+            face: hit.face,
+            position: position.coords,
+            barycentric: hit.barycentric,
+            normal: (normal_matrix * hit.normal).normalize(),
+            distance: (position - origin).norm()
+        };
+    }
+
+    /// size of the point markers relative to the mesh
+    const DIMENSION_MARKER_SCALE: f32 = 0.01;
+    const DIMENSION_COLOR: [f32; 3] = [1.0, 1.0, 0.0];
+
+    fn reload_dimension_gl_buffers(&mut self) -> Result<(), String>{
+        for chunk in &self.dimension_gl_buffers{
+            chunk.delete(self.gl.as_ref());
+        }
+        self.dimension_gl_buffers.clear();
+
+        let marker_size = self.scene.active().map_or(1.0, |rendered_mesh| rendered_mesh.mesh.bounding_radius()) * Renderer::DIMENSION_MARKER_SCALE;
+        let (vertices, indices) = self.dimension_tool.create_line_buffers(marker_size);
+        if indices.is_empty(){
+            return Ok(());
+        }
+
+        self.dimension_gl_buffers.extend(GLBuffers::create_indexed(&vertices, &indices, 3, 2, self.gl.as_ref())?);
+        Ok(())
+    }
+
+    /// size of the clipPlanes array in the shaders
+    const MAX_CLIP_PLANES: usize = 6;
+    const CLIP_CAP_COLOR: [f32; 3] = [0.8, 0.25, 0.2];
+    /// half size of the cap quads, reaches past the far plane
+    const CLIP_CAP_EXTENT: f32 = 200.0;
+
+    /// the active clip planes, optionally leaving one out
+    fn clip_planes_except(&self, skipped: Option<usize>) -> Vec<Plane>{
+        return self.clip_planes.iter().enumerate()
+            .filter(|(id, _)| Some(*id) != skipped)
+            .filter_map(|(_, clip_plane)| clip_plane.as_ref().map(|clip_plane| clip_plane.plane))
+            .collect();
+    }
+
+    fn pass_clip_plane_uniforms(&self, gl: &dyn Backend, program: ProgramId, planes: &[Plane]){
+        let plane_values: Vec<f32> = planes.iter()
+            .flat_map(|plane| [plane.normal.x, plane.normal.y, plane.normal.z, plane.offset])
+            .collect();
+
+        gl.set_uniform(program, "clipPlaneCount", UniformValue::Int(planes.len() as i32));
+        if !plane_values.is_empty(){
+            gl.set_uniform(program, "clipPlanes", UniformValue::Vec4Array(&plane_values));
+        }
+    }
+
+    /// Fills the openings the clip planes cut into solids. For every plane the surface, clipped by that plane only,
+    /// marks the stencil where more back than front faces lie behind the pixel, which is where the cut shows the inside.
+    /// The plane's quad, clipped by the other planes, is then drawn there.
+    fn draw_clip_caps(&self, gl: &dyn Backend, surface_chunks: &[GLBuffers], instances: Option<&InstanceBuffer>, model: &Matrix4<f32>) -> Result<(), String>{
+        let program = self.programs.program_lines;
+        gl.use_program(program);
+
+        let [r, g, b] = Renderer::CLIP_CAP_COLOR;
+        gl.set_uniform(program, "objectColor", UniformValue::Vec3([r, g, b]));
+
+        gl.set_enabled(Capability::CullFace, false);
+        gl.set_enabled(Capability::StencilTest, true);
+
+        for (id, clip_plane) in self.clip_planes.iter().enumerate(){
+            let Some(clip_plane) = clip_plane else {
+                continue;
+            };
+
+            // count faces into the stencil
+            gl.clear(false, false, true);
+            gl.color_mask(false);
+            gl.depth_mask(false);
+            gl.set_enabled(Capability::DepthTest, false);
+            gl.stencil_func(CompareFunc::Always, 0, 0xff);
+            gl.stencil_op(Face::Front, StencilOp::Keep, StencilOp::Keep, StencilOp::DecrWrap);
+            gl.stencil_op(Face::Back, StencilOp::Keep, StencilOp::Keep, StencilOp::IncrWrap);
+
+            self.pass_model_uniform(gl, program, model);
+            self.pass_clip_plane_uniforms(gl, program, &[clip_plane.plane]);
+            for chunk in surface_chunks{
+                Renderer::draw_instances(gl, Primitive::Triangles, chunk, instances);
+            }
+
+            // the cap, the quad is in world space
+            gl.color_mask(true);
+            gl.depth_mask(true);
+            gl.set_enabled(Capability::DepthTest, true);
+            gl.stencil_func(CompareFunc::NotEqual, 0, 0xff);
+            gl.stencil_op(Face::FrontAndBack, StencilOp::Keep, StencilOp::Keep, StencilOp::Keep);
+
+            self.pass_model_uniform(gl, program, &Matrix4::identity());
+            self.pass_clip_plane_uniforms(gl, program, &self.clip_planes_except(Some(id)));
+            clip_plane.cap_gl_buffers.draw(gl, Primitive::Triangles);
+        }
+
+        gl.set_enabled(Capability::StencilTest, false);
+        gl.set_enabled(Capability::CullFace, true);
+        Ok(())
+    }
+
+    const SECTION_LINE_COLOR: [f32; 3] = [0.2, 0.8, 1.0];
+    const SECTION_CAP_COLOR: [f32; 3] = [0.5, 0.5, 0.5];
+
+    const ISOLINE_COLOR: [f32; 3] = [0.05, 0.05, 0.05];
+
+    const REFERENCE_COLOR: [f32; 3] = [0.6, 0.6, 0.6];
+    /// scalar field holding the signed distances to the reference
+    const DEVIATION_FIELD: &'static str = "deviation";
+
+    /// contour lines, and with capping the inside of the loops filled even-odd through the stencil buffer
+    fn draw_section(&self, gl: &dyn Backend, section: &Section, model: &Matrix4<f32>) -> Result<(), String>{
+        let program = self.programs.program_lines;
+        gl.use_program(program);
+
+        self.pass_model_uniform(gl, program, model);
+        self.pass_clip_plane_uniforms(gl, program, &self.clip_planes_except(None));
+
+        let draw_chunks = |chunks: &[GLBuffers], primitive: Primitive| {
+            for chunk in chunks{
+                chunk.draw(gl, primitive);
+            }
+        };
+
+        if !section.cap_gl_buffers.is_empty(){
+            gl.set_enabled(Capability::CullFace, false); // fan triangles wind both ways
+            gl.set_enabled(Capability::StencilTest, true);
+
+            // every covering fan flips the stencil bit, odd counts are inside
+            gl.color_mask(false);
+            gl.depth_mask(false);
+            gl.stencil_func(CompareFunc::Always, 0, 1);
+            gl.stencil_op(Face::FrontAndBack, StencilOp::Keep, StencilOp::Keep, StencilOp::Invert);
+            draw_chunks(&section.cap_gl_buffers, Primitive::Triangles);
+
+            gl.color_mask(true);
+            gl.depth_mask(true);
+            gl.stencil_func(CompareFunc::Equal, 1, 1);
+            gl.stencil_op(Face::FrontAndBack, StencilOp::Keep, StencilOp::Keep, StencilOp::Keep);
+            let [r, g, b] = Renderer::SECTION_CAP_COLOR;
+            gl.set_uniform(program, "objectColor", UniformValue::Vec3([r, g, b]));
+            draw_chunks(&section.cap_gl_buffers, Primitive::Triangles);
+
+            gl.set_enabled(Capability::StencilTest, false);
+            gl.set_enabled(Capability::CullFace, true);
+        }
+
+        let [r, g, b] = Renderer::SECTION_LINE_COLOR;
+        gl.set_uniform(program, "objectColor", UniformValue::Vec3([r, g, b]));
+        gl.depth_func(CompareFunc::LessEqual); // lines lie exactly on the cap
+        draw_chunks(&section.line_gl_buffers, Primitive::Lines);
+        gl.depth_func(CompareFunc::Less);
+
+        Ok(())
+    }
+
+    /// line chunks in one color, on top of surfaces they lie on
+    fn draw_lines(&self, gl: &dyn Backend, chunks: &[GLBuffers], color: [f32; 3], model: &Matrix4<f32>) -> Result<(), String>{
+        let program = self.programs.program_lines;
+        gl.use_program(program);
+
+        self.pass_model_uniform(gl, program, model);
+        self.pass_clip_plane_uniforms(gl, program, &self.clip_planes_except(None));
+        let [r, g, b] = color;
+        gl.set_uniform(program, "objectColor", UniformValue::Vec3([r, g, b]));
+
+        gl.depth_func(CompareFunc::LessEqual);
+        for chunk in chunks{
+            chunk.draw(gl, Primitive::Lines);
+        }
+        gl.depth_func(CompareFunc::Less);
+
+        Ok(())
+    }
+
+    /// draws the chunk once, or once per instance when its vertex array reads `instances`
+    fn draw_instances(gl: &dyn Backend, primitive: Primitive, chunk: &GLBuffers, instances: Option<&InstanceBuffer>){
+        match instances{
+            Some(instances) => chunk.draw_instanced(gl, primitive, instances),
+            None => chunk.draw(gl, primitive)
+        }
+    }
+
+    /// Stores the signed distances to the reference as the deviation field and shows it
+    /// in false color, with a diverging range symmetric around zero.
+    fn update_deviation(&mut self) -> Result<DeviationStats, String>{
+        let model = self.model_matrix();
+        let reference = self.reference.as_ref().ok_or("No reference loaded")?;
+        let rendered_mesh = self.scene.active_mut().ok_or("No mesh loaded!")?;
+
+        let distances = rendered_mesh.mesh.signed_distances(&reference.bvh, &model);
+        let stats = DeviationStats::new(&distances);
+        rendered_mesh.scalar_fields.insert(Renderer::DEVIATION_FIELD.to_string(), distances);
+
+        let false_color = &mut rendered_mesh.false_color;
+        false_color.set_source(ScalarSource::Field(Renderer::DEVIATION_FIELD.to_string()));
+        false_color.color_map = ColorMap::Diverging;
+        let extent = stats.min.abs().max(stats.max.abs());
+        if extent > 0.0{
+            false_color.range = Some((-extent, extent));
+        }
+        rendered_mesh.shading = ShadingType::FalseColor;
+        rendered_mesh.reload_gl_buffers(self.gl.as_ref())?;
+
+        Ok(stats)
+    }
+
+    /// applies a false color setting, redrawing the surface when it is shown
+    fn edit_false_color<F>(&mut self, edit: F) -> Result<(), String> where F: FnOnce(&mut FalseColor){
+        if let Some(rendered_mesh) = self.scene.active_mut(){
+            edit(&mut rendered_mesh.false_color);
+            if rendered_mesh.shading == ShadingType::FalseColor{
+                rendered_mesh.reload_gl_buffers(self.gl.as_ref())?;
+            }
+            return Ok(());
+        }
+        Err("No mesh loaded!".to_string())
+    }
+
+    /// pixel coordinates of the canvas to normalized device coordinates
+    fn screen_to_ndc(&self, x: i32, y: i32) -> Point2<f32>{
+        return Point2::new(x as f32 / self.screen_dimensions.x as f32 * 2.0 - 1.0,
+            1.0 - y as f32 / self.screen_dimensions.y as f32 * 2.0);
+    }
+
+    /// Mesh shown the way `template` is: shading, false color, subdivision,
+    /// selection mode, section and bounding volume carry over.
+    fn create_rendered_mesh(&self, mesh: Mesh, template: Option<&RenderedMesh>) -> Result<RenderedMesh, String>{
+        let gl = self.gl.as_ref();
+
+        let mut shading = ShadingType::Flat;
+        let mut false_color = FalseColor::new();
+        let mut subdivision_level = 0;
+        let mut selection_mode = SelectionMode::Faces;
+        let mut section = None;
+        let mut bounds_kind = BoundsKind::Aabb;
+
+        if let Some(template) = template{
+            shading = template.shading;
+            false_color = template.false_color.clone();
+            if let ScalarSource::Field(_) = false_color.source{ // fields stay with their mesh
+                false_color.set_source(ScalarSource::Curvature(CurvatureKind::Mean));
+            }
+            subdivision_level = template.subdivision_level;
+            selection_mode = template.selection.mode();
+            section = template.section.as_ref().map(|section| (section.plane, section.is_capped));
+            bounds_kind = template.bounds_kind;
+        }
+
+        let mut rendered_mesh = RenderedMesh::new(gl, mesh, shading, false_color)?;
+        rendered_mesh.selection.set_mode(selection_mode);
+        if bounds_kind != BoundsKind::Aabb{
+            rendered_mesh.bounds_kind = bounds_kind;
+            rendered_mesh.reload_bounds_gl_buffers(gl)?;
+        }
+        if let Some((plane, is_capped)) = section{
+            rendered_mesh.set_section(gl, Some(plane), is_capped)?;
+        }
+        if subdivision_level > 0{
+            rendered_mesh.set_subdivision(gl, subdivision_level, self.crease_angle_deg)?;
+        }
+        return Ok(rendered_mesh);
+    }
+
+    /// dimensions are measured on the active object, they go when it changes
+    fn active_object_changed(&mut self) -> Result<(), String>{
+        self.dimension_tool.clear();
+        self.reload_dimension_gl_buffers()?;
+
+        self.should_run_animation = true;
+        Ok(())
+    }
+}
+
+#[wasm_bindgen]
+impl Renderer {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Result<Renderer, JsValue> {
+        let window = web_sys::window().unwrap();
+        let document = window.document().unwrap();
+        let canvas = document.get_element_by_id("canvas").unwrap();
+        let canvas: web_sys::HtmlCanvasElement = canvas.dyn_into()?;
+
+        // the stencil buffer is used for filling section caps
+        let context_options = js_sys::Object::new();
+        js_sys::Reflect::set(&context_options, &"stencil".into(), &true.into())?;
+
+        let gl = canvas
+            .get_context_with_context_options("webgl2", &context_options)?
+            .unwrap()
+            .dyn_into::<WebGl2RenderingContext>()?;
+
+        let canvas_dom_width = canvas.client_width();
+        let canvas_dom_height = canvas.client_height();
+
+        canvas.set_width(canvas_dom_width as u32);
+        canvas.set_height(canvas_dom_height as u32);
+
+        logging::set_logger(|message| web_sys::console::log_1(&message.into()));
+
+        let mut renderer = Renderer::with_backend(Box::new(WebGlBackend::new(gl)), canvas_dom_width, canvas_dom_height)?;
+        renderer.canvas = Some(canvas);
+        Ok(renderer)
+    }
+
+    /// replaces the mesh of the active object, which keeps its id, placement and display settings
+    #[wasm_bindgen]
+    pub fn load_model(&mut self, mesh_str: String) -> Result<(), JsValue>{
+        let mut rendered_mesh = self.create_rendered_mesh(Mesh::load_obj(&mesh_str)?, self.scene.active())?;
+
+        if let Some(current_mesh) = self.scene.active_mut(){
+            current_mesh.delete_mesh_gl_buffers(self.gl.as_ref());
+            current_mesh.delete_instance_buffer(self.gl.as_ref());
+            rendered_mesh.color = current_mesh.color;
+            rendered_mesh.set_instances(self.gl.as_ref(), current_mesh.instances.clone())?;
+            *current_mesh = rendered_mesh;
+        }else{
+            self.scene.add("model".to_string(), None, Matrix4::identity(), Some(rendered_mesh))?;
+        }
+
+        //console::log_1(&format!("displaying mesh {:?}v {:?}f", vertices.len()/3, indices.len()/3).into());
+
+        self.active_object_changed()?;
+        Ok(())
+    }
+
+    /// Adds the OBJ text below `parent` (a root without), shown like the active mesh. A file with several
+    /// groups becomes a group node with a child per group, otherwise it's a single mesh node, named
+    /// after its group if the file names one. The last mesh added becomes active, returns the id of the new node.
+    #[wasm_bindgen]
+    pub fn add_object(&mut self, name: String, mesh_str: String, parent: Option<u32>) -> Result<u32, String>{
+        let mut groups = Mesh::load_obj_groups(&mesh_str)?;
+        if groups.len() == 1{
+            let (group_name, mesh) = groups.remove(0);
+            let name = if group_name == "default" {name} else {group_name};
+            let rendered_mesh = self.create_rendered_mesh(mesh, self.scene.active())?;
+            let id = self.scene.add(name, parent, Matrix4::identity(), Some(rendered_mesh))?;
+            self.active_object_changed()?;
+            return Ok(id);
+        }
+
+        let id = self.scene.add(name, parent, Matrix4::identity(), None)?;
+        for (group_name, mesh) in groups.drain(..){
+            let rendered_mesh = self.create_rendered_mesh(mesh, self.scene.active())?;
+            self.scene.add(group_name, Some(id), Matrix4::identity(), Some(rendered_mesh))?;
+        }
+        self.active_object_changed()?;
+        Ok(id)
+    }
+
+    /// empty node to place other ones with, below `parent` or as a root
+    #[wasm_bindgen]
+    pub fn add_group(&mut self, name: String, parent: Option<u32>) -> Result<u32, String>{
+        return self.scene.add(name, parent, Matrix4::identity(), None);
+    }
+
+    /// removes the node with everything below it
+    #[wasm_bindgen]
+    pub fn remove_object(&mut self, id: u32) -> Result<(), String>{
+        let active = self.scene.active_id();
+        for mut node in self.scene.remove(id)?{
+            if let Some(rendered_mesh) = node.rendered_mesh.as_mut(){
+                rendered_mesh.delete_mesh_gl_buffers(self.gl.as_ref());
+                rendered_mesh.delete_instance_buffer(self.gl.as_ref());
+            }
+        }
+        if self.scene.active_id() != active{
+            self.active_object_changed()?;
+        }
+        Ok(())
+    }
+
+    /// moves the node below `parent`, or to the roots, keeping where it is in the world
+    #[wasm_bindgen]
+    pub fn set_object_parent(&mut self, id: u32, parent: Option<u32>) -> Result<(), String>{
+        return self.scene.set_parent(id, parent);
+    }
+
+    /// the mesh editing, picking and measuring apply to
+    #[wasm_bindgen]
+    pub fn set_active_object(&mut self, id: u32) -> Result<(), String>{
+        if self.scene.active_id() != Some(id){
+            self.scene.set_active(id)?;
+            self.active_object_changed()?;
+        }
+        Ok(())
+    }
+
+    #[wasm_bindgen]
+    pub fn active_object(&self) -> Option<u32>{
+        self.scene.active_id()
+    }
+
+    /// every node, parents before their children
+    #[wasm_bindgen]
+    pub fn objects(&self) -> Vec<SceneObjectInfo>{
+        self.scene.nodes().iter().map(|node| SceneObjectInfo{
+            id: node.id,
+            name: node.name.clone(),
+            parent: node.parent,
+            shading: node.rendered_mesh.as_ref().map(|rendered_mesh| rendered_mesh.shading),
+            color: node.rendered_mesh.as_ref().map_or(Vector3::new(1.0, 1.0, 1.0), |rendered_mesh| rendered_mesh.color),
+            local: node.local,
+            world: node.world,
+            face_count: node.rendered_mesh.as_ref().map_or(0, |rendered_mesh| rendered_mesh.mesh.face_count()),
+            instance_count: node.rendered_mesh.as_ref().map_or(0, |rendered_mesh| rendered_mesh.instances.len())
+        }).collect()
+    }
+
+    /// Places a node relative to its parent by translation, rotation (XYZ Euler angles in degrees,
+    /// applied in that order) and per axis scale, as local = translation * rotation * scale.
+    #[wasm_bindgen]
+    pub fn set_object_transform(&mut self, id: u32, translation: Vec<f32>, rotation_deg: Vec<f32>, scale: Vec<f32>) -> Result<(), String>{
+        if translation.len() != 3 || rotation_deg.len() != 3 || scale.len() != 3{
+            return Err("Expected three values for translation, rotation and scale".to_string());
+        }
+        if translation.iter().chain(&rotation_deg).chain(&scale).any(|value| !value.is_finite()){
+            return Err("Transform has non-finite values".to_string());
+        }
+        if scale.contains(&0.0){
+            return Err("Scale can't be zero".to_string());
+        }
+
+        let rotation = Rotation3::from_euler_angles(rotation_deg[0].to_radians(), rotation_deg[1].to_radians(), rotation_deg[2].to_radians());
+        let transform = Translation3::new(translation[0], translation[1], translation[2]).to_homogeneous()
+            * rotation.to_homogeneous()
+            * Matrix4::new_nonuniform_scaling(&Vector3::new(scale[0], scale[1], scale[2]));
+        return self.scene.set_local(id, &transform);
+    }
+
+    /// shading of the node's mesh and all meshes below it, see `change_shading`
+    #[wasm_bindgen]
+    pub fn set_object_shading(&mut self, id: u32, shading: String) -> Result<(), String>{
+        for id in self.scene.subtree(id)?{
+            if let Some(rendered_mesh) = self.scene.get_mut(id)?.rendered_mesh.as_mut(){
+                rendered_mesh.change_shading(self.gl.as_ref(), &shading)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// tints the surfaces, or colors the wireframes, of the node's mesh and all meshes below it
+    #[wasm_bindgen]
+    pub fn set_object_color(&mut self, id: u32, r: f32, g: f32, b: f32) -> Result<(), String>{
+        for id in self.scene.subtree(id)?{
+            if let Some(rendered_mesh) = self.scene.get_mut(id)?.rendered_mesh.as_mut(){
+                rendered_mesh.color = Vector3::new(r, g, b);
+            }
+        }
+        Ok(())
+    }
+
+    /// Draws the node's mesh once per instance, each placed relative to the node by a column major
+    /// matrix (16 values per instance) and tinted by a color (3 values per instance, white without any).
+    /// The selection, sections and bounds stay with the node itself. Transforms must be finite and invertible.
+    #[wasm_bindgen]
+    pub fn set_instances(&mut self, id: u32, transforms: Vec<f32>, colors: Vec<f32>) -> Result<(), String>{
+        if !transforms.len().is_multiple_of(16){
+            return Err(format!("Expected 16 matrix values per instance, got {}", transforms.len()));
+        }
+        let count = transforms.len() / 16;
+        if !colors.is_empty() && colors.len() != count * 3{
+            return Err(format!("Expected a color for each of the {} instances", count));
+        }
+
+        let instances = (0..count).map(|i| {
+            let transform = Matrix4::from_column_slice(&transforms[i * 16..(i + 1) * 16]);
+            let color = if colors.is_empty() {Vector3::new(1.0, 1.0, 1.0)} else {Vector3::from_column_slice(&colors[i * 3..(i + 1) * 3])};
+            Instance::new(transform, color).map_err(|error| format!("Instance {}: {}", i, error))
+        }).collect::<Result<Vec<Instance>, String>>()?;
+
+        let rendered_mesh = self.scene.get_mut(id)?.rendered_mesh.as_mut().ok_or(format!("Object {} has no mesh", id))?;
+        return rendered_mesh.set_instances(self.gl.as_ref(), instances);
+    }
+
+    /// draws the node's mesh on its own again
+    #[wasm_bindgen]
+    pub fn clear_instances(&mut self, id: u32) -> Result<(), String>{
+        return self.set_instances(id, vec![], vec![]);
+    }
+
+    /// id of the mesh node under the cursor, clip planes taken into account
+    #[wasm_bindgen]
+    pub fn pick_object(&self, x: i32, y: i32) -> Result<Option<u32>, String>{
+        let mut closest: Option<(u32, f32)> = None;
+        for node in self.scene.nodes(){
+            let Some(rendered_mesh) = &node.rendered_mesh else {continue};
+            let models: Vec<Matrix4<f32>> = if rendered_mesh.instances.is_empty() {vec![node.world]}
+                else {rendered_mesh.instances.iter().map(|instance| node.world * instance.transform).collect()};
+
+            for model in models{
+                let (origin, hit) = self.pick_in_model_space(rendered_mesh, &model, x, y)?;
+                let Some(hit) = hit else {continue};
+
+                let world_position = model.transform_point(&Point3::from(hit.position));
+                let distance = (world_position - origin).norm();
+                if closest.is_none_or(|(_, closest_distance)| distance < closest_distance){
+                    closest = Some((node.id, distance));
+                }
+            }
+        }
+        Ok(closest.map(|(id, _)| id))
+    }
+
+    /// loads the mesh to compare the displayed one against, replacing the previous reference
+    #[wasm_bindgen]
+    pub fn load_reference(&mut self, mesh_str: String) -> Result<(), JsValue>{
+        self.clear_reference();
+
+        let mesh = Mesh::load_obj(&mesh_str)?;
+        let bvh = Bvh::build(&mesh);
+
+        let (vertices, indices) = mesh.create_primitive_buffers_wireframe()?;
+        let mut gl_buffers = vec![];
+        gl_buffers.extend(GLBuffers::create_indexed(&vertices, &indices, 3, 2, self.gl.as_ref())?);
+
+        self.reference = Some(Reference{mesh: mesh, bvh: bvh, gl_buffers: gl_buffers});
+        Ok(())
+    }
+
+    #[wasm_bindgen]
+    pub fn clear_reference(&mut self){
+        if let Some(reference) = self.reference.take(){
+            for chunk in &reference.gl_buffers{
+                chunk.delete(self.gl.as_ref());
+            }
+        }
+    }
+
+    #[wasm_bindgen]
+    pub fn set_reference_visible(&mut self, visible: bool){
+        self.is_reference_visible = visible;
+    }
+
+    /// ICP stops once the RMS distance improves by less than the fraction `tolerance`,
+    /// points farther than `max_distance` from the reference are left out, 0 keeps all
+    #[wasm_bindgen]
+    pub fn set_icp_limits(&mut self, tolerance: f32, max_distance: f32) -> Result<(), String>{
+        if !(tolerance >= 0.0 && tolerance.is_finite()){
+            return Err(format!("ICP tolerance must be zero or positive, got {}", tolerance));
+        }
+        self.icp_options.tolerance = tolerance;
+        self.icp_options.max_distance = if max_distance > 0.0 {max_distance} else {f32::INFINITY};
+        Ok(())
+    }
+
+    /// Places the loaded mesh onto the reference by its model matrix: matches their "bounds" centers
+    /// or area "centroid"s ("none" keeps the current placement), then refines with up to `max_iterations`
+    /// of "point" to point or "point_to_plane" ICP, uniformly scaled too with `allow_scale`.
+    /// Pairs farther apart than `outlier_factor` times their median distance are ignored, 0 keeps all.
+    /// Stops and leaves out points as set by `set_icp_limits`. A shown deviation is computed again.
+    #[wasm_bindgen]
+    pub fn align_to_reference(&mut self, initial: String, metric: String, allow_scale: bool, max_iterations: u32, outlier_factor: f32) -> Result<IcpResult, String>{
+        let pivot = match initial.as_str(){
+            "none" => None,
+            "bounds" => Some(PivotCenter::BoundsCenter),
+            "centroid" => Some(PivotCenter::AreaCentroid),
+            _ => return Err(format!("Unrecognized initial alignment: {}", initial))
+        };
+        let mut options = self.icp_options;
+        options.metric = match metric.as_str(){
+            "point" => IcpMetric::PointToPoint,
+            "point_to_plane" => IcpMetric::PointToPlane,
+            _ => return Err(format!("Unrecognized ICP metric: {}", metric))
+        };
+        options.allow_scale = allow_scale;
+        options.max_iterations = max_iterations;
+        options.outlier_factor = if outlier_factor > 0.0 {Some(outlier_factor)} else {None};
+
+        let reference = self.reference.as_ref().ok_or("No reference loaded")?;
+        let id = self.scene.active_id().ok_or("No mesh loaded!")?;
+        let rendered_mesh = self.scene.active().ok_or("No mesh loaded!")?;
+
+        let mut initial_transform = self.model_matrix();
+        if let Some(pivot) = pivot{
+            let center = initial_transform.transform_point(&Point3::from(rendered_mesh.mesh.center(pivot))).coords;
+            initial_transform = Translation3::from(reference.mesh.center(pivot) - center).to_homogeneous() * initial_transform;
+        }
+        let report = rendered_mesh.mesh.align_icp(&reference.bvh, &initial_transform, &options)?;
+        let has_deviation = rendered_mesh.scalar_fields.contains_key(Renderer::DEVIATION_FIELD);
+        self.scene.set_world(id, &report.transform)?;
+
+        if has_deviation{
+            self.update_deviation()?;
+        }
+        Ok(IcpResult{report: report})
+    }
+
+    /// model matrix of the active mesh, column major
+    #[wasm_bindgen]
+    pub fn model_matrix_values(&self) -> Vec<f32>{
+        self.model_matrix().as_slice().to_vec()
+    }
+
+    /// places the active mesh in the world by a column major model matrix, its vertices stay as they are
+    #[wasm_bindgen]
+    pub fn set_model_matrix(&mut self, values: Vec<f32>) -> Result<(), String>{
+        if values.len() != 16{
+            return Err(format!("Expected 16 matrix values, got {}", values.len()));
+        }
+        if values.iter().any(|value| !value.is_finite()){
+            return Err("Model matrix has non-finite values".to_string());
+        }
+        let transform = Matrix4::from_column_slice(&values);
+        if !transform.try_inverse().is_some_and(|inverse| inverse.iter().all(|value| value.is_finite())){
+            return Err("Model matrix is not invertible".to_string());
+        }
+
+        let id = self.scene.active_id().ok_or("No mesh loaded!")?;
+        return self.scene.set_world(id, &transform);
+    }
+
+    /// puts the active mesh back where its parent is
+    #[wasm_bindgen]
+    pub fn reset_model_matrix(&mut self) -> Result<(), String>{
+        let id = self.scene.active_id().ok_or("No mesh loaded!")?;
+        return self.scene.set_local(id, &Matrix4::identity());
+    }
+
+    /// Moves the vertices of the active mesh by its transform relative to the parent, which becomes
+    /// the identity. Its children keep their place.
+    #[wasm_bindgen]
+    pub fn apply_model_matrix(&mut self) -> Result<(), String>{
+        let id = self.scene.active_id().ok_or("No mesh loaded!")?;
+        let local = self.scene.get(id)?.local;
+        let rendered_mesh = self.scene.active_mut().ok_or("No mesh loaded!")?;
+        rendered_mesh.edit_mesh(self.gl.as_ref(), self.crease_angle_deg, |mesh| mesh.transform(&local))?;
+        self.scene.take_local(id)?;
+        Ok(())
+    }
+
+    /// Signed distance from every vertex to the reference, positive outside it, shown in false color.
+    /// The values stay available as the "deviation" scalar field.
+    #[wasm_bindgen]
+    pub fn compute_deviation(&mut self) -> Result<Deviation, String>{
+        return Ok(Deviation{stats: self.update_deviation()?});
+    }
+
+    #[wasm_bindgen]
+    pub fn resize_context(&mut self) -> Result<(), String>{
+        let Some(canvas) = &self.canvas else {
+            return Ok(());
+        };
+
+        let canvas_dom_width = canvas.client_width();
+        let canvas_dom_height = canvas.client_height();
+
+        canvas.set_width(canvas_dom_width as u32);
+        canvas.set_height(canvas_dom_height as u32);
+
+        self.gl.viewport(canvas.width() as i32, canvas.height() as i32);
+
+        self.screen_dimensions = Vector2::new(canvas_dom_width, canvas_dom_height);
+
+        return Ok(());
+    }
+
+    #[wasm_bindgen]
+    pub fn set_bb_visible(&mut self, visible: bool) -> Result<(), JsValue>{
+        self.is_bb_visible = visible;
+        Ok(())
+    }
+
+    /// bounding volume shown: "aabb", "obb", "sphere" or "hull"
+    #[wasm_bindgen]
+    pub fn set_bounds_kind(&mut self, kind: String) -> Result<(), String>{
+        let kind = match kind.as_str(){
+            "aabb" => BoundsKind::Aabb,
+            "obb" => BoundsKind::Obb,
+            "sphere" => BoundsKind::Sphere,
+            "hull" => BoundsKind::Hull,
+            _ => return Err(format!("Unrecognized bounds: {}", kind))
+        };
+
+        if let Some(rendered_mesh) = self.scene.active_mut(){
+            if rendered_mesh.bounds_kind != kind{
+                rendered_mesh.bounds_kind = kind;
+                rendered_mesh.reload_bounds_gl_buffers(self.gl.as_ref())?;
+            }
+            return Ok(());
+        }
+        Err("No mesh loaded!".to_string())
+    }
+
+    /// oriented box, bounding sphere and convex hull of the displayed mesh
+    #[wasm_bindgen]
+    pub fn bounding_volumes(&self) -> Result<BoundingVolumes, String>{
+        if let Some(rendered_mesh) = self.scene.active(){
+            let mesh = &rendered_mesh.mesh;
+            let obb = mesh.oriented_bounding_box()?;
+            let hull = mesh.convex_hull()?;
+            let sphere = mesh.bounding_sphere();
+            return Ok(BoundingVolumes{obb: obb, sphere: sphere, hull_point_count: hull.points.len(),
+                hull_face_count: hull.faces.len(), hull_volume: hull.volume()});
+        }
+        Err("No mesh loaded!".to_string())
+    }
+
+    #[wasm_bindgen]
+    pub fn set_lod_enabled(&mut self, enabled: bool) -> Result<(), JsValue>{
+        self.is_lod_enabled = enabled;
+        Ok(())
+    }
+
+    #[wasm_bindgen]
+    pub fn set_subdivision_level(&mut self, level: u32) -> Result<(), String>{
+        if level > mesh::MAX_SUBDIVISION_LEVEL{
+            return Err(format!("Subdivision level must be at most {}", mesh::MAX_SUBDIVISION_LEVEL));
+        }
+
+        if let Some(rendered_mesh) = self.scene.active_mut(){
+            if rendered_mesh.subdivision_level != level{
+                rendered_mesh.set_subdivision(self.gl.as_ref(), level, self.crease_angle_deg)?;
+            }
+            return Ok(());
+        }
+        Err("No mesh loaded!".to_string())
+    }
+
+    /// edges with a dihedral angle above this stay sharp when subdividing
+    #[wasm_bindgen]
+    pub fn set_crease_angle(&mut self, angle_deg: f32) -> Result<(), String>{
+        let angle_deg = angle_deg.clamp(0.0, 180.0);
+        if angle_deg == self.crease_angle_deg{
+            return Ok(());
+        }
+        self.crease_angle_deg = angle_deg;
+
+        if let Some(rendered_mesh) = self.scene.active_mut()
+            && rendered_mesh.subdivision_level > 0{
+            let level = rendered_mesh.subdivision_level;
+            rendered_mesh.set_subdivision(self.gl.as_ref(), level, self.crease_angle_deg)?;
+        }
+        Ok(())
+    }
+
+    /// smooths the loaded mesh, method is "laplacian", "cotangent" or "taubin"
+    #[wasm_bindgen]
+    pub fn smooth_mesh(&mut self, method: String, iterations: u32, strength: f32, pin_boundary: bool) -> Result<(), String>{
+        let method = match method.as_str() {
+            "laplacian" => SmoothingMethod::Laplacian,
+            "cotangent" => SmoothingMethod::Cotangent,
+            "taubin" => SmoothingMethod::Taubin,
+            _ => {
+                return Err(format!("Unrecognized smoothing method: {}", method));
+            }
+        };
+
+        if let Some(rendered_mesh) = self.scene.active_mut(){
+            return rendered_mesh.edit_mesh(self.gl.as_ref(), self.crease_angle_deg,
+                |mesh| mesh.smooth(method, iterations, strength, pin_boundary));
+        }
+        Err("No mesh loaded!".to_string())
+    }
+
+    #[wasm_bindgen]
+    pub fn measure(&self) -> Result<Measurements, String>{
+        if let Some(rendered_mesh) = self.scene.active(){
+            return Ok(Measurements{properties: rendered_mesh.mesh.mass_properties()});
+        }
+        Err("No mesh loaded!".to_string())
+    }
+
+    /// moves the loaded mesh so the rotation pivot is "bounds", "area_centroid" or "volume_centroid"
+    #[wasm_bindgen]
+    pub fn set_pivot(&mut self, pivot: String) -> Result<(), String>{
+        let pivot = match pivot.as_str() {
+            "bounds" => PivotCenter::BoundsCenter,
+            "area_centroid" => PivotCenter::AreaCentroid,
+            "volume_centroid" => PivotCenter::VolumeCentroid,
+            _ => {
+                return Err(format!("Unrecognized pivot: {}", pivot));
+            }
+        };
+
+        if let Some(rendered_mesh) = self.scene.active_mut(){
+            return rendered_mesh.edit_mesh(self.gl.as_ref(), self.crease_angle_deg, |mesh| {
+                mesh.move_pivot_to_center(pivot);
+                Ok(())
+            });
+        }
+        Err("No mesh loaded!".to_string())
+    }
+
+    /// Components of the displayed mesh, ordered by face count, the same parts "parts" selection picks.
+    /// Ids stay valid until a component is deleted or the subdivision level changes.
+    #[wasm_bindgen]
+    pub fn components(&self) -> Result<Vec<Component>, String>{
+        if let Some(rendered_mesh) = self.scene.active(){
+            return Ok(rendered_mesh.mesh.connected_components().into_iter()
+                .map(|info| Component{info: info})
+                .collect());
+        }
+        Err("No mesh loaded!".to_string())
+    }
+
+    /// edits the loaded mesh given the ids there of the displayed `components`
+    fn edit_components<F>(&mut self, components: &[usize], edit: F) -> Result<(), String>
+    where F: FnOnce(&mut Mesh, &[usize]) -> Result<(), String>{
+        if let Some(rendered_mesh) = self.scene.active_mut(){
+            let components = rendered_mesh.loaded_components(components)?;
+            return rendered_mesh.edit_mesh(self.gl.as_ref(), self.crease_angle_deg, |mesh| edit(mesh, &components));
+        }
+        Err("No mesh loaded!".to_string())
+    }
+
+    #[wasm_bindgen]
+    pub fn set_component_visible(&mut self, component: usize, visible: bool) -> Result<(), String>{
+        self.edit_components(&[component], |mesh, components| mesh.set_components_hidden(components, !visible))
+    }
+
+    #[wasm_bindgen]
+    pub fn isolate_component(&mut self, component: usize) -> Result<(), String>{
+        self.edit_components(&[component], |mesh, components| mesh.isolate_components(components))
+    }
+
+    #[wasm_bindgen]
+    pub fn show_all_components(&mut self) -> Result<(), String>{
+        self.edit_components(&[], |mesh, _| {
+            let all: Vec<usize> = (0..mesh.connected_components().len()).collect();
+            mesh.set_components_hidden(&all, false)
+        })
+    }
+
+    #[wasm_bindgen]
+    pub fn set_component_color(&mut self, component: usize, r: f32, g: f32, b: f32) -> Result<(), String>{
+        self.edit_components(&[component], |mesh, components| {
+            for component in components{
+                mesh.set_component_color(*component, Vector3::new(r, g, b))?;
+            }
+            Ok(())
+        })
+    }
+
+    #[wasm_bindgen]
+    pub fn delete_component(&mut self, component: usize) -> Result<(), String>{
+        self.edit_components(&[component], |mesh, components| mesh.remove_components(components))
+    }
+
+    /// Deletes floating components with fewer faces than `min_face_count`, returns how many were deleted.
+    /// The largest component always stays.
+    #[wasm_bindgen]
+    pub fn delete_small_components(&mut self, min_face_count: usize) -> Result<usize, String>{
+        let small: Vec<usize> = self.components()?.iter().enumerate()
+            .skip(1)
+            .filter(|(_, component)| component.face_count() < min_face_count)
+            .map(|(id, _)| id)
+            .collect();
+        self.edit_components(&small, |mesh, components| mesh.remove_components(components))?;
+        Ok(small.len())
+    }
+
+    /// Surface under the pixel (x, y) of the canvas, in world space. None if the ray misses the mesh.
+    #[wasm_bindgen]
+    pub fn pick(&self, x: i32, y: i32) -> Result<Option<SurfaceHit>, String>{
+        let Some(rendered_mesh) = self.scene.active() else {
+            return Err("No mesh loaded!".to_string());
+        };
+
+        let (origin, Some(hit)) = self.pick_in_model_space(rendered_mesh, &self.model_matrix(), x, y)? else {
+            return Ok(None);
+        };
+
+        return Ok(Some(SurfaceHit{point: Renderer::hit_to_world(&hit, &self.model_matrix(), &origin)}));
+    }
+
+    /// what clicks and box drags select: "faces", "vertices" or "parts"
+    #[wasm_bindgen]
+    pub fn set_selection_mode(&mut self, mode: String) -> Result<(), String>{
+        let mode = match mode.as_str() {
+            "faces" => SelectionMode::Faces,
+            "vertices" => SelectionMode::Vertices,
+            "parts" => SelectionMode::Parts,
+            _ => {
+                return Err(format!("Unrecognized selection mode: {}", mode));
+            }
+        };
+
+        if let Some(rendered_mesh) = self.scene.active_mut(){
+            rendered_mesh.selection.set_mode(mode);
+            return rendered_mesh.reload_selection_gl_buffers(self.gl.as_ref());
+        }
+        Err("No mesh loaded!".to_string())
+    }
+
+    /// selects what is under the pixel (x, y), `toggle` (shift-click) adds or removes it instead of replacing
+    #[wasm_bindgen]
+    pub fn select_at(&mut self, x: i32, y: i32, toggle: bool) -> Result<(), String>{
+        let Some(rendered_mesh) = self.scene.active() else {
+            return Err("No mesh loaded!".to_string());
+        };
+
+        let items = match self.pick_in_model_space(rendered_mesh, &self.model_matrix(), x, y)?.1{
+            Some(hit) => rendered_mesh.selection.items_at(&rendered_mesh.mesh, &hit),
+            None => vec![] // clicking next to the mesh clears the selection
+        };
+
+        let rendered_mesh = self.scene.active_mut().unwrap();
+        rendered_mesh.selection.click(items, toggle);
+        rendered_mesh.reload_selection_gl_buffers(self.gl.as_ref())
+    }
+
+    /// selects everything projecting into the pixel rectangle, including hidden behind other surfaces
+    #[wasm_bindgen]
+    pub fn select_box(&mut self, x0: i32, y0: i32, x1: i32, y1: i32, add: bool) -> Result<(), String>{
+        let (corner_a, corner_b) = (self.screen_to_ndc(x0, y0), self.screen_to_ndc(x1, y1));
+        let (rect_min, rect_max) = (corner_a.inf(&corner_b), corner_a.sup(&corner_b));
+        let model_view_projection = Camera::projection_matrix(&self.screen_dimensions) * self.camera.view_matrix() * self.model_matrix();
+
+        if let Some(rendered_mesh) = self.scene.active_mut(){
+            let items = rendered_mesh.selection.items_in_rect(&rendered_mesh.mesh, &model_view_projection, &rect_min, &rect_max);
+            rendered_mesh.selection.select_box(items, add);
+            return rendered_mesh.reload_selection_gl_buffers(self.gl.as_ref());
+        }
+        Err("No mesh loaded!".to_string())
+    }
+
+    #[wasm_bindgen]
+    pub fn clear_selection(&mut self) -> Result<(), String>{
+        if let Some(rendered_mesh) = self.scene.active_mut(){
+            rendered_mesh.selection.clear();
+            return rendered_mesh.reload_selection_gl_buffers(self.gl.as_ref());
+        }
+        Err("No mesh loaded!".to_string())
+    }
+
+    /// Selected face ids, or vertex ids in "vertices" mode, of the displayed mesh.
+    /// They refer to the subdivided mesh while subdivision is on.
+    #[wasm_bindgen]
+    pub fn selected_indices(&self) -> Result<Vec<usize>, String>{
+        if let Some(rendered_mesh) = self.scene.active(){
+            return Ok(rendered_mesh.selection.indices());
+        }
+        Err("No mesh loaded!".to_string())
+    }
+
+    /// `tool` is "distance", "angle" or "radius", `snap` is "vertex", "edge" (midpoint) or "surface"
+    #[wasm_bindgen]
+    pub fn set_dimension_tool(&mut self, tool: String, snap: String) -> Result<(), String>{
+        let kind = match tool.as_str() {
+            "distance" => DimensionKind::Distance,
+            "angle" => DimensionKind::Angle,
+            "radius" => DimensionKind::Radius,
+            _ => {
+                return Err(format!("Unrecognized measurement tool: {}", tool));
+            }
+        };
+        let snap = match snap.as_str() {
+            "vertex" => SnapMode::Vertex,
+            "edge" => SnapMode::EdgeMidpoint,
+            "surface" => SnapMode::Surface,
+            _ => {
+                return Err(format!("Unrecognized snap mode: {}", snap));
+            }
+        };
+
+        self.dimension_tool.set_tool(kind, snap);
+        self.reload_dimension_gl_buffers()
+    }
+
+    /// Adds the snapped point under the pixel (x, y) to the current measurement and returns it,
+    /// None if nothing was hit. Distances and angles finish by themselves.
+    #[wasm_bindgen]
+    pub fn add_dimension_point(&mut self, x: i32, y: i32) -> Result<Option<Vec<f32>>, String>{
+        let Some(rendered_mesh) = self.scene.active() else {
+            return Err("No mesh loaded!".to_string());
+        };
+
+        let Some(hit) = self.pick_in_model_space(rendered_mesh, &self.model_matrix(), x, y)?.1 else {
+            return Ok(None);
+        };
+        let point = rendered_mesh.mesh.snap_point(&hit, self.dimension_tool.snap);
+
+        let added = self.dimension_tool.add_point(point);
+        self.reload_dimension_gl_buffers()?;
+        added?;
+        Ok(Some(point.as_slice().to_vec()))
+    }
+
+    /// finishes a radius measurement from the points added so far
+    #[wasm_bindgen]
+    pub fn finish_dimension(&mut self) -> Result<(), String>{
+        let finished = self.dimension_tool.finish();
+        self.reload_dimension_gl_buffers()?;
+        finished
+    }
+
+    #[wasm_bindgen]
+    pub fn clear_dimensions(&mut self) -> Result<(), String>{
+        self.dimension_tool.clear();
+        self.reload_dimension_gl_buffers()
+    }
+
+    #[wasm_bindgen]
+    pub fn dimensions(&self) -> Vec<MeasuredDimension>{
+        return self.dimension_tool.dimensions.iter()
+            .map(|dimension| MeasuredDimension{dimension: dimension.clone()})
+            .collect();
+    }
+
+    /// model space point to canvas pixels, for placing labels. Empty if it is behind the camera.
+    #[wasm_bindgen]
+    pub fn project_to_screen(&self, x: f32, y: f32, z: f32) -> Vec<f32>{
+        let model_view_projection = Camera::projection_matrix(&self.screen_dimensions) * self.camera.view_matrix() * self.model_matrix();
+        let clip = model_view_projection * Point3::new(x, y, z).to_homogeneous();
+        if clip.w <= 0.0{
+            return vec![];
+        }
+
+        return vec![
+            (clip.x / clip.w + 1.0) / 2.0 * self.screen_dimensions.x as f32,
+            (1.0 - clip.y / clip.w) / 2.0 * self.screen_dimensions.y as f32
+        ];
+    }
+
+    /// Contour mode: draws the outline of the mesh cut by the plane `normal · p = offset`
+    /// instead of its surface, `capped` fills the inside of closed outlines.
+    #[wasm_bindgen]
+    pub fn set_section(&mut self, normal_x: f32, normal_y: f32, normal_z: f32, offset: f32, capped: bool) -> Result<(), String>{
+        let plane = Plane::new(Vector3::new(normal_x, normal_y, normal_z), offset)?;
+
+        if let Some(rendered_mesh) = self.scene.active_mut(){
+            return rendered_mesh.set_section(self.gl.as_ref(), Some(plane), capped);
+        }
+        Err("No mesh loaded!".to_string())
+    }
+
+    /// back to drawing the surface
+    #[wasm_bindgen]
+    pub fn clear_section(&mut self) -> Result<(), String>{
+        if let Some(rendered_mesh) = self.scene.active_mut(){
+            return rendered_mesh.set_section(self.gl.as_ref(), None, false);
+        }
+        Err("No mesh loaded!".to_string())
+    }
+
+    /// outlines of the current section in model units
+    #[wasm_bindgen]
+    pub fn section_polylines(&self) -> Result<Vec<SectionPolyline>, String>{
+        if let Some(section) = self.scene.active().and_then(|rendered_mesh| rendered_mesh.section.as_ref()){
+            return Ok(section.polylines.iter().map(|polyline| SectionPolyline{polyline: polyline.clone()}).collect());
+        }
+        Err("No section set!".to_string())
+    }
+
+    /// outlines of the current section as an SVG document, in model units on the plane's axes
+    #[wasm_bindgen]
+    pub fn section_svg(&self) -> Result<String, String>{
+        if let Some(section) = self.scene.active().and_then(|rendered_mesh| rendered_mesh.section.as_ref()){
+            return Ok(mesh::section_svg(&section.polylines, &section.plane));
+        }
+        Err("No section set!".to_string())
+    }
+
+    /// Sets clip plane `index` (0 to 5) to cut away everything with `normal · p > offset`, in world space.
+    #[wasm_bindgen]
+    pub fn set_clip_plane(&mut self, index: usize, normal_x: f32, normal_y: f32, normal_z: f32, offset: f32) -> Result<(), String>{
+        if index >= Renderer::MAX_CLIP_PLANES{
+            return Err(format!("Clip plane index {} out of range, there are {}", index, Renderer::MAX_CLIP_PLANES));
+        }
+        let plane = Plane::new(Vector3::new(normal_x, normal_y, normal_z), offset)?;
+
+        // quad around the point of the plane closest to the origin
+        let (u, v) = plane.basis();
+        let center = plane.normal * plane.offset;
+        let extent = Renderer::CLIP_CAP_EXTENT;
+        let mut quad_vertices: Vec<f32> = vec![];
+        for (su, sv) in [(-1.0, -1.0), (1.0, -1.0), (1.0, 1.0), (-1.0, 1.0)]{
+            quad_vertices.extend_from_slice((center + (u * su + v * sv) * extent).as_slice());
+        }
+        let cap_gl_buffers = GLBuffers::create(&quad_vertices, &[0, 1, 2, 0, 2, 3], 3, self.gl.as_ref())?;
+
+        if let Some(old) = self.clip_planes[index].replace(ClipPlane{plane: plane, cap_gl_buffers: cap_gl_buffers}){
+            old.cap_gl_buffers.delete(self.gl.as_ref());
+        }
+        Ok(())
+    }
+
+    #[wasm_bindgen]
+    pub fn remove_clip_plane(&mut self, index: usize) -> Result<(), String>{
+        if index >= Renderer::MAX_CLIP_PLANES{
+            return Err(format!("Clip plane index {} out of range, there are {}", index, Renderer::MAX_CLIP_PLANES));
+        }
+        if let Some(old) = self.clip_planes[index].take(){
+            old.cap_gl_buffers.delete(self.gl.as_ref());
+        }
+        Ok(())
+    }
+
+    /// fill the cut open insides of solids
+    #[wasm_bindgen]
+    pub fn set_clip_caps_enabled(&mut self, enabled: bool) -> Result<(), JsValue>{
+        self.are_clip_caps_enabled = enabled;
+        Ok(())
+    }
+
+    /// active clip planes as [index, normal x, y, z, offset] per plane
+    #[wasm_bindgen]
+    pub fn clip_planes(&self) -> Vec<f32>{
+        return self.clip_planes.iter().enumerate()
+            .filter_map(|(id, clip_plane)| clip_plane.as_ref().map(|clip_plane| (id, clip_plane.plane)))
+            .flat_map(|(id, plane)| [id as f32, plane.normal.x, plane.normal.y, plane.normal.z, plane.offset])
+            .collect();
+    }
+
+    /// nearest surface hit along a ray in world space, None on a miss
+    #[wasm_bindgen]
+    pub fn ray_cast(&self, origin_x: f32, origin_y: f32, origin_z: f32, dir_x: f32, dir_y: f32, dir_z: f32) -> Result<Option<SurfaceHit>, String>{
+        if let Some(rendered_mesh) = self.scene.active(){
+            let model = self.model_matrix();
+            let inverse_model = model.try_inverse().ok_or("Model matrix is not invertible")?;
+            let origin = Point3::new(origin_x, origin_y, origin_z);
+            let model_origin = inverse_model.transform_point(&origin);
+            let model_direction = inverse_model.transform_vector(&Vector3::new(dir_x, dir_y, dir_z));
+
+            let hit = rendered_mesh.bvh.intersect_ray(&model_origin.coords, &model_direction, f32::INFINITY);
+            return Ok(hit.map(|hit| SurfaceHit{point: Renderer::hit_to_world(&hit, &model, &origin)}));
+        }
+        Err("No mesh loaded!".to_string())
+    }
+
+    /// closest point on the surface to a point, both in world space
+    #[wasm_bindgen]
+    pub fn closest_point(&self, x: f32, y: f32, z: f32) -> Result<Option<SurfaceHit>, String>{
+        if let Some(rendered_mesh) = self.scene.active(){
+            let point = rendered_mesh.bvh.closest_point_transformed(&Vector3::new(x, y, z), f32::INFINITY, &self.model_matrix());
+            return Ok(point.map(|point| SurfaceHit{point: point}));
+        }
+        Err("No mesh loaded!".to_string())
+    }
+
+    /// ids of the displayed faces touching the box, given in world space
+    #[wasm_bindgen]
+    pub fn faces_in_box(&self, min_x: f32, min_y: f32, min_z: f32, max_x: f32, max_y: f32, max_z: f32) -> Result<Vec<usize>, String>{
+        if let Some(rendered_mesh) = self.scene.active(){
+            let (bb_min, bb_max) = (Vector3::new(min_x, min_y, min_z), Vector3::new(max_x, max_y, max_z));
+            return Ok(rendered_mesh.bvh.faces_in_transformed_box(&bb_min, &bb_max, &self.model_matrix()));
+        }
+        Err("No mesh loaded!".to_string())
+    }
+
+    #[wasm_bindgen]
+    pub fn change_shading(&mut self, shading: String) -> Result<(), String>{
+        // log(&format!("{:?}", shading));
+        let rendered_mesh = self.scene.active_mut().ok_or("No mesh loaded!")?;
+        return rendered_mesh.change_shading(self.gl.as_ref(), &shading);
+    }
+
+    /// curvature shown by false color shading: "mean", "gaussian", "max" or "min"
+    #[wasm_bindgen]
+    pub fn set_false_color_curvature(&mut self, curvature: String) -> Result<(), String>{
+        let curvature = match curvature.as_str(){
+            "mean" => CurvatureKind::Mean,
+            "gaussian" => CurvatureKind::Gaussian,
+            "max" => CurvatureKind::Maximum,
+            "min" => CurvatureKind::Minimum,
+            _ => return Err(format!("Unrecognized curvature: {}", curvature))
+        };
+        return self.edit_false_color(|false_color| false_color.set_source(ScalarSource::Curvature(curvature)));
+    }
+
+    /// "viridis", "jet" or "diverging"
+    #[wasm_bindgen]
+    pub fn set_color_map(&mut self, color_map: String) -> Result<(), String>{
+        let color_map = ColorMap::from_name(&color_map)?;
+        return self.edit_false_color(|false_color| false_color.color_map = color_map);
+    }
+
+    /// values mapped to the ends of the color map, beyond them colors are clamped
+    #[wasm_bindgen]
+    pub fn set_false_color_range(&mut self, min: f32, max: f32) -> Result<(), String>{
+        if min >= max || !min.is_finite() || !max.is_finite(){
+            return Err(format!("Invalid range: {} to {}", min, max));
+        }
+        return self.edit_false_color(|false_color| false_color.range = Some((min, max)));
+    }
+
+    /// goes back to a range fitted to the values, without the extreme few percent
+    #[wasm_bindgen]
+    pub fn fit_false_color_range(&mut self) -> Result<(), String>{
+        return self.edit_false_color(|false_color| false_color.range = None);
+    }
+
+    /// Attaches per-vertex values to the displayed mesh under `name`, replacing a field of the same name.
+    /// They follow the vertex ids of `vertex_curvature` and the selection, and are dropped when those change.
+    #[wasm_bindgen]
+    pub fn set_scalar_field(&mut self, name: String, values: Vec<f32>) -> Result<(), String>{
+        if let Some(rendered_mesh) = self.scene.active_mut(){
+            if values.len() != rendered_mesh.mesh.vert_count(){
+                return Err(format!("Expected {} values, got {}", rendered_mesh.mesh.vert_count(), values.len()));
+            }
+            rendered_mesh.scalar_fields.insert(name.clone(), values);
+            if rendered_mesh.false_color.source == ScalarSource::Field(name) && rendered_mesh.shading == ShadingType::FalseColor{
+                rendered_mesh.reload_gl_buffers(self.gl.as_ref())?;
+            }
+            return Ok(());
+        }
+        Err("No mesh loaded!".to_string())
+    }
+
+    /// removes a scalar field, false color goes back to mean curvature if it was shown
+    #[wasm_bindgen]
+    pub fn remove_scalar_field(&mut self, name: String) -> Result<(), String>{
+        if let Some(rendered_mesh) = self.scene.active_mut(){
+            if rendered_mesh.scalar_fields.remove(&name).is_none(){
+                return Err(format!("No scalar field named {}", name));
+            }
+            if rendered_mesh.false_color.source == ScalarSource::Field(name){
+                rendered_mesh.false_color.set_source(ScalarSource::Curvature(CurvatureKind::Mean));
+                if rendered_mesh.shading == ShadingType::FalseColor{
+                    rendered_mesh.reload_gl_buffers(self.gl.as_ref())?;
+                }
+            }
+            return Ok(());
+        }
+        Err("No mesh loaded!".to_string())
+    }
+
+    #[wasm_bindgen]
+    pub fn scalar_field_names(&self) -> Result<Vec<String>, String>{
+        if let Some(rendered_mesh) = self.scene.active(){
+            return Ok(rendered_mesh.scalar_fields.keys().cloned().collect());
+        }
+        Err("No mesh loaded!".to_string())
+    }
+
+    /// false color shows the named scalar field
+    #[wasm_bindgen]
+    pub fn show_scalar_field(&mut self, name: String) -> Result<(), String>{
+        if let Some(rendered_mesh) = self.scene.active()
+            && !rendered_mesh.scalar_fields.contains_key(&name){
+            return Err(format!("No scalar field named {}", name));
+        }
+        return self.edit_false_color(|false_color| false_color.set_source(ScalarSource::Field(name)));
+    }
+
+    /// values at which isolines are drawn over false color, empty for none
+    #[wasm_bindgen]
+    pub fn set_isolines(&mut self, levels: Vec<f32>) -> Result<(), String>{
+        if levels.iter().any(|level| !level.is_finite()){
+            return Err("Isoline levels must be finite".to_string());
+        }
+        return self.edit_false_color(|false_color| false_color.isoline_levels = levels);
+    }
+
+    /// `count` evenly spaced colors of the color map in use as flat [r, g, b] values in [0, 1], for the legend
+    #[wasm_bindgen]
+    pub fn color_map_colors(&self, count: usize) -> Result<Vec<f32>, String>{
+        if let Some(rendered_mesh) = self.scene.active(){
+            let color_map = rendered_mesh.false_color.color_map;
+            return Ok((0..count)
+                .flat_map(|i| color_map.color(i as f32 / (count.max(2) - 1) as f32).as_slice().to_vec())
+                .collect());
+        }
+        Err("No mesh loaded!".to_string())
+    }
+
+    /// [min, max] of the false color range in use, for the legend
+    #[wasm_bindgen]
+    pub fn false_color_range(&self) -> Result<Vec<f32>, String>{
+        if let Some(rendered_mesh) = self.scene.active(){
+            let (min, max) = rendered_mesh.false_color.range();
+            return Ok(vec![min, max]);
+        }
+        Err("No mesh loaded!".to_string())
+    }
+
+    /// one value per vertex of the displayed mesh: "mean", "gaussian", "max" or "min"
+    #[wasm_bindgen]
+    pub fn vertex_curvature(&self, curvature: String) -> Result<Vec<f32>, String>{
+        if let Some(rendered_mesh) = self.scene.active(){
+            let curvatures = rendered_mesh.mesh.curvature()?;
+            return match curvature.as_str(){
+                "mean" => Ok(curvatures.iter().map(|curvature| curvature.mean).collect()),
+                "gaussian" => Ok(curvatures.iter().map(|curvature| curvature.gaussian).collect()),
+                "max" => Ok(curvatures.iter().map(|curvature| curvature.max).collect()),
+                "min" => Ok(curvatures.iter().map(|curvature| curvature.min).collect()),
+                _ => Err(format!("Unrecognized curvature: {}", curvature))
+            };
+        }
+        Err("No mesh loaded!".to_string())
+    }
+
+    /// per vertex the direction of maximum then of minimum curvature, six values each
+    #[wasm_bindgen]
+    pub fn principal_directions(&self) -> Result<Vec<f32>, String>{
+        if let Some(rendered_mesh) = self.scene.active(){
+            return Ok(rendered_mesh.mesh.curvature()?.iter()
+                .flat_map(|curvature| curvature.max_direction.iter().chain(curvature.min_direction.iter()).cloned().collect::<Vec<f32>>())
+                .collect());
+        }
+        Err("No mesh loaded!".to_string())
+    }
+
+    #[wasm_bindgen]
+    pub fn update(&mut self, mouse_down: bool, mouse_x: i32, mouse_y: i32, mouse_wheel: i32) ->Result<(), JsValue>{
+        if mouse_down{
+            if !self.is_mouse_down{ // set anchor
+                self.mouse_anchor = Point2::new(mouse_x, mouse_y);
+            } else { // move
+                self.camera.mouse_move(Vector2::new(
+                    (self.mouse_anchor.x - mouse_x) as f32, 
+                    (self.mouse_anchor.y - mouse_y) as f32));
+                self.mouse_anchor = Point2::new(mouse_x, mouse_y);
+            }
+        }
+        self.is_mouse_down = mouse_down;
+
+        self.camera.mouse_scroll(mouse_wheel as f32);
+        // log(&format!("displaying mesh {:?}", mouse_wheel));
+
+        Ok(())
+    }
+
+    /// the rest of the transforms come from the frame's uniform block
+    fn pass_model_uniform(&self, gl: &dyn Backend, program: ProgramId, model: &Matrix4<f32>){
+        gl.set_uniform(program, "model", UniformValue::Mat4(model.as_slice()));
+    }
+
+    #[wasm_bindgen]
+    pub fn render(&mut self) -> Result<(), JsValue> {
+        let current_time_step = (window().unwrap().performance().unwrap().now() as f32) / 1000.0;
+        return Ok(self.render_frame(current_time_step)?);
+    }
+
+    /// draws the scene as of `current_time_step`, in seconds, which drives the load animation
+    fn render_frame(&mut self, current_time_step: f32) -> Result<(), String> {
+        if self.scene.is_empty() {
+            return Err("No mesh loaded!".to_string());
+        }
+        
+        // update anim time BEGIN
+        let delta_time = current_time_step-self.last_time_step;
+        self.last_time_step = current_time_step;
+
+        self.anim_time_counter += delta_time;
+
+        if self.should_run_animation{
+            self.anim_time_counter = 0.0;
+            self.should_run_animation = false;
+        }
+        // update anim time END
+
+        for node in self.scene.nodes_mut(){
+            let Some(rendered_mesh) = node.rendered_mesh.as_mut() else {continue};
+            let scale = (0..3).map(|axis| node.world.fixed_view::<3, 1>(0, axis).norm()).fold(0.0, f32::max);
+            let screen_size = self.camera.projected_size(rendered_mesh.mesh.bounding_radius() * scale, &self.screen_dimensions);
+            rendered_mesh.update_lod(screen_size, self.is_lod_enabled);
+        }
+
+        let gl = self.gl.as_ref();
+
+        let projection = Camera::projection_matrix(&(self.screen_dimensions));
+        let view = self.camera.view_matrix();
+        self.frame_uniforms.upload(gl, &FrameUniforms{projection: projection, view: view, anim_time: self.anim_time_counter});
+        self.light_uniforms.upload(gl, &LightUniforms{position: self.camera.position.coords, color: Vector3::new(1.0, 1.0, 1.0)});
+
+        gl.clear_color([0.0, 0.0, 0.0, 1.0]);
+        gl.clear(true, true, true);
+
+        for node in self.scene.nodes(){
+            let Some(rendered_mesh) = &node.rendered_mesh else {continue};
+            let program = match rendered_mesh.shading{
+                ShadingType::Flat => {self.programs.program_flat},
+                ShadingType::Smooth | ShadingType::FalseColor => {self.programs.program_smooth},
+                ShadingType::Wireframe => {self.programs.program_lines}
+            };
+
+            gl.use_program(program);
+
+            let model = node.world;
+
+            // Pass uniforms BEGIN
+            gl.set_uniform(program, "objectColor", UniformValue::Vec3([rendered_mesh.color.x, rendered_mesh.color.y, rendered_mesh.color.z]));
+
+            self.pass_model_uniform(gl, program, &model);
+            self.pass_clip_plane_uniforms(gl, program, &self.clip_planes_except(None));
+
+            if rendered_mesh.shading != ShadingType::Wireframe{
+                gl.set_uniform(program, "normalMatrix", UniformValue::Mat3(node.normal_matrix.as_slice()));
+            }
+            // Pass uniforms END
+
+            // contour mode shows the section instead of the surface
+            let surface_chunks: &[GLBuffers] = if rendered_mesh.section.is_none() {rendered_mesh.active_gl_buffers()} else {&[]};
+
+            let primitive = if rendered_mesh.shading == ShadingType::Wireframe {Primitive::Lines} else {Primitive::Triangles};
+            for chunk in surface_chunks{
+                Renderer::draw_instances(gl, primitive, chunk, rendered_mesh.instance_buffer.as_ref());
+            }
+
+            if !surface_chunks.is_empty() && rendered_mesh.shading == ShadingType::FalseColor{
+                self.draw_lines(gl, &rendered_mesh.isoline_gl_buffers, Renderer::ISOLINE_COLOR, &model)?;
+            }
+
+            let has_clip_planes = self.clip_planes.iter().any(|clip_plane| clip_plane.is_some());
+            if has_clip_planes && self.are_clip_caps_enabled && rendered_mesh.shading != ShadingType::Wireframe{
+                self.draw_clip_caps(gl, surface_chunks, rendered_mesh.instance_buffer.as_ref(), &model)?;
+            }
+
+            if let Some(section) = &rendered_mesh.section{
+                self.draw_section(gl, section, &model)?;
+            }
+
+            if !rendered_mesh.selection_gl_buffers.is_empty(){ // highlight the selection over the surface
+                let highlight_program = self.programs.program_lines;
+                gl.use_program(highlight_program);
+
+                let [r, g, b] = Renderer::SELECTION_COLOR;
+                gl.set_uniform(highlight_program, "objectColor", UniformValue::Vec3([r, g, b]));
+
+                self.pass_model_uniform(gl, highlight_program, &model);
+                self.pass_clip_plane_uniforms(gl, highlight_program, &self.clip_planes_except(None));
+
+                gl.set_enabled(Capability::Blend, true);
+                gl.blend_constant_alpha(Renderer::SELECTION_OPACITY);
+                gl.set_enabled(Capability::PolygonOffsetFill, true); // pull the faces in front of the surface they cover
+                gl.polygon_offset(-1.0, -1.0);
+                gl.depth_func(CompareFunc::LessEqual);
+
+                let primitive = match rendered_mesh.selection.mode(){
+                    SelectionMode::Vertices => Primitive::Points,
+                    SelectionMode::Faces | SelectionMode::Parts => Primitive::Triangles
+                };
+
+                for chunk in &rendered_mesh.selection_gl_buffers{
+                    chunk.draw(gl, primitive);
+                }
+
+                gl.depth_func(CompareFunc::Less);
+                gl.set_enabled(Capability::PolygonOffsetFill, false);
+                gl.set_enabled(Capability::Blend, false);
+            }
+
+            if self.is_bb_visible{
+                let bb_program = self.programs.program_lines;
+                gl.use_program(bb_program);
+
+                gl.set_uniform(bb_program, "objectColor", UniformValue::Vec3([1.0, 0.0, 0.0]));
+                self.pass_model_uniform(gl, bb_program, &model);
+                self.pass_clip_plane_uniforms(gl, bb_program, &[]);
+
+                for bb_gl_buffers in &rendered_mesh.bb_gl_buffers{            //render bounding volume
+                    bb_gl_buffers.draw(gl, Primitive::Lines);
+                }
+            }
+        }
+
+        if !self.dimension_gl_buffers.is_empty(){ // dimension lines, always on top
+            let dimension_program = self.programs.program_lines;
+            gl.use_program(dimension_program);
+
+            let [r, g, b] = Renderer::DIMENSION_COLOR;
+            gl.set_uniform(dimension_program, "objectColor", UniformValue::Vec3([r, g, b]));
+
+            self.pass_model_uniform(gl, dimension_program, &self.model_matrix());
+            self.pass_clip_plane_uniforms(gl, dimension_program, &[]);
+
+            gl.set_enabled(Capability::DepthTest, false);
+            for chunk in &self.dimension_gl_buffers{
+                chunk.draw(gl, Primitive::Lines);
+            }
+            gl.set_enabled(Capability::DepthTest, true);
+        }
+
+        if let Some(reference) = self.reference.as_ref().filter(|_| self.is_reference_visible){
+            self.draw_lines(gl, &reference.gl_buffers, Renderer::REFERENCE_COLOR, &Matrix4::identity())?;
+        }
+
+        Ok(())
+    }
+}
+
+pub async fn fetch_resource_as_str(path : &str) -> Result<String, JsValue>{
+    let resp = JsFuture::from(window().unwrap().fetch_with_str(path)).await?;
+    let resp: Response = resp.dyn_into().unwrap();
+    let text = JsFuture::from(resp.text()?).await?;
+    let obj_str = text.as_string().unwrap();
+    Ok(obj_str)
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+    use backend::SoftwareBackend;
+
+    const SIZE: i32 = 64;
+
+    fn sphere_renderer() -> Renderer{
+        let backend = SoftwareBackend::new(SIZE as usize, SIZE as usize);
+        let mut renderer = Renderer::with_backend(Box::new(backend), SIZE, SIZE).unwrap();
+        renderer.add_object("sphere".to_string(), include_str!("../assets/sphere.obj").to_string(), None).unwrap();
+        return renderer;
+    }
+
+    /// RGBA of the pixel at (x, y) from the bottom left, after the load animation played out
+    fn render(renderer: &mut Renderer) -> impl Fn(i32, i32) -> [u8; 4] + use<>{
+        renderer.render_frame(0.0).unwrap();
+        renderer.render_frame(2.0).unwrap();
+        let (width, _, pixels) = renderer.gl.read_pixels().unwrap();
+        return move |x, y| {
+            let i = (y as usize * width + x as usize) * 4;
+            [pixels[i], pixels[i + 1], pixels[i + 2], pixels[i + 3]]
+        };
+    }
+
+    #[test]
+    fn renders_the_lit_sphere(){
+        let mut renderer = sphere_renderer();
+        let pixel = render(&mut renderer);
+        let center = pixel(SIZE / 2, SIZE / 2);
+        assert!(center[0] > 200 && center[0] == center[1] && center[1] == center[2], "{:?}", center);
+        assert_eq!(pixel(0, 0), [0, 0, 0, 255]);
+    }
+
+    #[test]
+    fn object_color_and_wireframe(){
+        let mut renderer = sphere_renderer();
+        let id = renderer.active_object().unwrap();
+        renderer.set_object_color(id, 1.0, 0.0, 0.0).unwrap();
+        let pixel = render(&mut renderer);
+        let center = pixel(SIZE / 2, SIZE / 2);
+        assert!(center[0] > 200 && center[1] == 0 && center[2] == 0, "{:?}", center);
+
+        renderer.set_object_shading(id, "wireframe".to_string()).unwrap();
+        let pixel = render(&mut renderer);
+        let lit = (0..SIZE).flat_map(|y| (0..SIZE).map(move |x| (x, y))).filter(|&(x, y)| pixel(x, y) != [0, 0, 0, 255]).count();
+        assert!(lit > 0 && lit < (SIZE * SIZE / 2) as usize, "{} lit pixels", lit);
+    }
+
+    #[test]
+    fn instances_are_lit_by_their_own_normals(){
+        let mut renderer = sphere_renderer();
+        let id = renderer.active_object().unwrap();
+        let single = render(&mut renderer)(SIZE / 2, SIZE / 2);
+
+        // squashed along the view direction the front still faces the camera
+        let squash = Matrix4::new_nonuniform_scaling(&Vector3::new(1.0, 1.0, 0.25));
+        renderer.set_instances(id, squash.as_slice().to_vec(), vec![]).unwrap();
+        assert_eq!(render(&mut renderer)(SIZE / 2, SIZE / 2), single);
+
+        // a normal stays perpendicular to the surface the transform sheared
+        let shear = Matrix4::new(1.0, 2.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 1.0);
+        let instance = Instance::new(shear, Vector3::new(1.0, 1.0, 1.0)).unwrap();
+        let tangent = shear.transform_vector(&Vector3::new(1.0, -1.0, 0.0));
+        let normal = instance.normal_matrix * Vector3::new(1.0, 1.0, 0.0);
+        assert!(tangent.dot(&normal).abs() < 1e-6);
+
+        let flat = Matrix4::new_nonuniform_scaling(&Vector3::new(1.0, 1.0, 0.0));
+        assert!(renderer.set_instances(id, flat.as_slice().to_vec(), vec![]).is_err());
+        let mut broken = Matrix4::<f32>::identity();
+        broken[(0, 3)] = f32::NAN;
+        assert!(renderer.set_instances(id, broken.as_slice().to_vec(), vec![]).is_err());
+    }
+
+    #[test]
+    fn component_ids_follow_the_subdivided_mesh(){
+        // a quad cube of 12 triangles and a fan of 10, the fan gains more faces from subdividing
+        let mut obj = crate::mesh::tests::UNIT_CUBE.to_string();
+        obj.push_str("v 3 0 0\n");
+        for i in 0..10{
+            let angle = i as f32 * std::f32::consts::TAU / 10.0;
+            obj.push_str(&format!("v {} {} 0\n", 3.0 + angle.cos(), angle.sin()));
+        }
+        for i in 0..10{
+            obj.push_str(&format!("f 9 {} {}\n", 10 + i, 10 + (i + 1) % 10));
+        }
+
+        let backend = SoftwareBackend::new(SIZE as usize, SIZE as usize);
+        let mut renderer = Renderer::with_backend(Box::new(backend), SIZE, SIZE).unwrap();
+        renderer.add_object("parts".to_string(), obj, None).unwrap();
+        let face_counts = |renderer: &Renderer| renderer.components().unwrap().iter().map(|c| c.face_count()).collect::<Vec<_>>();
+        assert_eq!(face_counts(&renderer), vec![12, 10]);
+
+        renderer.set_subdivision_level(1).unwrap();
+        assert_eq!(face_counts(&renderer), vec![60, 48]);
+
+        renderer.set_component_visible(0, false).unwrap();
+        let hidden = renderer.components().unwrap().iter().map(|c| c.hidden()).collect::<Vec<_>>();
+        assert_eq!(hidden, vec![true, false]);
+        assert!(renderer.set_component_visible(2, false).is_err());
+
+        // the cube goes, the fan left on its own is all triangles and gets Loop subdivided
+        assert_eq!(renderer.delete_small_components(50).unwrap(), 1);
+        assert_eq!(face_counts(&renderer), vec![40]);
+        assert!(renderer.components().unwrap()[0].hidden());
+    }
+
+    /// `count` separate triangles along the x axis, positions only
+    fn separate_triangles(count: usize) -> (Vec<f32>, Vec<usize>){
+        let vertices = (0..count * 3).flat_map(|i| [(i / 3) as f32, (i % 3 == 2) as u8 as f32, 0.0]).collect();
+        return (vertices, (0..count * 3).collect());
+    }
+
+    #[test]
+    fn index_size_follows_the_vertex_count(){
+        let backend = SoftwareBackend::new(SIZE as usize, SIZE as usize);
+        let (vertices, indices) = separate_triangles(1000);
+        let buffers = GLBuffers::create_indexed(&vertices, &indices, 3, 3, &backend).unwrap();
+        assert_eq!(buffers.len(), 1);
+        assert_eq!(backend.has_wide_indices(buffers[0].vertex_array), Some(false));
+
+        // past 65535 vertices a single vertex array with 32 bit indices
+        let (vertices, indices) = separate_triangles(30000);
+        let buffers = GLBuffers::create_indexed(&vertices, &indices, 3, 3, &backend).unwrap();
+        assert_eq!(buffers.len(), 1);
+        assert_eq!(backend.has_wide_indices(buffers[0].vertex_array), Some(true));
+
+        // split into 16 bit chunks when the backend can't index that many
+        let backend = SoftwareBackend::with_max_element_index(SIZE as usize, SIZE as usize, u16::MAX as u32);
+        let buffers = GLBuffers::create_indexed(&vertices, &indices, 3, 3, &backend).unwrap();
+        assert_eq!(buffers.len(), 2);
+        assert!(buffers.iter().all(|chunk| backend.has_wide_indices(chunk.vertex_array) == Some(false)));
+    }
+
+    /// image of the triangles, in clip coordinates, drawn through the buffers `create_indexed` makes on the backend
+    fn draw_indexed(backend: &SoftwareBackend, vertices: &[f32], indices: &[usize]) -> Vec<u8>{
+        let buffers = GLBuffers::create_indexed(vertices, indices, 3, 3, backend).unwrap();
+        let frame = FrameUniforms{projection: Matrix4::identity(), view: Matrix4::identity(), anim_time: 1.0};
+        UniformBuffer::<FrameUniforms>::create(backend).unwrap().upload(backend, &frame);
+        let program = backend.create_program(ProgramKind::Lines).unwrap();
+        backend.use_program(program);
+        backend.set_uniform(program, "model", UniformValue::Mat4(Matrix4::<f32>::identity().as_slice()));
+        backend.set_uniform(program, "objectColor", UniformValue::Vec3([1.0, 1.0, 1.0]));
+        backend.set_enabled(Capability::CullFace, false);
+        backend.clear(true, true, true);
+        for chunk in &buffers{
+            chunk.draw(backend, Primitive::Triangles);
+        }
+        return backend.read_pixels().unwrap().2;
+    }
+
+    #[test]
+    fn chunked_meshes_render_the_same(){
+        // small triangles scattered over the view, a wrong index after the split stretches one across it
+        let mut seed = 1u32;
+        let mut random = move || {
+            seed = seed.wrapping_mul(1664525).wrapping_add(1013904223);
+            (seed >> 8) as f32 / (1 << 24) as f32 * 2.0 - 1.0
+        };
+        let count = 25000;
+        let mut vertices = vec![];
+        for _ in 0..count{
+            let (x, y) = (random(), random());
+            vertices.extend_from_slice(&[x, y, 0.0, x + 0.02, y, 0.0, x, y + 0.02, 0.0]);
+        }
+        let indices: Vec<usize> = (0..count * 3).collect();
+
+        let whole = SoftwareBackend::new(SIZE as usize, SIZE as usize);
+        let chunked = SoftwareBackend::with_max_element_index(SIZE as usize, SIZE as usize, u16::MAX as u32);
+        assert_eq!(GLBuffers::create_indexed(&vertices, &indices, 3, 3, &chunked).unwrap().len(), 2);
+        let image = draw_indexed(&whole, &vertices, &indices);
+        assert!(image.chunks_exact(4).any(|pixel| pixel != [0, 0, 0, 0]));
+        assert!(image == draw_indexed(&chunked, &vertices, &indices));
+    }
+
+    #[test]
+    fn deleting_small_components_keeps_the_largest(){
+        let backend = SoftwareBackend::new(SIZE as usize, SIZE as usize);
+        let mut renderer = Renderer::with_backend(Box::new(backend), SIZE, SIZE).unwrap();
+        renderer.add_object("cubes".to_string(), crate::mesh::tests::two_cubes_obj(), None).unwrap();
+
+        // both cubes are under the threshold, only one goes
+        assert_eq!(renderer.delete_small_components(100).unwrap(), 1);
+        let components = renderer.components().unwrap();
+        assert_eq!(components.len(), 1);
+        assert_eq!(components[0].face_count(), 12);
+        assert_eq!(renderer.delete_small_components(100).unwrap(), 0);
+    }
+
+    #[test]
+    fn picks_the_surface_under_the_pixel(){
+        let mut renderer = sphere_renderer();
+        renderer.camera = Camera::new(Point3::new(0.0, 0.0, 5.0), Point3::origin(), Vector3::y());
+        let radius = renderer.scene.active().unwrap().mesh.bounding_sphere().radius;
+
+        // the canvas center looks at the front of the sphere, straight down the view axis
+        let hit = renderer.pick(SIZE / 2, SIZE / 2).unwrap().unwrap().point;
+        assert!(hit.position.xy().norm() < radius * 0.05 && (hit.position.z - radius).abs() < radius * 0.05, "{:?}", hit.position);
+        assert!(hit.normal.z > 0.99, "{:?}", hit.normal);
+        assert!((hit.distance - (5.0 - hit.position.z)).abs() < 0.15, "{}", hit.distance);
+
+        // pixels count from the top, the ray goes through the upper half
+        let hit = renderer.pick(SIZE / 2, SIZE * 3 / 8).unwrap().unwrap().point;
+        assert!(hit.position.y > radius * 0.3 && hit.position.x.abs() < radius * 0.05, "{:?}", hit.position);
+
+        assert!(renderer.pick(0, 0).unwrap().is_none());
+
+        // hits come back in world space
+        let moved = Matrix4::new_translation(&Vector3::new(0.0, 0.0, 1.0));
+        renderer.set_model_matrix(moved.as_slice().to_vec()).unwrap();
+        let hit = renderer.pick(SIZE / 2, SIZE / 2).unwrap().unwrap().point;
+        assert!((hit.position.z - radius - 1.0).abs() < radius * 0.05, "{:?}", hit.position);
+    }
+
+    #[test]
+    fn surface_queries_are_in_world_space(){
+        let mut renderer = sphere_renderer();
+        let radius = renderer.scene.active().unwrap().mesh.bounding_sphere().radius;
+        let moved = Matrix4::new_translation(&Vector3::new(0.0, 0.0, 1.0)) * Matrix4::new_scaling(2.0);
+        renderer.set_model_matrix(moved.as_slice().to_vec()).unwrap();
+        let front = 1.0 + radius * 2.0;
+
+        let hit = renderer.ray_cast(0.0, 0.0, 10.0, 0.0, 0.0, -1.0).unwrap().unwrap().point;
+        assert!((hit.position.z - front).abs() < 0.05 && hit.normal.z > 0.99, "{:?}", hit);
+        assert!((hit.distance - (10.0 - hit.position.z)).abs() < 1e-4);
+        assert!(renderer.ray_cast(3.0, 0.0, 10.0, 0.0, 0.0, -1.0).unwrap().is_none());
+
+        let closest = renderer.closest_point(0.0, 0.0, 10.0).unwrap().unwrap().point;
+        assert!((closest.position.z - front).abs() < 0.05 && (closest.distance - (10.0 - front)).abs() < 0.05, "{:?}", closest);
+
+        let near = |z: f32| renderer.faces_in_box(-0.1, -0.1, z - 0.1, 0.1, 0.1, z + 0.1).unwrap();
+        assert!(!near(front).is_empty());
+        assert!(near(radius).is_empty()); // the front before moving, now inside the sphere
+    }
+
+    #[test]
+    fn clip_caps_fill_the_cut_section(){
+        let backend = SoftwareBackend::new(SIZE as usize, SIZE as usize);
+        let mut renderer = Renderer::with_backend(Box::new(backend), SIZE, SIZE).unwrap();
+        renderer.add_object("cube".to_string(), crate::mesh::tests::UNIT_CUBE.to_string(), None).unwrap();
+        renderer.camera = Camera::new(Point3::new(0.0, 0.0, 3.0), Point3::origin(), Vector3::y());
+
+        // cut through the middle, the front half goes and the camera looks into the cube
+        renderer.set_clip_plane(0, 0.0, 0.0, 1.0, 0.0).unwrap();
+        renderer.set_clip_caps_enabled(false).unwrap();
+        assert_eq!(render(&mut renderer)(SIZE / 2, SIZE / 2), [0, 0, 0, 255]);
+
+        renderer.set_clip_caps_enabled(true).unwrap();
+        let pixel = render(&mut renderer);
+        let cap = Renderer::CLIP_CAP_COLOR.map(|value| value * 255.0);
+        let center = pixel(SIZE / 2, SIZE / 2);
+        assert!((0..3).all(|channel| (center[channel] as f32 - cap[channel]).abs() <= 1.0), "{:?}", center);
+
+        // the cap quad reaches past the cube, the stencil keeps it to the section
+        assert_eq!(pixel(2, SIZE / 2), [0, 0, 0, 255]);
+        assert_eq!(pixel(SIZE / 2, 2), [0, 0, 0, 255]);
+    }
+
+    #[test]
+    fn object_transforms_must_be_finite(){
+        let mut renderer = sphere_renderer();
+        let id = renderer.active_object().unwrap();
+        let world = renderer.model_matrix();
+        assert!(renderer.set_object_transform(id, vec![f32::NAN, 0.0, 0.0], vec![0.0; 3], vec![1.0; 3]).is_err());
+        assert!(renderer.set_object_transform(id, vec![0.0; 3], vec![f32::INFINITY, 0.0, 0.0], vec![1.0; 3]).is_err());
+        assert!(renderer.set_object_transform(id, vec![0.0; 3], vec![0.0; 3], vec![1.0, f32::NEG_INFINITY, 1.0]).is_err());
+        assert_eq!(renderer.model_matrix(), world);
+
+        let mut values = Matrix4::<f32>::identity().as_slice().to_vec();
+        values[12] = f32::NAN;
+        assert!(renderer.set_model_matrix(values.clone()).is_err());
+        values[12] = f32::INFINITY;
+        assert!(renderer.set_model_matrix(values).is_err());
+        assert!(renderer.set_model_matrix(Matrix4::<f32>::new_scaling(0.0).as_slice().to_vec()).is_err());
+        assert_eq!(renderer.model_matrix(), world);
+
+        renderer.set_object_transform(id, vec![1.0, 0.0, 0.0], vec![0.0, 90.0, 0.0], vec![2.0; 3]).unwrap();
+        assert!((renderer.model_matrix().column(3).xyz() - Vector3::new(1.0, 0.0, 0.0)).norm() < 1e-6);
+    }
+
+    #[test]
+    fn objects_are_named_after_their_groups(){
+        let mut renderer = sphere_renderer();
+        let triangle = "v 0 0 0\nv 1 0 0\nv 0 1 0\n";
+        let named = renderer.add_object("file".to_string(), format!("{}o wheel\nf 1 2 3\n", triangle), None).unwrap();
+        let unnamed = renderer.add_object("file".to_string(), format!("{}f 1 2 3\n", triangle), None).unwrap();
+        let grouped = renderer.add_object("file".to_string(), format!("{}g a\nf 1 2 3\ng b\nf 1 3 2\n", triangle), None).unwrap();
+
+        let objects = renderer.objects();
+        let name = |id: u32| objects.iter().find(|object| object.id == id).unwrap().name.clone();
+        assert_eq!((name(named), name(unnamed), name(grouped)), ("wheel".to_string(), "file".to_string(), "file".to_string()));
+        let children: Vec<String> = objects.iter().filter(|object| object.parent == Some(grouped)).map(|object| object.name.clone()).collect();
+        assert_eq!(children, vec!["a".to_string(), "b".to_string()]);
+    }
+
+    #[test]
+    fn clip_planes_cut_the_surface(){
+        let mut renderer = sphere_renderer();
+        renderer.set_clip_plane(0, 1.0, 0.0, 0.0, 0.0).unwrap();
+        let pixel = render(&mut renderer);
+        assert_ne!(pixel(SIZE / 2 - 4, SIZE / 2), [0, 0, 0, 255]);
+        assert_ne!(pixel(SIZE / 2 + 4, SIZE / 2), pixel(SIZE / 2 - 4, SIZE / 2));
+    }
+}
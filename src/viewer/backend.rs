@@ -0,0 +1,132 @@
+//! What the renderer needs from the GPU: buffers, the shader programs, render state and draws.
+//! WebGL2 implements it in the browser, a CPU rasterizer for native tests.
+
+#[cfg(test)]
+mod software;
+mod webgl;
+
+#[cfg(test)]
+pub use software::SoftwareBackend;
+pub use webgl::WebGlBackend;
+
+/// values per instance in an instance buffer, a column major model matrix, an RGB color
+/// then the column major inverse transpose of the matrix's upper 3x3 for the normals
+pub const VALUES_PER_INSTANCE: usize = 28;
+
+/// vertex buffer, element buffer and the attribute setup reading them
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct VertexArrayId(u32);
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct BufferId(u32);
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct ProgramId(u32);
+
+/// the programs of shaders.rs
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ProgramKind{
+    Flat,
+    Smooth,
+    Lines // also points, in a single color
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Primitive{
+    Points,
+    Lines,
+    Triangles
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Capability{
+    DepthTest,
+    CullFace, // back faces, counter-clockwise is front
+    StencilTest,
+    Blend,
+    PolygonOffsetFill
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum CompareFunc{
+    Always,
+    Less,
+    LessEqual,
+    Equal,
+    NotEqual
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum StencilOp{
+    Keep,
+    Invert,
+    IncrWrap,
+    DecrWrap
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Face{
+    Front,
+    Back,
+    FrontAndBack
+}
+
+pub enum Indices<'a>{
+    U16(&'a [u16]),
+    U32(&'a [u32])
+}
+
+pub enum UniformValue<'a>{
+    Int(i32),
+    Vec3([f32; 3]),
+    Vec4Array(&'a [f32]),
+    Mat3(&'a [f32]), // column major
+    Mat4(&'a [f32])
+}
+
+/// Handles are only valid with the backend that created them. Methods take `&self`, backends keep their
+/// state behind cells like the GL context does.
+pub trait Backend{
+    /// largest vertex index one vertex array can use with 32 bit indices
+    fn max_element_index(&self) -> u32;
+
+    /// Uploads vertices of 3 (positions) or 9 (positions, normals, colors) values and the indices of their primitives.
+    fn create_vertex_array(&self, vertices: &[f32], values_per_vertex: usize, indices: Indices) -> Result<VertexArrayId, String>;
+    fn delete_vertex_array(&self, vertex_array: VertexArrayId);
+    /// makes the vertex array read the instance attributes from `buffer`, without it places a single white copy
+    fn set_instance_buffer(&self, vertex_array: VertexArrayId, buffer: Option<BufferId>);
+
+    /// `VALUES_PER_INSTANCE` values per instance
+    fn create_instance_buffer(&self, values: &[f32]) -> Result<BufferId, String>;
+    /// buffer of `size` bytes feeding the uniform block at `binding` in every program
+    fn create_uniform_buffer(&self, binding: u32, size: usize) -> Result<BufferId, String>;
+    /// the block's std140 contents
+    fn update_uniform_buffer(&self, buffer: BufferId, values: &[f32]);
+    fn delete_buffer(&self, buffer: BufferId);
+
+    fn create_program(&self, kind: ProgramKind) -> Result<ProgramId, String>;
+    fn use_program(&self, program: ProgramId);
+    /// sets a uniform of `program`, which has to be in use. Uniforms the program doesn't have are skipped.
+    fn set_uniform(&self, program: ProgramId, name: &str, value: UniformValue);
+
+    fn viewport(&self, width: i32, height: i32);
+    fn set_enabled(&self, capability: Capability, enabled: bool);
+    fn clear_color(&self, color: [f32; 4]);
+    fn clear(&self, color: bool, depth: bool, stencil: bool);
+    fn depth_func(&self, func: CompareFunc);
+    fn depth_mask(&self, write: bool);
+    fn color_mask(&self, write: bool);
+    fn stencil_func(&self, func: CompareFunc, reference: i32, mask: u32);
+    /// what happens to the stencil when the stencil test fails, the depth test fails and both pass
+    fn stencil_op(&self, face: Face, stencil_fail: StencilOp, depth_fail: StencilOp, depth_pass: StencilOp);
+    /// blending with the constant alpha as the source's weight
+    fn blend_constant_alpha(&self, alpha: f32);
+    fn polygon_offset(&self, factor: f32, units: f32);
+
+    /// draws the vertex array's primitives, once per instance of its instance buffer when given the count
+    fn draw(&self, vertex_array: VertexArrayId, primitive: Primitive, instance_count: Option<i32>);
+
+    /// width, height and RGBA bytes of the drawn image, rows from the bottom
+    #[cfg(test)]
+    fn read_pixels(&self) -> Result<(usize, usize, Vec<u8>), String>;
+}
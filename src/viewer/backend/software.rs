@@ -0,0 +1,717 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+use nalgebra::{Matrix3, Matrix4, Vector2, Vector3, Vector4};
+
+use crate::viewer::uniforms::{std140_layout, FrameUniforms, LightUniforms, UniformBlock};
+use super::{Backend, BufferId, Capability, CompareFunc, Face, Indices, Primitive, ProgramId, ProgramKind, StencilOp, UniformValue, VertexArrayId, VALUES_PER_INSTANCE};
+
+/// gl_PointSize of the line shader
+const POINT_SIZE: f32 = 6.0;
+/// share of the base color lighting doesn't change, as in the shaded fragment shaders
+const AMBIENT_STRENGTH: f32 = 0.1;
+/// depth difference one polygon offset unit stands for, a 24 bit depth buffer's
+const DEPTH_RESOLUTION: f32 = 1.0 / (1 << 24) as f32;
+/// primitives with a vertex this close to the camera plane or behind it are dropped instead of clipped
+const MIN_CLIP_W: f32 = 1e-6;
+
+struct VertexArray{
+    vertices: Vec<f32>,
+    values_per_vertex: usize,
+    indices: Vec<u32>,
+    wide_indices: bool, // created from 32 bit indices
+    instance_buffer: Option<BufferId>
+}
+
+struct Program{
+    kind: ProgramKind,
+    uniforms: HashMap<String, Vec<f32>> // unset ones read as zeros, like in GL
+}
+
+#[derive(Clone, Copy)]
+struct StencilOps{
+    stencil_fail: StencilOp,
+    depth_fail: StencilOp,
+    depth_pass: StencilOp
+}
+
+/// the images drawn into and the state deciding what a fragment does to them
+struct Target{
+    width: usize,
+    height: usize,
+    color: Vec<[u8; 4]>, // rows from the bottom
+    depth: Vec<f32>,
+    stencil: Vec<u8>,
+    clear_color: [f32; 4],
+    depth_test: bool,
+    cull_face: bool,
+    stencil_test: bool,
+    blend: bool,
+    polygon_offset_fill: bool,
+    depth_func: CompareFunc,
+    depth_mask: bool,
+    color_mask: bool,
+    stencil_func: (CompareFunc, i32, u32),
+    stencil_ops: [StencilOps; 2], // front, back
+    blend_alpha: f32,
+    polygon_offset: (f32, f32) // factor, units
+}
+
+struct State{
+    target: Target,
+    vertex_arrays: HashMap<VertexArrayId, VertexArray>,
+    buffers: HashMap<BufferId, Vec<f32>>,
+    uniform_bindings: HashMap<u32, BufferId>,
+    programs: HashMap<ProgramId, Program>,
+    current_program: Option<ProgramId>,
+    next_id: u32
+}
+
+/// Rasterizes on the CPU what the shaders of shaders.rs would draw, for tests without a GPU.
+/// Follows GL's rules closely enough for images to be compared, but it isn't pixel exact with any GPU.
+pub struct SoftwareBackend{
+    state: RefCell<State>,
+    max_element_index: u32
+}
+
+/// uniforms and uniform block values of a draw call
+struct DrawUniforms{
+    kind: ProgramKind,
+    model: Matrix4<f32>,
+    normal_matrix: Matrix3<f32>,
+    object_color: Vector3<f32>,
+    clip_planes: Vec<Vector4<f32>>,
+    view_projection: Matrix4<f32>,
+    anim_scale: f32,
+    light_pos: Vector3<f32>,
+    light_color: Vector3<f32>
+}
+
+struct Instance{
+    model: Matrix4<f32>,
+    normal_matrix: Matrix3<f32>,
+    color: Vector3<f32>
+}
+
+/// vertex shader outputs
+#[derive(Clone, Copy)]
+struct ShadedVertex{
+    clip: Vector4<f32>,
+    frag_pos: Vector3<f32>,
+    normal: Vector3<f32>,
+    color: Vector3<f32> // the vertex color for the shaded programs, the instance color for lines
+}
+
+/// fragment position in window coordinates, depth in [0, 1]
+struct Fragment{
+    x: usize,
+    y: usize,
+    depth: f32,
+    is_front: bool
+}
+
+impl SoftwareBackend{
+    pub fn new(width: usize, height: usize) -> SoftwareBackend{
+        return SoftwareBackend::with_max_element_index(width, height, u32::MAX);
+    }
+
+    /// backend passing for a GPU whose vertex arrays index at most `max_element_index` vertices
+    pub fn with_max_element_index(width: usize, height: usize, max_element_index: u32) -> SoftwareBackend{
+        let keep = StencilOps{stencil_fail: StencilOp::Keep, depth_fail: StencilOp::Keep, depth_pass: StencilOp::Keep};
+        let target = Target{width: width, height: height, color: vec![[0, 0, 0, 0]; width * height], depth: vec![1.0; width * height],
+            stencil: vec![0; width * height], clear_color: [0.0; 4], depth_test: true, cull_face: true, stencil_test: false, blend: false,
+            polygon_offset_fill: false, depth_func: CompareFunc::Less, depth_mask: true, color_mask: true,
+            stencil_func: (CompareFunc::Always, 0, u32::MAX), stencil_ops: [keep, keep], blend_alpha: 0.0, polygon_offset: (0.0, 0.0)};
+
+        return SoftwareBackend{state: RefCell::new(State{target: target, vertex_arrays: HashMap::new(), buffers: HashMap::new(),
+            uniform_bindings: HashMap::new(), programs: HashMap::new(), current_program: None, next_id: 0}), max_element_index: max_element_index};
+    }
+
+    /// whether the vertex array was created from 32 bit indices, None for a deleted one
+    pub fn has_wide_indices(&self, vertex_array: VertexArrayId) -> Option<bool>{
+        return self.state.borrow().vertex_arrays.get(&vertex_array).map(|vertex_array| vertex_array.wide_indices);
+    }
+}
+
+impl State{
+    fn next_id(&mut self) -> u32{
+        self.next_id += 1;
+        return self.next_id;
+    }
+
+    /// values of a member of the block bound at the block's binding point, zeros without one
+    fn block_member<B: UniformBlock>(&self, name: &str) -> Vec<f32>{
+        let (offsets, _) = std140_layout(B::MEMBERS);
+        let Some(i) = B::MEMBERS.iter().position(|(member_name, _)| *member_name == name) else {return vec![]};
+        let count = B::MEMBERS[i].1.size() / 4;
+        let start = offsets[i] / 4;
+
+        let values = self.uniform_bindings.get(&B::BINDING).and_then(|buffer| self.buffers.get(buffer));
+        return match values{
+            Some(values) if values.len() >= start + count => values[start..start + count].to_vec(),
+            _ => vec![0.0; count]
+        };
+    }
+
+    fn draw_uniforms(&self, program: &Program) -> DrawUniforms{
+        let uniform = |name: &str, count: usize| -> Vec<f32>{
+            let mut values = program.uniforms.get(name).cloned().unwrap_or_default();
+            values.resize(count, 0.0);
+            values
+        };
+
+        let clip_plane_count = uniform("clipPlaneCount", 1)[0] as usize;
+        let clip_plane_values = program.uniforms.get("clipPlanes").cloned().unwrap_or_default();
+        let clip_planes = clip_plane_values.chunks_exact(4).take(clip_plane_count).map(Vector4::from_column_slice).collect();
+
+        let projection = Matrix4::from_column_slice(&self.block_member::<FrameUniforms>("projection"));
+        let view = Matrix4::from_column_slice(&self.block_member::<FrameUniforms>("view"));
+        let anim_time = self.block_member::<FrameUniforms>("animTime")[0];
+        let ease_out_cubic = |x: f32| 1.0 - (1.0 - x).powi(3);
+
+        return DrawUniforms{kind: program.kind, model: Matrix4::from_column_slice(&uniform("model", 16)),
+            normal_matrix: Matrix3::from_column_slice(&uniform("normalMatrix", 9)),
+            object_color: Vector3::from_column_slice(&uniform("objectColor", 3)), clip_planes: clip_planes,
+            view_projection: projection * view, anim_scale: ease_out_cubic(anim_time.min(1.0)),
+            light_pos: Vector3::from_column_slice(&self.block_member::<LightUniforms>("lightPos")),
+            light_color: Vector3::from_column_slice(&self.block_member::<LightUniforms>("lightColor"))};
+    }
+}
+
+/// the flat, smooth and line vertex shaders
+fn shade_vertex(uniforms: &DrawUniforms, attributes: &[f32], instance: &Instance) -> ShadedVertex{
+    let position = Vector4::new(attributes[0], attributes[1], attributes[2], 1.0);
+    let frag_pos = (uniforms.model * instance.model * position).xyz();
+    let clip = uniforms.view_projection * (frag_pos * uniforms.anim_scale).push(1.0);
+
+    if uniforms.kind == ProgramKind::Lines || attributes.len() < 9{
+        return ShadedVertex{clip: clip, frag_pos: frag_pos, normal: Vector3::zeros(), color: instance.color};
+    }
+
+    let normal = uniforms.normal_matrix * instance.normal_matrix * Vector3::new(attributes[3], attributes[4], attributes[5]);
+    let color = Vector3::new(attributes[6], attributes[7], attributes[8]).component_mul(&instance.color);
+    return ShadedVertex{clip: clip, frag_pos: frag_pos, normal: normal, color: color};
+}
+
+/// the fragment shaders, None where a clip plane discards the fragment
+fn shade_fragment(uniforms: &DrawUniforms, frag_pos: &Vector3<f32>, normal: &Vector3<f32>, color: &Vector3<f32>) -> Option<Vector3<f32>>{
+    if uniforms.clip_planes.iter().any(|plane| plane.xyz().dot(frag_pos) > plane.w){
+        return None;
+    }
+
+    if uniforms.kind == ProgramKind::Lines{
+        return Some(uniforms.object_color.component_mul(color));
+    }
+
+    let base_color = color.component_mul(&uniforms.object_color);
+    let ambient = base_color * AMBIENT_STRENGTH;
+    let light_dir = (uniforms.light_pos - frag_pos).try_normalize(0.0).unwrap_or(Vector3::zeros());
+    let diff = normal.try_normalize(0.0).unwrap_or(Vector3::zeros()).dot(&light_dir).max(0.0);
+    let diffuse = uniforms.light_color * diff;
+    return Some((ambient + diffuse).component_mul(&base_color));
+}
+
+fn compare(func: CompareFunc, incoming: f32, stored: f32) -> bool{
+    return match func{
+        CompareFunc::Always => true,
+        CompareFunc::Less => incoming < stored,
+        CompareFunc::LessEqual => incoming <= stored,
+        CompareFunc::Equal => incoming == stored,
+        CompareFunc::NotEqual => incoming != stored
+    };
+}
+
+fn apply_stencil_op(op: StencilOp, value: u8) -> u8{
+    return match op{
+        StencilOp::Keep => value,
+        StencilOp::Invert => !value,
+        StencilOp::IncrWrap => value.wrapping_add(1),
+        StencilOp::DecrWrap => value.wrapping_sub(1)
+    };
+}
+
+/// twice the signed area of (a, b, p), positive when counter-clockwise
+fn edge(a: &Vector2<f32>, b: &Vector2<f32>, p: &Vector2<f32>) -> f32{
+    return (b.x - a.x) * (p.y - a.y) - (b.y - a.y) * (p.x - a.x);
+}
+
+/// Edges of a counter-clockwise triangle that own the pixels centered exactly on them, so triangles sharing
+/// an edge cover each pixel once. With y up the left edges go down and the top edge goes left.
+fn is_top_left(a: &Vector2<f32>, b: &Vector2<f32>) -> bool{
+    let d = b - a;
+    return d.y < 0.0 || (d.y == 0.0 && d.x < 0.0);
+}
+
+impl Target{
+    /// window coordinates, None behind the camera
+    fn to_window(&self, clip: &Vector4<f32>) -> Option<Vector3<f32>>{
+        if clip.w < MIN_CLIP_W{
+            return None;
+        }
+        let ndc = clip.xyz() / clip.w;
+        return Some(Vector3::new((ndc.x + 1.0) * 0.5 * self.width as f32, (ndc.y + 1.0) * 0.5 * self.height as f32, (ndc.z + 1.0) * 0.5));
+    }
+
+    /// stencil and depth tests and their writes, then the color write with blending
+    fn write_fragment(&mut self, fragment: &Fragment, color: Option<Vector3<f32>>){
+        let Some(color) = color else {return}; // discarded before any test
+        if !(0.0..=1.0).contains(&fragment.depth){ // clipped by the near or far plane
+            return;
+        }
+
+        let i = fragment.y * self.width + fragment.x;
+        let ops = self.stencil_ops[if fragment.is_front {0} else {1}];
+        if self.stencil_test{
+            let (func, reference, mask) = self.stencil_func;
+            if !compare(func, (reference as u32 & mask) as f32, (self.stencil[i] as u32 & mask) as f32){
+                self.stencil[i] = apply_stencil_op(ops.stencil_fail, self.stencil[i]);
+                return;
+            }
+        }
+
+        if self.depth_test && !compare(self.depth_func, fragment.depth, self.depth[i]){
+            if self.stencil_test{
+                self.stencil[i] = apply_stencil_op(ops.depth_fail, self.stencil[i]);
+            }
+            return;
+        }
+        if self.stencil_test{
+            self.stencil[i] = apply_stencil_op(ops.depth_pass, self.stencil[i]);
+        }
+        if self.depth_test && self.depth_mask{
+            self.depth[i] = fragment.depth;
+        }
+
+        if self.color_mask{
+            let mut rgb = [color.x, color.y, color.z];
+            if self.blend{
+                for (channel, value) in rgb.iter_mut().enumerate(){
+                    *value = *value * self.blend_alpha + self.color[i][channel] as f32 / 255.0 * (1.0 - self.blend_alpha);
+                }
+            }
+            let to_byte = |value: f32| (value.clamp(0.0, 1.0) * 255.0).round() as u8;
+            self.color[i] = [to_byte(rgb[0]), to_byte(rgb[1]), to_byte(rgb[2]), 255];
+        }
+    }
+
+    fn draw_triangle(&mut self, uniforms: &DrawUniforms, vertices: [&ShadedVertex; 3]){
+        let (Some(w0), Some(w1), Some(w2)) = (self.to_window(&vertices[0].clip), self.to_window(&vertices[1].clip), self.to_window(&vertices[2].clip)) else {return};
+        let mut window = [w0, w1, w2];
+        let mut vertices = vertices;
+        let flat_normal = vertices[2].normal; // the last vertex provokes flat outputs
+
+        let area = edge(&window[0].xy(), &window[1].xy(), &window[2].xy());
+        let is_front = area > 0.0;
+        if area == 0.0 || (self.cull_face && !is_front){
+            return;
+        }
+        if !is_front{ // rasterize counter-clockwise
+            window.swap(1, 2);
+            vertices.swap(1, 2);
+        }
+        let area = area.abs();
+
+        let mut depth_offset = 0.0;
+        if self.polygon_offset_fill{
+            let (p0, p1, p2) = (&window[0], &window[1], &window[2]);
+            let dz_dx = ((p1.z - p0.z) * (p2.y - p0.y) - (p2.z - p0.z) * (p1.y - p0.y)) / area;
+            let dz_dy = ((p2.z - p0.z) * (p1.x - p0.x) - (p1.z - p0.z) * (p2.x - p0.x)) / area;
+            let (factor, units) = self.polygon_offset;
+            depth_offset = factor * dz_dx.abs().max(dz_dy.abs()) + units * DEPTH_RESOLUTION;
+        }
+
+        let min_x = window.iter().map(|p| p.x).fold(f32::MAX, f32::min).floor().max(0.0) as usize;
+        let min_y = window.iter().map(|p| p.y).fold(f32::MAX, f32::min).floor().max(0.0) as usize;
+        let max_x = window.iter().map(|p| p.x).fold(f32::MIN, f32::max).ceil().min(self.width as f32) as usize;
+        let max_y = window.iter().map(|p| p.y).fold(f32::MIN, f32::max).ceil().min(self.height as f32) as usize;
+
+        let corners = [window[0].xy(), window[1].xy(), window[2].xy()];
+        let edges = [(1, 2), (2, 0), (0, 1)]; // opposite to each vertex
+        let owns_edge = edges.map(|(a, b)| is_top_left(&corners[a], &corners[b]));
+
+        for y in min_y..max_y{
+            for x in min_x..max_x{
+                let p = Vector2::new(x as f32 + 0.5, y as f32 + 0.5);
+                let weights = edges.map(|(a, b)| edge(&corners[a], &corners[b], &p));
+                if (0..3).any(|i| weights[i] < 0.0 || (weights[i] == 0.0 && !owns_edge[i])){
+                    continue;
+                }
+
+                let barycentric = weights.map(|weight| weight / area);
+                let depth = (0..3).map(|i| barycentric[i] * window[i].z).sum::<f32>() + depth_offset;
+
+                // perspective correct interpolation of the shader outputs
+                let perspective = [0, 1, 2].map(|i| barycentric[i] / vertices[i].clip.w);
+                let sum: f32 = perspective.iter().sum();
+                let interpolate = |value: &dyn Fn(&ShadedVertex) -> Vector3<f32>| -> Vector3<f32>{
+                    (0..3).map(|i| value(vertices[i]) * (perspective[i] / sum)).sum()
+                };
+                let frag_pos = interpolate(&|vertex| vertex.frag_pos);
+                let color = interpolate(&|vertex| vertex.color);
+                let normal = if uniforms.kind == ProgramKind::Flat {flat_normal} else {interpolate(&|vertex| vertex.normal)};
+
+                let fragment = Fragment{x: x, y: y, depth: depth, is_front: is_front};
+                self.write_fragment(&fragment, shade_fragment(uniforms, &frag_pos, &normal, &color));
+            }
+        }
+    }
+
+    fn draw_line(&mut self, uniforms: &DrawUniforms, vertices: [&ShadedVertex; 2]){
+        let (Some(start), Some(end)) = (self.to_window(&vertices[0].clip), self.to_window(&vertices[1].clip)) else {return};
+        let steps = (end.x - start.x).abs().max((end.y - start.y).abs()).ceil().max(1.0) as usize;
+
+        for step in 0..steps{ // the end pixel belongs to the next line of a strip
+            let t = (step as f32 + 0.5) / steps as f32;
+            let p = start.lerp(&end, t);
+            if p.x < 0.0 || p.y < 0.0 || p.x >= self.width as f32 || p.y >= self.height as f32{
+                continue;
+            }
+
+            let (w0, w1) = ((1.0 - t) / vertices[0].clip.w, t / vertices[1].clip.w);
+            let frag_pos = (vertices[0].frag_pos * w0 + vertices[1].frag_pos * w1) / (w0 + w1);
+            let color = (vertices[0].color * w0 + vertices[1].color * w1) / (w0 + w1);
+
+            let fragment = Fragment{x: p.x as usize, y: p.y as usize, depth: p.z, is_front: true};
+            self.write_fragment(&fragment, shade_fragment(uniforms, &frag_pos, &Vector3::zeros(), &color));
+        }
+    }
+
+    fn draw_point(&mut self, uniforms: &DrawUniforms, vertex: &ShadedVertex){
+        let Some(center) = self.to_window(&vertex.clip) else {return};
+        let half = POINT_SIZE / 2.0;
+        let min_x = (center.x - half).round().max(0.0) as usize;
+        let min_y = (center.y - half).round().max(0.0) as usize;
+        let max_x = ((center.x + half).round().max(0.0) as usize).min(self.width);
+        let max_y = ((center.y + half).round().max(0.0) as usize).min(self.height);
+
+        let color = shade_fragment(uniforms, &vertex.frag_pos, &Vector3::zeros(), &vertex.color);
+        for y in min_y..max_y{
+            for x in min_x..max_x{
+                self.write_fragment(&Fragment{x: x, y: y, depth: center.z, is_front: true}, color);
+            }
+        }
+    }
+}
+
+impl Backend for SoftwareBackend{
+    fn max_element_index(&self) -> u32{
+        return self.max_element_index;
+    }
+
+    fn create_vertex_array(&self, vertices: &[f32], values_per_vertex: usize, indices: Indices) -> Result<VertexArrayId, String>{
+        let wide_indices = matches!(indices, Indices::U32(_));
+        let indices = match indices{
+            Indices::U16(indices) => indices.iter().map(|&i| i as u32).collect(),
+            Indices::U32(indices) => indices.to_vec()
+        };
+        if indices.iter().any(|&i| (i as usize + 1) * values_per_vertex > vertices.len()){
+            return Err("Index out of the vertex buffer".to_string());
+        }
+
+        let mut state = self.state.borrow_mut();
+        let id = VertexArrayId(state.next_id());
+        state.vertex_arrays.insert(id, VertexArray{vertices: vertices.to_vec(), values_per_vertex: values_per_vertex, indices: indices, wide_indices: wide_indices, instance_buffer: None});
+        Ok(id)
+    }
+
+    fn delete_vertex_array(&self, vertex_array: VertexArrayId){
+        self.state.borrow_mut().vertex_arrays.remove(&vertex_array);
+    }
+
+    fn set_instance_buffer(&self, vertex_array: VertexArrayId, buffer: Option<BufferId>){
+        if let Some(vertex_array) = self.state.borrow_mut().vertex_arrays.get_mut(&vertex_array){
+            vertex_array.instance_buffer = buffer;
+        }
+    }
+
+    fn create_instance_buffer(&self, values: &[f32]) -> Result<BufferId, String>{
+        let mut state = self.state.borrow_mut();
+        let id = BufferId(state.next_id());
+        state.buffers.insert(id, values.to_vec());
+        Ok(id)
+    }
+
+    fn create_uniform_buffer(&self, binding: u32, size: usize) -> Result<BufferId, String>{
+        let mut state = self.state.borrow_mut();
+        let id = BufferId(state.next_id());
+        state.buffers.insert(id, vec![0.0; size / 4]);
+        state.uniform_bindings.insert(binding, id);
+        Ok(id)
+    }
+
+    fn update_uniform_buffer(&self, buffer: BufferId, values: &[f32]){
+        if let Some(buffer) = self.state.borrow_mut().buffers.get_mut(&buffer){
+            let count = values.len().min(buffer.len());
+            buffer[..count].copy_from_slice(&values[..count]);
+        }
+    }
+
+    fn delete_buffer(&self, buffer: BufferId){
+        self.state.borrow_mut().buffers.remove(&buffer);
+    }
+
+    fn create_program(&self, kind: ProgramKind) -> Result<ProgramId, String>{
+        let mut state = self.state.borrow_mut();
+        let id = ProgramId(state.next_id());
+        state.programs.insert(id, Program{kind: kind, uniforms: HashMap::new()});
+        Ok(id)
+    }
+
+    fn use_program(&self, program: ProgramId){
+        self.state.borrow_mut().current_program = Some(program);
+    }
+
+    fn set_uniform(&self, program: ProgramId, name: &str, value: UniformValue){
+        let mut state = self.state.borrow_mut();
+        let Some(program) = state.programs.get_mut(&program) else {return};
+        let values = match value{
+            UniformValue::Int(value) => vec![value as f32],
+            UniformValue::Vec3(values) => values.to_vec(),
+            UniformValue::Vec4Array(values) | UniformValue::Mat3(values) | UniformValue::Mat4(values) => values.to_vec()
+        };
+        program.uniforms.insert(name.to_string(), values);
+    }
+
+    fn viewport(&self, width: i32, height: i32){
+        let target = &mut self.state.borrow_mut().target;
+        let (width, height) = (width.max(1) as usize, height.max(1) as usize);
+        if (width, height) != (target.width, target.height){
+            target.width = width;
+            target.height = height;
+            target.color = vec![[0, 0, 0, 0]; width * height];
+            target.depth = vec![1.0; width * height];
+            target.stencil = vec![0; width * height];
+        }
+    }
+
+    fn set_enabled(&self, capability: Capability, enabled: bool){
+        let target = &mut self.state.borrow_mut().target;
+        match capability{
+            Capability::DepthTest => target.depth_test = enabled,
+            Capability::CullFace => target.cull_face = enabled,
+            Capability::StencilTest => target.stencil_test = enabled,
+            Capability::Blend => target.blend = enabled,
+            Capability::PolygonOffsetFill => target.polygon_offset_fill = enabled
+        }
+    }
+
+    fn clear_color(&self, color: [f32; 4]){
+        self.state.borrow_mut().target.clear_color = color;
+    }
+
+    fn clear(&self, color: bool, depth: bool, stencil: bool){
+        let target = &mut self.state.borrow_mut().target;
+        if color{
+            let clear_color = target.clear_color.map(|value| (value.clamp(0.0, 1.0) * 255.0).round() as u8);
+            target.color.fill(clear_color);
+        }
+        if depth{
+            target.depth.fill(1.0);
+        }
+        if stencil{
+            target.stencil.fill(0);
+        }
+    }
+
+    fn depth_func(&self, func: CompareFunc){
+        self.state.borrow_mut().target.depth_func = func;
+    }
+
+    fn depth_mask(&self, write: bool){
+        self.state.borrow_mut().target.depth_mask = write;
+    }
+
+    fn color_mask(&self, write: bool){
+        self.state.borrow_mut().target.color_mask = write;
+    }
+
+    fn stencil_func(&self, func: CompareFunc, reference: i32, mask: u32){
+        self.state.borrow_mut().target.stencil_func = (func, reference, mask);
+    }
+
+    fn stencil_op(&self, face: Face, stencil_fail: StencilOp, depth_fail: StencilOp, depth_pass: StencilOp){
+        let target = &mut self.state.borrow_mut().target;
+        let ops = StencilOps{stencil_fail: stencil_fail, depth_fail: depth_fail, depth_pass: depth_pass};
+        match face{
+            Face::Front => target.stencil_ops[0] = ops,
+            Face::Back => target.stencil_ops[1] = ops,
+            Face::FrontAndBack => target.stencil_ops = [ops, ops]
+        }
+    }
+
+    fn blend_constant_alpha(&self, alpha: f32){
+        self.state.borrow_mut().target.blend_alpha = alpha;
+    }
+
+    fn polygon_offset(&self, factor: f32, units: f32){
+        self.state.borrow_mut().target.polygon_offset = (factor, units);
+    }
+
+    fn draw(&self, vertex_array: VertexArrayId, primitive: Primitive, instance_count: Option<i32>){
+        let mut state = self.state.borrow_mut();
+        let Some(program) = state.current_program.and_then(|program| state.programs.get(&program)) else {return};
+        let uniforms = state.draw_uniforms(program);
+
+        let State{target, vertex_arrays, buffers, ..} = &mut *state;
+        let Some(vertex_array) = vertex_arrays.get(&vertex_array) else {return};
+
+        let single = [1.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 1.0, 1.0, 1.0, 1.0,
+            1.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0];
+        let instance_values = match (instance_count, vertex_array.instance_buffer.and_then(|buffer| buffers.get(&buffer))){
+            (Some(count), Some(values)) => &values[..(count.max(0) as usize * VALUES_PER_INSTANCE).min(values.len())],
+            _ => &single[..]
+        };
+
+        for values in instance_values.chunks_exact(VALUES_PER_INSTANCE){
+            let instance = Instance{model: Matrix4::from_column_slice(&values[..16]), normal_matrix: Matrix3::from_column_slice(&values[19..28]),
+                color: Vector3::from_column_slice(&values[16..19])};
+
+            let shaded: Vec<ShadedVertex> = vertex_array.vertices.chunks_exact(vertex_array.values_per_vertex)
+                .map(|attributes| shade_vertex(&uniforms, attributes, &instance))
+                .collect();
+            let vertex = |i: &u32| &shaded[*i as usize];
+
+            match primitive{
+                Primitive::Triangles => for triangle in vertex_array.indices.chunks_exact(3){
+                    target.draw_triangle(&uniforms, [vertex(&triangle[0]), vertex(&triangle[1]), vertex(&triangle[2])]);
+                },
+                Primitive::Lines => for line in vertex_array.indices.chunks_exact(2){
+                    target.draw_line(&uniforms, [vertex(&line[0]), vertex(&line[1])]);
+                },
+                Primitive::Points => for point in &vertex_array.indices{
+                    target.draw_point(&uniforms, vertex(point));
+                }
+            }
+        }
+    }
+
+    #[cfg(test)]
+    fn read_pixels(&self) -> Result<(usize, usize, Vec<u8>), String>{
+        let target = &self.state.borrow().target;
+        return Ok((target.width, target.height, target.color.iter().flatten().cloned().collect()));
+    }
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+    use crate::viewer::uniforms::std140_values;
+
+    const SIZE: usize = 16;
+    const WHITE: [u8; 4] = [255, 255, 255, 255];
+    const BLACK: [u8; 4] = [0, 0, 0, 255];
+
+    /// backend with a line program in use drawing in white, positions are clip coordinates
+    fn line_backend() -> (SoftwareBackend, ProgramId){
+        let backend = SoftwareBackend::new(SIZE, SIZE);
+        let frame = FrameUniforms{projection: Matrix4::identity(), view: Matrix4::identity(), anim_time: 1.0};
+        let (_, frame_size) = std140_layout(FrameUniforms::MEMBERS);
+        let frame_buffer = backend.create_uniform_buffer(FrameUniforms::BINDING, frame_size).unwrap();
+        backend.update_uniform_buffer(frame_buffer, &std140_values(&frame));
+
+        let program = backend.create_program(ProgramKind::Lines).unwrap();
+        backend.use_program(program);
+        backend.set_uniform(program, "model", UniformValue::Mat4(Matrix4::<f32>::identity().as_slice()));
+        backend.set_uniform(program, "objectColor", UniformValue::Vec3([1.0, 1.0, 1.0]));
+        backend.clear_color([0.0, 0.0, 0.0, 1.0]);
+        backend.clear(true, true, true);
+        return (backend, program);
+    }
+
+    fn pixel(backend: &SoftwareBackend, x: usize, y: usize) -> [u8; 4]{
+        return backend.state.borrow().target.color[y * SIZE + x];
+    }
+
+    fn triangles(backend: &SoftwareBackend, vertices: &[f32], indices: &[u16]){
+        let vertex_array = backend.create_vertex_array(vertices, 3, Indices::U16(indices)).unwrap();
+        backend.draw(vertex_array, Primitive::Triangles, None);
+    }
+
+    #[test]
+    fn shared_edges_cover_pixels_once(){
+        let (backend, _) = line_backend();
+        backend.set_enabled(Capability::StencilTest, true);
+        backend.stencil_func(CompareFunc::Always, 0, 0xff);
+        backend.stencil_op(Face::FrontAndBack, StencilOp::Keep, StencilOp::Keep, StencilOp::IncrWrap);
+        backend.set_enabled(Capability::DepthTest, false);
+
+        // a fan around the center, the diagonals run through pixel centers
+        let vertices = [-1.0, -1.0, 0.0, 1.0, -1.0, 0.0, 1.0, 1.0, 0.0, -1.0, 1.0, 0.0, 0.0, 0.0, 0.0];
+        triangles(&backend, &vertices, &[0, 1, 4, 1, 2, 4, 2, 3, 4, 3, 0, 4]);
+        assert!(backend.state.borrow().target.stencil.iter().all(|&count| count == 1));
+    }
+
+    #[test]
+    fn back_faces_are_culled(){
+        let (backend, _) = line_backend();
+        let clockwise = [-1.0, -1.0, 0.0, -1.0, 1.0, 0.0, 1.0, -1.0, 0.0];
+        triangles(&backend, &clockwise, &[0, 1, 2]);
+        assert_eq!(pixel(&backend, 2, 2), BLACK);
+
+        backend.set_enabled(Capability::CullFace, false);
+        triangles(&backend, &clockwise, &[0, 1, 2]);
+        assert_eq!(pixel(&backend, 2, 2), WHITE);
+    }
+
+    #[test]
+    fn depth_test_keeps_the_nearest(){
+        let (backend, program) = line_backend();
+        let quad = |z: f32| [-1.0, -1.0, z, 1.0, -1.0, z, 1.0, 1.0, z, -1.0, 1.0, z];
+
+        triangles(&backend, &quad(-0.5), &[0, 1, 2, 0, 2, 3]);
+        backend.set_uniform(program, "objectColor", UniformValue::Vec3([1.0, 0.0, 0.0]));
+        triangles(&backend, &quad(0.5), &[0, 1, 2, 0, 2, 3]);
+        assert_eq!(pixel(&backend, 8, 8), WHITE);
+
+        backend.depth_func(CompareFunc::Always);
+        triangles(&backend, &quad(0.5), &[0, 1, 2, 0, 2, 3]);
+        assert_eq!(pixel(&backend, 8, 8), [255, 0, 0, 255]);
+    }
+
+    #[test]
+    fn clip_planes_discard(){
+        let (backend, program) = line_backend();
+        backend.set_uniform(program, "clipPlanes", UniformValue::Vec4Array(&[1.0, 0.0, 0.0, 0.0]));
+        backend.set_uniform(program, "clipPlaneCount", UniformValue::Int(1));
+
+        triangles(&backend, &[-1.0, -1.0, 0.0, 1.0, -1.0, 0.0, 1.0, 1.0, 0.0, -1.0, 1.0, 0.0], &[0, 1, 2, 0, 2, 3]);
+        assert_eq!(pixel(&backend, 4, 8), WHITE);
+        assert_eq!(pixel(&backend, 12, 8), BLACK);
+    }
+
+    #[test]
+    fn instances_are_placed_and_tinted(){
+        let (backend, _) = line_backend();
+        let mut values = vec![];
+        for (x, color) in [(-0.5, [1.0, 0.0, 0.0]), (0.5, [0.0, 0.0, 1.0])]{
+            values.extend_from_slice(Matrix4::new_translation(&Vector3::new(x, 0.0, 0.0)).as_slice());
+            values.extend_from_slice(&color);
+            values.extend_from_slice(Matrix3::<f32>::identity().as_slice());
+        }
+        let instances = backend.create_instance_buffer(&values).unwrap();
+
+        let small_quad = [-0.25, -0.25, 0.0, 0.25, -0.25, 0.0, 0.25, 0.25, 0.0, -0.25, 0.25, 0.0];
+        let vertex_array = backend.create_vertex_array(&small_quad, 3, Indices::U16(&[0, 1, 2, 0, 2, 3])).unwrap();
+        backend.set_instance_buffer(vertex_array, Some(instances));
+        backend.draw(vertex_array, Primitive::Triangles, Some(2));
+
+        assert_eq!(pixel(&backend, 4, 8), [255, 0, 0, 255]);
+        assert_eq!(pixel(&backend, 12, 8), [0, 0, 255, 255]);
+        assert_eq!(pixel(&backend, 8, 8), BLACK);
+    }
+
+    #[test]
+    fn lines_and_points(){
+        let (backend, _) = line_backend();
+        let vertex_array = backend.create_vertex_array(&[-1.0, 0.0625, 0.0, 1.0, 0.0625, 0.0], 3, Indices::U16(&[0, 1])).unwrap();
+        backend.draw(vertex_array, Primitive::Lines, None);
+        assert!((0..SIZE).all(|x| pixel(&backend, x, 8) == WHITE));
+        assert_eq!(pixel(&backend, 8, 9), BLACK);
+
+        backend.clear(true, true, true);
+        backend.draw(vertex_array, Primitive::Points, None);
+        assert_eq!(pixel(&backend, 0, 8), WHITE);
+        assert_eq!(pixel(&backend, 2, 10), WHITE);
+        assert_eq!(pixel(&backend, 8, 8), BLACK);
+    }
+}
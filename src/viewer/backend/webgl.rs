@@ -0,0 +1,420 @@
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+
+use web_sys::{console, WebGl2RenderingContext as GL, WebGl2RenderingContext, WebGlBuffer, WebGlProgram, WebGlShader, WebGlUniformLocation, WebGlVertexArrayObject};
+
+use crate::viewer::shaders::{FSHADER_FLAT, FSHADER_LINE, FSHADER_SMOOTH, VSHADER_FLAT, VSHADER_LINE, VSHADER_SMOOTH};
+use crate::viewer::uniforms::{FrameUniforms, LightUniforms, UniformBlock};
+use super::{Backend, BufferId, Capability, CompareFunc, Face, Indices, Primitive, ProgramId, ProgramKind, StencilOp, UniformValue, VertexArrayId, VALUES_PER_INSTANCE};
+
+const POSITION_LOCATION: u32 = 0;
+const NORMAL_LOCATION: u32 = 1;
+const COLOR_LOCATION: u32 = 2;
+const INSTANCE_MODEL_LOCATION: u32 = 3; // the matrix takes four locations, a column each
+const INSTANCE_COLOR_LOCATION: u32 = 7;
+const INSTANCE_NORMAL_MATRIX_LOCATION: u32 = 8; // three locations, a column each
+
+const LINE_UNIFORMS: [&str; 4] = ["model", "objectColor", "clipPlanes", "clipPlaneCount"];
+const SHADED_UNIFORMS: [&str; 1] = ["normalMatrix"];
+
+/// Linked program with the locations of its uniforms, looked up once after linking. Uniforms it doesn't have
+/// are reported then and setting them does nothing. Attributes sit at the locations the shaders fix for every
+/// program, which the vertex arrays are set up with, and uniform blocks at their blocks' binding points.
+struct ShaderProgram{
+    program: WebGlProgram,
+    uniforms: HashMap<&'static str, WebGlUniformLocation>
+}
+
+impl ShaderProgram{
+    pub fn new(gl: &WebGl2RenderingContext, name: &str, vertex_source: &str, fragment_source: &str,
+        uniform_names: &[&'static str], attributes: &[(&str, u32)], blocks: &[(&str, u32)]) -> Result<ShaderProgram, String>{
+        let vertex_shader = compile_shader(gl, GL::VERTEX_SHADER, vertex_source)?;
+        let fragment_shader = compile_shader(gl, GL::FRAGMENT_SHADER, fragment_source)?;
+        let program = link_program(gl, &vertex_shader, &fragment_shader)?;
+
+        let mut uniforms = HashMap::new();
+        for uniform_name in uniform_names{
+            match gl.get_uniform_location(&program, uniform_name){
+                Some(location) => {uniforms.insert(*uniform_name, location);},
+                None => console::warn_1(&format!("Shader uniform {} not found in the {} program", uniform_name, name).into())
+            }
+        }
+
+        for (attribute_name, location) in attributes{
+            let found = gl.get_attrib_location(&program, attribute_name);
+            if found < 0{
+                console::warn_1(&format!("Shader attribute {} not found in the {} program", attribute_name, name).into());
+            }else if found as u32 != *location{
+                return Err(format!("Shader attribute {} of the {} program is at location {} instead of {}", attribute_name, name, found, location));
+            }
+        }
+
+        for (block_name, binding) in blocks{
+            let index = gl.get_uniform_block_index(&program, block_name);
+            if index == GL::INVALID_INDEX{
+                console::warn_1(&format!("Shader uniform block {} not found in the {} program", block_name, name).into());
+            }else{
+                gl.uniform_block_binding(&program, index, *binding);
+            }
+        }
+
+        Ok(ShaderProgram{program: program, uniforms: uniforms})
+    }
+}
+
+struct VertexArray{
+    vao: WebGlVertexArrayObject,
+    vbo: WebGlBuffer,
+    ebo: WebGlBuffer,
+    index_count: i32,
+    index_type: u32 // GL::UNSIGNED_SHORT or GL::UNSIGNED_INT
+}
+
+/// the browser's WebGL2 context
+pub struct WebGlBackend{
+    gl: WebGl2RenderingContext,
+    vertex_arrays: RefCell<HashMap<VertexArrayId, VertexArray>>,
+    buffers: RefCell<HashMap<BufferId, WebGlBuffer>>,
+    programs: RefCell<HashMap<ProgramId, ShaderProgram>>,
+    next_id: Cell<u32>
+}
+
+impl WebGlBackend{
+    pub fn new(gl: WebGl2RenderingContext) -> WebGlBackend{
+        gl.cull_face(GL::BACK);
+
+        // values of the instance attributes while not read from a buffer, placing a single white copy
+        for column in 0..4{
+            let mut values = [0.0; 4];
+            values[column] = 1.0;
+            gl.vertex_attrib4f(INSTANCE_MODEL_LOCATION + column as u32, values[0], values[1], values[2], values[3]);
+        }
+        gl.vertex_attrib3f(INSTANCE_COLOR_LOCATION, 1.0, 1.0, 1.0);
+        for column in 0..3{
+            let mut values = [0.0; 3];
+            values[column] = 1.0;
+            gl.vertex_attrib3f(INSTANCE_NORMAL_MATRIX_LOCATION + column as u32, values[0], values[1], values[2]);
+        }
+
+        return WebGlBackend{gl: gl, vertex_arrays: RefCell::new(HashMap::new()), buffers: RefCell::new(HashMap::new()),
+            programs: RefCell::new(HashMap::new()), next_id: Cell::new(0)};
+    }
+
+    fn next_id(&self) -> u32{
+        let id = self.next_id.get();
+        self.next_id.set(id + 1);
+        return id;
+    }
+
+    fn create_buffer(&self, target: u32, values: &[f32], usage: u32) -> Result<BufferId, String>{
+        let buffer = self.gl.create_buffer().ok_or("Failed to create buffer")?;
+        self.gl.bind_buffer(target, Some(&buffer));
+        unsafe {
+            let value_array = js_sys::Float32Array::view(values);
+            self.gl.buffer_data_with_array_buffer_view(target, &value_array, usage);
+        }
+
+        let id = BufferId(self.next_id());
+        self.buffers.borrow_mut().insert(id, buffer);
+        Ok(id)
+    }
+}
+
+fn compare_func(func: CompareFunc) -> u32{
+    return match func{
+        CompareFunc::Always => GL::ALWAYS,
+        CompareFunc::Less => GL::LESS,
+        CompareFunc::LessEqual => GL::LEQUAL,
+        CompareFunc::Equal => GL::EQUAL,
+        CompareFunc::NotEqual => GL::NOTEQUAL
+    };
+}
+
+fn stencil_op(op: StencilOp) -> u32{
+    return match op{
+        StencilOp::Keep => GL::KEEP,
+        StencilOp::Invert => GL::INVERT,
+        StencilOp::IncrWrap => GL::INCR_WRAP,
+        StencilOp::DecrWrap => GL::DECR_WRAP
+    };
+}
+
+impl Backend for WebGlBackend{
+    fn max_element_index(&self) -> u32{
+        return self.gl.get_parameter(GL::MAX_ELEMENT_INDEX).ok().and_then(|value| value.as_f64()).unwrap_or(0.0) as u32;
+    }
+
+    fn create_vertex_array(&self, vertices: &[f32], values_per_vertex: usize, indices: Indices) -> Result<VertexArrayId, String>{
+        let gl = &self.gl;
+        let vao = gl.create_vertex_array().ok_or("Failed to create vertex array")?;
+        gl.bind_vertex_array(Some(&vao));
+
+        let vbo = gl.create_buffer().ok_or("Failed to create buffer")?;
+        gl.bind_buffer(GL::ARRAY_BUFFER, Some(&vbo));
+        unsafe {
+            let vertex_array = js_sys::Float32Array::view(vertices);
+            gl.buffer_data_with_array_buffer_view(GL::ARRAY_BUFFER, &vertex_array, GL::STATIC_DRAW);
+        }
+
+        let stride = (values_per_vertex * 4) as i32;
+        gl.vertex_attrib_pointer_with_i32(POSITION_LOCATION, 3, GL::FLOAT, false, stride, 0);
+        gl.enable_vertex_attrib_array(POSITION_LOCATION);
+        if values_per_vertex == 9{
+            gl.vertex_attrib_pointer_with_i32(NORMAL_LOCATION, 3, GL::FLOAT, false, stride, 3 * 4);
+            gl.enable_vertex_attrib_array(NORMAL_LOCATION);
+            gl.vertex_attrib_pointer_with_i32(COLOR_LOCATION, 3, GL::FLOAT, false, stride, 6 * 4);
+            gl.enable_vertex_attrib_array(COLOR_LOCATION);
+        }
+
+        let ebo = gl.create_buffer().ok_or("Failed to create element buffer")?;
+        gl.bind_buffer(GL::ELEMENT_ARRAY_BUFFER, Some(&ebo));
+        let (index_count, index_type) = match indices{
+            Indices::U16(indices) => {
+                unsafe {
+                    let index_array = js_sys::Uint16Array::view(indices);
+                    gl.buffer_data_with_array_buffer_view(GL::ELEMENT_ARRAY_BUFFER, &index_array, GL::STATIC_DRAW);
+                }
+                (indices.len(), GL::UNSIGNED_SHORT)
+            },
+            Indices::U32(indices) => {
+                unsafe {
+                    let index_array = js_sys::Uint32Array::view(indices);
+                    gl.buffer_data_with_array_buffer_view(GL::ELEMENT_ARRAY_BUFFER, &index_array, GL::STATIC_DRAW);
+                }
+                (indices.len(), GL::UNSIGNED_INT)
+            }
+        };
+        gl.bind_vertex_array(None);
+
+        let id = VertexArrayId(self.next_id());
+        self.vertex_arrays.borrow_mut().insert(id, VertexArray{vao: vao, vbo: vbo, ebo: ebo, index_count: index_count as i32, index_type: index_type});
+        Ok(id)
+    }
+
+    fn delete_vertex_array(&self, vertex_array: VertexArrayId){
+        if let Some(vertex_array) = self.vertex_arrays.borrow_mut().remove(&vertex_array){
+            self.gl.delete_vertex_array(Some(&vertex_array.vao));
+            self.gl.delete_buffer(Some(&vertex_array.vbo));
+            self.gl.delete_buffer(Some(&vertex_array.ebo));
+        }
+    }
+
+    fn set_instance_buffer(&self, vertex_array: VertexArrayId, buffer: Option<BufferId>){
+        let gl = &self.gl;
+        let vertex_arrays = self.vertex_arrays.borrow();
+        let Some(vertex_array) = vertex_arrays.get(&vertex_array) else {return};
+        let buffers = self.buffers.borrow();
+
+        gl.bind_vertex_array(Some(&vertex_array.vao));
+        match buffer.and_then(|buffer| buffers.get(&buffer)){
+            Some(buffer) => {
+                gl.bind_buffer(GL::ARRAY_BUFFER, Some(buffer));
+                let stride = (VALUES_PER_INSTANCE * 4) as i32;
+                for column in 0..4{
+                    let location = INSTANCE_MODEL_LOCATION + column;
+                    gl.vertex_attrib_pointer_with_i32(location, 4, GL::FLOAT, false, stride, (column * 4 * 4) as i32);
+                    gl.enable_vertex_attrib_array(location);
+                    gl.vertex_attrib_divisor(location, 1);
+                }
+                gl.vertex_attrib_pointer_with_i32(INSTANCE_COLOR_LOCATION, 3, GL::FLOAT, false, stride, 16 * 4);
+                gl.enable_vertex_attrib_array(INSTANCE_COLOR_LOCATION);
+                gl.vertex_attrib_divisor(INSTANCE_COLOR_LOCATION, 1);
+                for column in 0..3{
+                    let location = INSTANCE_NORMAL_MATRIX_LOCATION + column;
+                    gl.vertex_attrib_pointer_with_i32(location, 3, GL::FLOAT, false, stride, ((19 + column * 3) * 4) as i32);
+                    gl.enable_vertex_attrib_array(location);
+                    gl.vertex_attrib_divisor(location, 1);
+                }
+            },
+            None => {
+                for location in INSTANCE_MODEL_LOCATION..INSTANCE_NORMAL_MATRIX_LOCATION + 3{
+                    gl.vertex_attrib_divisor(location, 0);
+                    gl.disable_vertex_attrib_array(location);
+                }
+            }
+        }
+        gl.bind_vertex_array(None);
+    }
+
+    fn create_instance_buffer(&self, values: &[f32]) -> Result<BufferId, String>{
+        return self.create_buffer(GL::ARRAY_BUFFER, values, GL::STATIC_DRAW);
+    }
+
+    fn create_uniform_buffer(&self, binding: u32, size: usize) -> Result<BufferId, String>{
+        let id = self.create_buffer(GL::UNIFORM_BUFFER, &vec![0.0; size / 4], GL::DYNAMIC_DRAW)?;
+        self.gl.bind_buffer_base(GL::UNIFORM_BUFFER, binding, self.buffers.borrow().get(&id));
+        Ok(id)
+    }
+
+    fn update_uniform_buffer(&self, buffer: BufferId, values: &[f32]){
+        self.gl.bind_buffer(GL::UNIFORM_BUFFER, self.buffers.borrow().get(&buffer));
+        unsafe {
+            let value_array = js_sys::Float32Array::view(values);
+            self.gl.buffer_sub_data_with_i32_and_array_buffer_view(GL::UNIFORM_BUFFER, 0, &value_array);
+        }
+    }
+
+    fn delete_buffer(&self, buffer: BufferId){
+        if let Some(buffer) = self.buffers.borrow_mut().remove(&buffer){
+            self.gl.delete_buffer(Some(&buffer));
+        }
+    }
+
+    fn create_program(&self, kind: ProgramKind) -> Result<ProgramId, String>{
+        let shaded_uniforms = [LINE_UNIFORMS.as_slice(), SHADED_UNIFORMS.as_slice()].concat();
+        let shaded_attributes = [("aPosition", POSITION_LOCATION), ("aNormal", NORMAL_LOCATION), ("aColor", COLOR_LOCATION),
+            ("aInstanceModel", INSTANCE_MODEL_LOCATION), ("aInstanceColor", INSTANCE_COLOR_LOCATION),
+            ("aInstanceNormalMatrix", INSTANCE_NORMAL_MATRIX_LOCATION)];
+        let line_attributes = [("aPosition", POSITION_LOCATION), ("aInstanceModel", INSTANCE_MODEL_LOCATION),
+            ("aInstanceColor", INSTANCE_COLOR_LOCATION)];
+        let shaded_blocks = [(FrameUniforms::NAME, FrameUniforms::BINDING), (LightUniforms::NAME, LightUniforms::BINDING)];
+        let line_blocks = [(FrameUniforms::NAME, FrameUniforms::BINDING)];
+
+        let program = match kind{
+            ProgramKind::Flat => ShaderProgram::new(&self.gl, "flat", VSHADER_FLAT, FSHADER_FLAT, &shaded_uniforms, &shaded_attributes, &shaded_blocks)?,
+            ProgramKind::Smooth => ShaderProgram::new(&self.gl, "smooth", VSHADER_SMOOTH, FSHADER_SMOOTH, &shaded_uniforms, &shaded_attributes, &shaded_blocks)?,
+            ProgramKind::Lines => ShaderProgram::new(&self.gl, "line", VSHADER_LINE, FSHADER_LINE, &LINE_UNIFORMS, &line_attributes, &line_blocks)?
+        };
+
+        let id = ProgramId(self.next_id());
+        self.programs.borrow_mut().insert(id, program);
+        Ok(id)
+    }
+
+    fn use_program(&self, program: ProgramId){
+        self.gl.use_program(self.programs.borrow().get(&program).map(|program| &program.program));
+    }
+
+    fn set_uniform(&self, program: ProgramId, name: &str, value: UniformValue){
+        let programs = self.programs.borrow();
+        let location = programs.get(&program).and_then(|program| program.uniforms.get(name));
+        match value{
+            UniformValue::Int(value) => self.gl.uniform1i(location, value),
+            UniformValue::Vec3([x, y, z]) => self.gl.uniform3f(location, x, y, z),
+            UniformValue::Vec4Array(values) => self.gl.uniform4fv_with_f32_array(location, values),
+            UniformValue::Mat3(values) => self.gl.uniform_matrix3fv_with_f32_array(location, false, values),
+            UniformValue::Mat4(values) => self.gl.uniform_matrix4fv_with_f32_array(location, false, values)
+        }
+    }
+
+    fn viewport(&self, width: i32, height: i32){
+        self.gl.viewport(0, 0, width, height);
+    }
+
+    fn set_enabled(&self, capability: Capability, enabled: bool){
+        let capability = match capability{
+            Capability::DepthTest => GL::DEPTH_TEST,
+            Capability::CullFace => GL::CULL_FACE,
+            Capability::StencilTest => GL::STENCIL_TEST,
+            Capability::Blend => GL::BLEND,
+            Capability::PolygonOffsetFill => GL::POLYGON_OFFSET_FILL
+        };
+        if enabled{
+            self.gl.enable(capability);
+        }else{
+            self.gl.disable(capability);
+        }
+    }
+
+    fn clear_color(&self, color: [f32; 4]){
+        let [r, g, b, a] = color;
+        self.gl.clear_color(r, g, b, a);
+    }
+
+    fn clear(&self, color: bool, depth: bool, stencil: bool){
+        let mut mask = 0;
+        if color{
+            mask |= GL::COLOR_BUFFER_BIT;
+        }
+        if depth{
+            mask |= GL::DEPTH_BUFFER_BIT;
+        }
+        if stencil{
+            mask |= GL::STENCIL_BUFFER_BIT;
+        }
+        self.gl.clear(mask);
+    }
+
+    fn depth_func(&self, func: CompareFunc){
+        self.gl.depth_func(compare_func(func));
+    }
+
+    fn depth_mask(&self, write: bool){
+        self.gl.depth_mask(write);
+    }
+
+    fn color_mask(&self, write: bool){
+        self.gl.color_mask(write, write, write, write);
+    }
+
+    fn stencil_func(&self, func: CompareFunc, reference: i32, mask: u32){
+        self.gl.stencil_func(compare_func(func), reference, mask);
+    }
+
+    fn stencil_op(&self, face: Face, stencil_fail: StencilOp, depth_fail: StencilOp, depth_pass: StencilOp){
+        let face = match face{
+            Face::Front => GL::FRONT,
+            Face::Back => GL::BACK,
+            Face::FrontAndBack => GL::FRONT_AND_BACK
+        };
+        self.gl.stencil_op_separate(face, stencil_op(stencil_fail), stencil_op(depth_fail), stencil_op(depth_pass));
+    }
+
+    fn blend_constant_alpha(&self, alpha: f32){
+        self.gl.blend_color(0.0, 0.0, 0.0, alpha);
+        self.gl.blend_func(GL::CONSTANT_ALPHA, GL::ONE_MINUS_CONSTANT_ALPHA);
+    }
+
+    fn polygon_offset(&self, factor: f32, units: f32){
+        self.gl.polygon_offset(factor, units);
+    }
+
+    fn draw(&self, vertex_array: VertexArrayId, primitive: Primitive, instance_count: Option<i32>){
+        let vertex_arrays = self.vertex_arrays.borrow();
+        let Some(vertex_array) = vertex_arrays.get(&vertex_array) else {return};
+        let mode = match primitive{
+            Primitive::Points => GL::POINTS,
+            Primitive::Lines => GL::LINES,
+            Primitive::Triangles => GL::TRIANGLES
+        };
+
+        self.gl.bind_vertex_array(Some(&vertex_array.vao));
+        match instance_count{
+            Some(instance_count) => self.gl.draw_elements_instanced_with_i32(mode, vertex_array.index_count, vertex_array.index_type, 0, instance_count),
+            None => self.gl.draw_elements_with_i32(mode, vertex_array.index_count, vertex_array.index_type, 0)
+        }
+        self.gl.bind_vertex_array(None);
+    }
+
+    #[cfg(test)]
+    fn read_pixels(&self) -> Result<(usize, usize, Vec<u8>), String>{
+        let (width, height) = (self.gl.drawing_buffer_width(), self.gl.drawing_buffer_height());
+        let mut pixels = vec![0; (width * height * 4) as usize];
+        self.gl.read_pixels_with_opt_u8_array(0, 0, width, height, GL::RGBA, GL::UNSIGNED_BYTE, Some(&mut pixels))
+            .map_err(|_| "Failed to read the pixels".to_string())?;
+        Ok((width as usize, height as usize, pixels))
+    }
+}
+
+fn compile_shader(gl: &GL, shader_type: u32, source: &str) -> Result<WebGlShader, String> {
+    let shader = gl.create_shader(shader_type).ok_or("Failed to create shader")?;
+    gl.shader_source(&shader, source);
+    gl.compile_shader(&shader);
+    if gl.get_shader_parameter(&shader, GL::COMPILE_STATUS).as_bool().unwrap_or(false) {
+        Ok(shader)
+    } else {
+        Err(gl.get_shader_info_log(&shader).unwrap_or("Unknown error".into()))
+    }
+}
+
+fn link_program(gl: &GL, vert_shader: &WebGlShader, frag_shader: &WebGlShader) -> Result<WebGlProgram, String> {
+    let program = gl.create_program().ok_or("Failed to create program")?;
+    gl.attach_shader(&program, vert_shader);
+    gl.attach_shader(&program, frag_shader);
+    gl.link_program(&program);
+    if gl.get_program_parameter(&program, GL::LINK_STATUS).as_bool().unwrap_or(false) {
+        Ok(program)
+    } else {
+        Err(gl.get_program_info_log(&program).unwrap_or("Unknown error".into()))
+    }
+}
@@ -0,0 +1,137 @@
+use nalgebra::Vector3;
+
+/// colour of values outside any range, NaN and the like
+const INVALID_COLOR: [f32; 3] = [0.5, 0.5, 0.5];
+
+/// viridis sampled at 0, 1/8 .. 1
+const VIRIDIS: [[f32; 3]; 9] = [
+    [0.267, 0.005, 0.329], [0.283, 0.141, 0.458], [0.254, 0.265, 0.530],
+    [0.207, 0.372, 0.553], [0.164, 0.471, 0.558], [0.128, 0.567, 0.551],
+    [0.135, 0.659, 0.518], [0.267, 0.749, 0.441], [0.993, 0.906, 0.144]
+];
+
+/// Moreland's cool to warm map, white in the middle
+const DIVERGING: [[f32; 3]; 5] = [
+    [0.230, 0.299, 0.754], [0.552, 0.690, 0.996], [0.866, 0.866, 0.866],
+    [0.956, 0.604, 0.486], [0.706, 0.016, 0.150]
+];
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ColorMap{
+    Viridis,
+    Jet,
+    Diverging // for values around zero, curvature or signed deviation
+}
+
+fn interpolate(samples: &[[f32; 3]], t: f32) -> Vector3<f32>{
+    let scaled = t * (samples.len() - 1) as f32;
+    let i = (scaled.floor() as usize).min(samples.len() - 2);
+    let (a, b) = (Vector3::from(samples[i]), Vector3::from(samples[i + 1]));
+    return a.lerp(&b, scaled - i as f32);
+}
+
+impl ColorMap{
+    pub fn from_name(name: &str) -> Result<ColorMap, String>{
+        return match name{
+            "viridis" => Ok(ColorMap::Viridis),
+            "jet" => Ok(ColorMap::Jet),
+            "diverging" => Ok(ColorMap::Diverging),
+            _ => Err(format!("Unrecognized color map: {}", name))
+        };
+    }
+
+    /// colour at `t`, clamped to [0, 1]
+    pub fn color(&self, t: f32) -> Vector3<f32>{
+        if t.is_nan(){
+            return Vector3::from(INVALID_COLOR);
+        }
+        let t = t.clamp(0.0, 1.0);
+
+        return match self{
+            ColorMap::Viridis => interpolate(&VIRIDIS, t),
+            ColorMap::Jet => Vector3::new(
+                (1.5 - (4.0 * t - 3.0).abs()).clamp(0.0, 1.0),
+                (1.5 - (4.0 * t - 2.0).abs()).clamp(0.0, 1.0),
+                (1.5 - (4.0 * t - 1.0).abs()).clamp(0.0, 1.0)),
+            ColorMap::Diverging => interpolate(&DIVERGING, t)
+        };
+    }
+
+    /// colours of `values` with `range` spread over the map
+    pub fn map_values(&self, values: &[f32], range: (f32, f32)) -> Vec<Vector3<f32>>{
+        let span = range.1 - range.0;
+        return values.iter().map(|value| {
+            let t = if span > 0.0 || value.is_nan() {(value - range.0) / span} else {0.5};
+            self.color(t)
+        }).collect();
+    }
+}
+
+/// Range of the finite values leaving out `outlier_fraction` of them at either end,
+/// curvature of noisy scans would otherwise squeeze everything into the middle colour.
+pub fn fit_range(values: &[f32], outlier_fraction: f32) -> (f32, f32){
+    let mut sorted: Vec<f32> = values.iter().cloned().filter(|value| value.is_finite()).collect();
+    if sorted.is_empty(){
+        return (0.0, 1.0);
+    }
+    sorted.sort_by(f32::total_cmp);
+
+    let skipped = ((sorted.len() as f32 * outlier_fraction) as usize).min((sorted.len() - 1) / 2);
+    return (sorted[skipped], sorted[sorted.len() - 1 - skipped]);
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+
+    fn assert_color(actual: Vector3<f32>, expected: [f32; 3]){
+        assert!((actual - Vector3::from(expected)).abs().max() < 1e-6, "{:?} instead of {:?}", actual, expected);
+    }
+
+    #[test]
+    fn maps_end_at_their_extreme_colors(){
+        assert_color(ColorMap::Viridis.color(0.0), VIRIDIS[0]);
+        assert_color(ColorMap::Viridis.color(1.0), VIRIDIS[8]);
+        assert_color(ColorMap::Diverging.color(0.0), DIVERGING[0]);
+        assert_color(ColorMap::Diverging.color(0.5), DIVERGING[2]);
+        assert_color(ColorMap::Diverging.color(1.0), DIVERGING[4]);
+        assert_color(ColorMap::Jet.color(0.0), [0.0, 0.0, 0.5]);
+        assert_color(ColorMap::Jet.color(0.5), [0.5, 1.0, 0.5]);
+        assert_color(ColorMap::Jet.color(1.0), [0.5, 0.0, 0.0]);
+
+        // clamped past the ends
+        for map in [ColorMap::Viridis, ColorMap::Jet, ColorMap::Diverging]{
+            assert_eq!(map.color(-3.0), map.color(0.0));
+            assert_eq!(map.color(f32::INFINITY), map.color(1.0));
+            assert_eq!(map.color(f32::NAN), Vector3::from(INVALID_COLOR));
+        }
+        assert!(ColorMap::from_name("rainbow").is_err());
+    }
+
+    #[test]
+    fn values_spread_over_the_range(){
+        let colors = ColorMap::Viridis.map_values(&[2.0, 4.0, 6.0, 10.0, f32::NAN], (2.0, 6.0));
+        assert_eq!(colors[0], ColorMap::Viridis.color(0.0));
+        assert_eq!(colors[1], ColorMap::Viridis.color(0.5));
+        assert_eq!(colors[2], ColorMap::Viridis.color(1.0));
+        assert_eq!(colors[3], ColorMap::Viridis.color(1.0));
+        assert_eq!(colors[4], Vector3::from(INVALID_COLOR));
+
+        // an empty range puts everything in the middle, NaN still stands out
+        let colors = ColorMap::Diverging.map_values(&[1.0, 5.0, f32::NAN], (3.0, 3.0));
+        assert_eq!(colors[0], ColorMap::Diverging.color(0.5));
+        assert_eq!(colors[1], ColorMap::Diverging.color(0.5));
+        assert_eq!(colors[2], Vector3::from(INVALID_COLOR));
+    }
+
+    #[test]
+    fn fitted_range_leaves_out_outliers_and_non_finite_values(){
+        let mut values: Vec<f32> = (1..=98).map(|i| i as f32).collect();
+        values.extend([-1000.0, 1000.0, f32::NAN, f32::INFINITY, f32::NEG_INFINITY]);
+        assert_eq!(fit_range(&values, 0.0), (-1000.0, 1000.0));
+        assert_eq!(fit_range(&values, 0.01), (1.0, 98.0));
+
+        assert_eq!(fit_range(&[f32::NAN], 0.02), (0.0, 1.0));
+        assert_eq!(fit_range(&[1.0, 2.0, 3.0], 0.9), (2.0, 2.0));
+    }
+}
@@ -0,0 +1,340 @@
+use nalgebra::{Matrix3, SymmetricEigen, Vector3};
+
+use crate::mesh::SnapMode;
+
+/// segments of drawn circles and angle arcs
+const ARC_SEGMENTS: usize = 64;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum DimensionKind{
+    Distance, // between two points
+    Angle, // at the second of three points, in degrees
+    Radius // of the circle fitted through three or more points
+}
+
+impl DimensionKind{
+    /// points completing the dimension, None when more can always be added
+    fn point_count(&self) -> Option<usize>{
+        return match self{
+            DimensionKind::Distance => Some(2),
+            DimensionKind::Angle => Some(3),
+            DimensionKind::Radius => None
+        };
+    }
+
+    /// Err when `count` points are too few to finish the dimension
+    fn check_point_count(&self, count: usize) -> Result<(), String>{
+        let required = self.point_count().unwrap_or(3);
+        if count < required{
+            return Err(format!("A {} needs at least {} points, got {}", self.name(), required, count));
+        }
+        Ok(())
+    }
+
+    pub fn name(&self) -> &'static str{
+        return match self{
+            DimensionKind::Distance => "distance",
+            DimensionKind::Angle => "angle",
+            DimensionKind::Radius => "radius"
+        };
+    }
+}
+
+/// circle in 3D, from `fit_circle`
+#[derive(Clone, Copy, Debug)]
+pub struct Circle{
+    pub center: Vector3<f32>,
+    pub normal: Vector3<f32>,
+    pub radius: f32
+}
+
+/// Least squares circle through points: the plane comes from the smallest principal axis,
+/// the circle from the algebraic (Kåsa) fit within it. Exact for three points.
+pub fn fit_circle(points: &[Vector3<f32>]) -> Option<Circle>{
+    if points.len() < 3{
+        return None;
+    }
+
+    let centroid = points.iter().sum::<Vector3<f32>>() / points.len() as f32;
+    let covariance = points.iter()
+        .map(|point| (point - centroid) * (point - centroid).transpose())
+        .sum::<Matrix3<f32>>();
+    let eigen = SymmetricEigen::new(covariance);
+    // the spread across the line through the points, too small to solve the fit reliably when collinear
+    let (smallest, largest) = (eigen.eigenvalues.min(), eigen.eigenvalues.max());
+    let middle = eigen.eigenvalues.sum() - smallest - largest;
+    if middle <= largest * 1e-6{
+        return None;
+    }
+    let normal = eigen.eigenvectors.column(eigen.eigenvalues.imin()).into_owned();
+    let u = eigen.eigenvectors.column(eigen.eigenvalues.imax()).into_owned();
+    let v = normal.cross(&u);
+
+    // x² + y² = a·x + b·y + c in plane coordinates
+    let mut normal_matrix = Matrix3::<f32>::zeros();
+    let mut rhs = Vector3::<f32>::zeros();
+    for point in points{
+        let (x, y) = ((point - centroid).dot(&u), (point - centroid).dot(&v));
+        let row = Vector3::new(x, y, 1.0);
+        normal_matrix += row * row.transpose();
+        rhs += row * (x * x + y * y);
+    }
+    let [a, b, c]: [f32; 3] = normal_matrix.lu().solve(&rhs)?.into();
+
+    let (center_x, center_y) = (a / 2.0, b / 2.0);
+    let radius_squared = c + center_x * center_x + center_y * center_y;
+    if radius_squared <= 0.0 || !radius_squared.is_finite(){
+        return None; // collinear points
+    }
+
+    return Some(Circle{center: centroid + u * center_x + v * center_y, normal: normal, radius: radius_squared.sqrt()});
+}
+
+/// one measurement between points picked on the mesh, in model units
+#[derive(Clone, Debug)]
+pub struct Dimension{
+    pub kind: DimensionKind,
+    pub points: Vec<Vector3<f32>>,
+    pub value: f32,
+    pub circle: Option<Circle> // radius only
+}
+
+impl Dimension{
+    pub fn new(kind: DimensionKind, points: Vec<Vector3<f32>>) -> Result<Dimension, String>{
+        kind.check_point_count(points.len())?;
+        let mut circle = None;
+
+        let value = match kind{
+            DimensionKind::Distance => (points[1] - points[0]).norm(),
+            DimensionKind::Angle => {
+                let (leg_a, leg_b) = (points[0] - points[1], points[2] - points[1]);
+                if leg_a.norm() == 0.0 || leg_b.norm() == 0.0{
+                    return Err("The angle's corner coincides with one of its ends".to_string());
+                }
+                leg_a.angle(&leg_b).to_degrees()
+            },
+            DimensionKind::Radius => {
+                let fitted = fit_circle(&points).ok_or("Can't fit a circle, the points are collinear")?;
+                circle = Some(fitted);
+                fitted.radius
+            }
+        };
+
+        if !value.is_finite(){
+            return Err(format!("Degenerate {} measurement", kind.name()));
+        }
+
+        return Ok(Dimension{kind: kind, points: points, value: value, circle: circle});
+    }
+
+    /// where the value label goes
+    pub fn label_anchor(&self) -> Vector3<f32>{
+        return match self.kind{
+            DimensionKind::Distance => (self.points[0] + self.points[1]) / 2.0,
+            DimensionKind::Angle => self.points[1],
+            DimensionKind::Radius => self.circle.map(|circle| circle.center).unwrap_or(self.points[0])
+        };
+    }
+
+    /// dimension lines as segments
+    fn push_lines(&self, lines: &mut Vec<(Vector3<f32>, Vector3<f32>)>){
+        match self.kind{
+            DimensionKind::Distance => lines.push((self.points[0], self.points[1])),
+            DimensionKind::Angle => {
+                let corner = self.points[1];
+                let (leg_a, leg_b) = (self.points[0] - corner, self.points[2] - corner);
+                lines.push((corner, self.points[0]));
+                lines.push((corner, self.points[2]));
+
+                // arc at a third of the shorter leg
+                let arc_radius = leg_a.norm().min(leg_b.norm()) / 3.0;
+                let (dir_a, dir_b) = (leg_a.normalize(), leg_b.normalize());
+                let arc: Vec<Vector3<f32>> = (0..=ARC_SEGMENTS / 4)
+                    .map(|i| corner + dir_a.slerp(&dir_b, i as f32 / (ARC_SEGMENTS / 4) as f32) * arc_radius)
+                    .collect();
+                lines.extend(arc.windows(2).map(|pair| (pair[0], pair[1])));
+            },
+            DimensionKind::Radius => {
+                if let Some(circle) = self.circle{
+                    let u = (self.points[0] - circle.center).normalize();
+                    let v = circle.normal.cross(&u);
+                    let ring: Vec<Vector3<f32>> = (0..=ARC_SEGMENTS)
+                        .map(|i| {
+                            let angle = i as f32 / ARC_SEGMENTS as f32 * std::f32::consts::TAU;
+                            circle.center + (u * angle.cos() + v * angle.sin()) * circle.radius
+                        })
+                        .collect();
+                    lines.extend(ring.windows(2).map(|pair| (pair[0], pair[1])));
+                    lines.push((circle.center, self.points[0]));
+                }
+            }
+        }
+    }
+}
+
+/// interactive measurement: collects snapped points until a dimension is complete
+pub struct DimensionTool{
+    pub kind: DimensionKind,
+    pub snap: SnapMode,
+    pub pending_points: Vec<Vector3<f32>>,
+    pub dimensions: Vec<Dimension>
+}
+
+impl DimensionTool{
+    pub fn new() -> DimensionTool{
+        return DimensionTool{kind: DimensionKind::Distance, snap: SnapMode::Vertex, pending_points: vec![], dimensions: vec![]};
+    }
+
+    pub fn set_tool(&mut self, kind: DimensionKind, snap: SnapMode){
+        if kind != self.kind{
+            self.pending_points.clear();
+        }
+        self.kind = kind;
+        self.snap = snap;
+    }
+
+    /// adds a point, finishing the dimension once it has all of them
+    pub fn add_point(&mut self, point: Vector3<f32>) -> Result<(), String>{
+        self.pending_points.push(point);
+        if self.kind.point_count() == Some(self.pending_points.len()){
+            return self.finish();
+        }
+        Ok(())
+    }
+
+    /// turns the pending points into a dimension
+    pub fn finish(&mut self) -> Result<(), String>{
+        self.kind.check_point_count(self.pending_points.len())?;
+        let points = std::mem::take(&mut self.pending_points);
+        self.dimensions.push(Dimension::new(self.kind, points)?);
+        Ok(())
+    }
+
+    pub fn clear(&mut self){
+        self.pending_points.clear();
+        self.dimensions.clear();
+    }
+
+    /// Dimension lines, point markers of `marker_size` and the pending points joined up,
+    /// as positions and line indices.
+    pub fn create_line_buffers(&self, marker_size: f32) -> (Vec<f32>, Vec<usize>){
+        let mut lines: Vec<(Vector3<f32>, Vector3<f32>)> = vec![];
+
+        for dimension in &self.dimensions{
+            dimension.push_lines(&mut lines);
+        }
+        lines.extend(self.pending_points.windows(2).map(|pair| (pair[0], pair[1])));
+
+        let all_points = self.dimensions.iter().flat_map(|dimension| dimension.points.iter()).chain(self.pending_points.iter());
+        for point in all_points{ // axis aligned crosses
+            for axis in [Vector3::x(), Vector3::y(), Vector3::z()]{
+                lines.push((point - axis * marker_size, point + axis * marker_size));
+            }
+        }
+
+        let mut verts = vec![];
+        for (a, b) in &lines{
+            verts.extend_from_slice(a.as_slice());
+            verts.extend_from_slice(b.as_slice());
+        }
+        return (verts, (0..lines.len() * 2).collect());
+    }
+}
+
+impl Default for DimensionTool{
+    fn default() -> DimensionTool{
+        return DimensionTool::new();
+    }
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+
+    fn assert_close(actual: Vector3<f32>, expected: Vector3<f32>, tolerance: f32){
+        assert!((actual - expected).norm() < tolerance, "{:?} instead of {:?}", actual, expected);
+    }
+
+    #[test]
+    fn three_points_give_their_circle(){
+        // circle of radius 2 around (1, 2, 3) in a tilted plane
+        let center = Vector3::new(1.0, 2.0, 3.0);
+        let u = Vector3::new(1.0, 1.0, 0.0).normalize();
+        let v = Vector3::new(-1.0, 1.0, 1.0).normalize();
+        let points: Vec<Vector3<f32>> = [0.3f32, 2.0, 4.5].iter().map(|angle| center + (u * angle.cos() + v * angle.sin()) * 2.0).collect();
+
+        let circle = fit_circle(&points).unwrap();
+        assert_close(circle.center, center, 1e-4);
+        assert!((circle.radius - 2.0).abs() < 1e-4, "{}", circle.radius);
+        assert!(circle.normal.dot(&u.cross(&v)).abs() > 0.9999);
+    }
+
+    #[test]
+    fn noisy_points_fit_closely(){
+        let points: Vec<Vector3<f32>> = (0..24)
+            .map(|i| {
+                let angle = i as f32 / 24.0 * std::f32::consts::TAU;
+                let noise = if i % 2 == 0 { 0.01 } else { -0.01 };
+                Vector3::new(angle.cos() * (5.0 + noise), angle.sin() * (5.0 - noise), noise)
+            })
+            .collect();
+
+        let circle = fit_circle(&points).unwrap();
+        assert_close(circle.center, Vector3::zeros(), 0.01);
+        assert!((circle.radius - 5.0).abs() < 0.01, "{}", circle.radius);
+    }
+
+    #[test]
+    fn collinear_points_have_no_circle(){
+        let points = [Vector3::new(0.0, 0.0, 0.0), Vector3::new(1.0, 1.0, 1.0), Vector3::new(3.0, 3.0, 3.0)];
+        assert!(fit_circle(&points).is_none());
+        assert!(fit_circle(&points[..2]).is_none());
+        assert!(Dimension::new(DimensionKind::Radius, points.to_vec()).is_err());
+    }
+
+    #[test]
+    fn distance_and_angle_values(){
+        let distance = Dimension::new(DimensionKind::Distance, vec![Vector3::new(1.0, 1.0, 1.0), Vector3::new(4.0, 5.0, 1.0)]).unwrap();
+        assert_eq!(distance.value, 5.0);
+
+        let angle = Dimension::new(DimensionKind::Angle, vec![Vector3::new(2.0, 0.0, 0.0), Vector3::zeros(), Vector3::new(0.0, 0.0, 3.0)]).unwrap();
+        assert!((angle.value - 90.0).abs() < 1e-4, "{}", angle.value);
+
+        assert!(Dimension::new(DimensionKind::Angle, vec![Vector3::zeros(), Vector3::zeros(), Vector3::x()]).is_err());
+    }
+
+    #[test]
+    fn too_few_points_are_rejected(){
+        assert!(Dimension::new(DimensionKind::Distance, vec![Vector3::zeros()]).is_err());
+        assert!(Dimension::new(DimensionKind::Angle, vec![Vector3::zeros(), Vector3::x()]).is_err());
+        assert!(Dimension::new(DimensionKind::Radius, vec![Vector3::zeros(), Vector3::x()]).is_err());
+    }
+
+    #[test]
+    fn tool_finishes_distances_by_itself_and_radii_on_request(){
+        let mut tool = DimensionTool::new();
+        tool.add_point(Vector3::zeros()).unwrap();
+        assert!(tool.dimensions.is_empty());
+        tool.add_point(Vector3::new(0.0, 2.0, 0.0)).unwrap();
+        assert_eq!(tool.dimensions.len(), 1);
+        assert_eq!(tool.dimensions[0].value, 2.0);
+        assert!(tool.pending_points.is_empty());
+
+        tool.set_tool(DimensionKind::Radius, SnapMode::Vertex);
+        for point in [Vector3::x(), Vector3::y(), -Vector3::x(), -Vector3::y()]{
+            tool.add_point(point).unwrap();
+        }
+        assert_eq!(tool.dimensions.len(), 1);
+        assert_eq!(tool.pending_points.len(), 4);
+
+        tool.finish().unwrap();
+        assert_eq!(tool.dimensions.len(), 2);
+        assert_eq!(tool.dimensions[1].kind, DimensionKind::Radius);
+        assert!((tool.dimensions[1].value - 1.0).abs() < 1e-5);
+
+        // too few points stay pending
+        tool.add_point(Vector3::zeros()).unwrap();
+        assert!(tool.finish().is_err());
+        assert_eq!(tool.pending_points.len(), 1);
+    }
+}
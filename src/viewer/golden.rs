@@ -0,0 +1,191 @@
+//! Golden image tests: the bundled assets rendered headless through the software backend from fixed cameras,
+//! compared with the reference images in tests/golden. `UPDATE_GOLDEN=1 cargo test golden` rewrites the
+//! references, a failing comparison leaves the rendered image and a diff in target/golden.
+
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+
+use nalgebra::{Point3, Vector3};
+
+use crate::viewer::backend::SoftwareBackend;
+use crate::viewer::{Camera, Renderer};
+
+const SIZE: usize = 128;
+const SHADINGS: [&str; 3] = ["flat", "smooth", "wireframe"];
+/// directions from the target to the camera
+const CAMERAS: [(&str, [f32; 3]); 2] = [("front", [0.0, 0.0, 1.0]), ("iso", [1.0, 1.0, 1.0])];
+
+/// YIQ distance below which two pixels look the same, as a share of the largest distance there is
+const PIXEL_THRESHOLD: f32 = 0.1;
+/// share of the pixels allowed to look different, for edges rounding the other way
+const MAX_DIFFERENT_SHARE: f32 = 0.005;
+/// squared YIQ distance between black and white
+const MAX_YIQ_DELTA: f32 = 35215.0;
+
+/// RGBA, rows from the top
+struct Image{
+    width: usize,
+    height: usize,
+    pixels: Vec<u8>
+}
+
+impl Image{
+    fn pixel(&self, i: usize) -> [u8; 4]{
+        return [self.pixels[i * 4], self.pixels[i * 4 + 1], self.pixels[i * 4 + 2], self.pixels[i * 4 + 3]];
+    }
+
+    fn load(path: &Path) -> Result<Image, String>{
+        let file = File::open(path).map_err(|err| format!("{}: {}", path.display(), err))?;
+        let mut reader = png::Decoder::new(file).read_info().map_err(|err| format!("{}: {}", path.display(), err))?;
+        let mut pixels = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut pixels).map_err(|err| format!("{}: {}", path.display(), err))?;
+        if info.color_type != png::ColorType::Rgba || info.bit_depth != png::BitDepth::Eight{
+            return Err(format!("{}: not an 8 bit RGBA image", path.display()));
+        }
+        pixels.truncate(info.buffer_size());
+        return Ok(Image{width: info.width as usize, height: info.height as usize, pixels: pixels});
+    }
+
+    fn save(&self, path: &Path) -> Result<(), String>{
+        if let Some(directory) = path.parent(){
+            std::fs::create_dir_all(directory).map_err(|err| format!("{}: {}", directory.display(), err))?;
+        }
+        let file = File::create(path).map_err(|err| format!("{}: {}", path.display(), err))?;
+        let mut encoder = png::Encoder::new(BufWriter::new(file), self.width as u32, self.height as u32);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder.write_header().map_err(|err| format!("{}: {}", path.display(), err))?;
+        writer.write_image_data(&self.pixels).map_err(|err| format!("{}: {}", path.display(), err))?;
+        Ok(())
+    }
+}
+
+fn luma(r: f32, g: f32, b: f32) -> f32{
+    return r * 0.2989 + g * 0.5866 + b * 0.1145;
+}
+
+/// squared distance of the colors in YIQ space, which weighs them roughly as the eye does
+fn yiq_delta(a: [u8; 4], b: [u8; 4]) -> f32{
+    let [r, g, b] = [0, 1, 2].map(|channel| a[channel] as f32 - b[channel] as f32);
+    let y = luma(r, g, b);
+    let i = r * 0.5960 - g * 0.2742 - b * 0.3218;
+    let q = r * 0.2115 - g * 0.5226 + b * 0.3111;
+    return 0.5053 * y * y + 0.299 * i * i + 0.1957 * q * q;
+}
+
+/// Count of the pixels that look different, and an image of the reference faded with them in red.
+fn compare(actual: &Image, reference: &Image) -> (usize, Image){
+    let mut different = 0;
+    let mut diff = Vec::with_capacity(reference.pixels.len());
+    for i in 0..reference.width * reference.height{
+        let (a, b) = (actual.pixel(i), reference.pixel(i));
+        if yiq_delta(a, b) > PIXEL_THRESHOLD * PIXEL_THRESHOLD * MAX_YIQ_DELTA{
+            different += 1;
+            diff.extend_from_slice(&[255, 0, 0, 255]);
+        }else{
+            let faded = (255.0 + (luma(b[0] as f32, b[1] as f32, b[2] as f32) - 255.0) * 0.1) as u8;
+            diff.extend_from_slice(&[faded, faded, faded, 255]);
+        }
+    }
+    return (different, Image{width: reference.width, height: reference.height, pixels: diff});
+}
+
+fn golden_directory() -> PathBuf{
+    return Path::new(env!("CARGO_MANIFEST_DIR")).join("tests").join("golden");
+}
+
+fn output_directory() -> PathBuf{
+    return Path::new(env!("CARGO_MANIFEST_DIR")).join("target").join("golden");
+}
+
+/// the frame after the load animation played out, flipped to rows from the top
+fn render(renderer: &mut Renderer) -> Result<Image, String>{
+    renderer.render_frame(0.0)?;
+    renderer.render_frame(2.0)?;
+    let (width, height, pixels) = renderer.gl.read_pixels()?;
+    let pixels = pixels.chunks_exact(width * 4).rev().flatten().cloned().collect();
+    return Ok(Image{width: width, height: height, pixels: pixels});
+}
+
+/// Renders the asset in every shading from every camera, framed by its bounding sphere, and
+/// checks the images against the references. Reports all mismatching images at once. LOD is off,
+/// the references show the asset itself rather than what the simplifier makes of it.
+fn check_asset(name: &str, obj: &str){
+    let mut renderer = Renderer::with_backend(Box::new(SoftwareBackend::new(SIZE, SIZE)), SIZE as i32, SIZE as i32).unwrap();
+    let id = renderer.add_object(name.to_string(), obj.to_string(), None).unwrap();
+    renderer.set_lod_enabled(false).unwrap();
+
+    let bounds = renderer.scene.active().unwrap().mesh.bounding_sphere();
+    let distance = bounds.radius / (Camera::FOV / 2.0).sin() * 1.05;
+    let update = std::env::var("UPDATE_GOLDEN").is_ok_and(|value| value == "1");
+
+    let mut failures = vec![];
+    for shading in SHADINGS{
+        renderer.set_object_shading(id, shading.to_string()).unwrap();
+        for (camera_name, direction) in CAMERAS{
+            let direction = Vector3::from(direction).normalize();
+            let target = Point3::from(bounds.center);
+            renderer.camera = Camera::new(target + direction * distance, target, Vector3::y());
+
+            let image_name = format!("{}_{}_{}.png", name, shading, camera_name);
+            let actual = render(&mut renderer).unwrap();
+            let reference_path = golden_directory().join(&image_name);
+            if update{
+                actual.save(&reference_path).unwrap();
+                continue;
+            }
+
+            let reference = match Image::load(&reference_path){
+                Ok(reference) => reference,
+                Err(err) => {
+                    failures.push(format!("{}, run with UPDATE_GOLDEN=1 to create it", err));
+                    continue;
+                }
+            };
+            if (reference.width, reference.height) != (actual.width, actual.height){
+                failures.push(format!("{} is {}x{}, the rendering {}x{}", image_name, reference.width, reference.height, actual.width, actual.height));
+                continue;
+            }
+
+            let (different, diff) = compare(&actual, &reference);
+            if different as f32 > MAX_DIFFERENT_SHARE * (SIZE * SIZE) as f32{
+                let actual_path = output_directory().join(&image_name);
+                let diff_path = output_directory().join(format!("{}_{}_{}_diff.png", name, shading, camera_name));
+                actual.save(&actual_path).unwrap();
+                diff.save(&diff_path).unwrap();
+                failures.push(format!("{} differs in {} pixels, see {} and {}", image_name, different, actual_path.display(), diff_path.display()));
+            }
+        }
+    }
+    assert!(failures.is_empty(), "{}", failures.join("\n"));
+}
+
+#[test]
+fn teapot(){
+    check_asset("teapot", include_str!("../../assets/teapot.obj"));
+}
+
+#[test]
+fn sphere(){
+    check_asset("sphere", include_str!("../../assets/sphere.obj"));
+}
+
+#[test]
+fn gear(){
+    check_asset("gear", include_str!("../../assets/gear.obj"));
+}
+
+#[test]
+fn comparison_tolerates_slight_changes(){
+    let image = |pixels: &[[u8; 4]]| Image{width: pixels.len(), height: 1, pixels: pixels.concat()};
+    let reference = image(&[[0, 0, 0, 255], [200, 100, 50, 255], [255, 255, 255, 255]]);
+
+    let (different, _) = compare(&image(&[[3, 3, 3, 255], [204, 98, 50, 255], [250, 252, 255, 255]]), &reference);
+    assert_eq!(different, 0);
+
+    let (different, diff) = compare(&image(&[[0, 0, 0, 255], [100, 100, 50, 255], [0, 0, 0, 255]]), &reference);
+    assert_eq!(different, 2);
+    assert_eq!(diff.pixel(1), [255, 0, 0, 255]);
+    assert_ne!(diff.pixel(0), [255, 0, 0, 255]);
+}
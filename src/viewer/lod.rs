@@ -0,0 +1,87 @@
+use crate::mesh::Mesh;
+
+/// fraction of the full detail faces kept by each generated level
+const LOD_FACE_RATIOS: [f32; 3] = [0.5, 0.25, 0.1];
+/// meshes with fewer faces are always drawn in full detail
+const LOD_MIN_FACE_COUNT: usize = 2000;
+/// projected size (in pixels) under which level i is replaced by the coarser level i+1
+const LOD_SWITCH_SIZES: [f32; 3] = [500.0, 250.0, 120.0];
+/// relative band around a switch size in which the current level is kept, avoids popping
+const LOD_HYSTERESIS: f32 = 0.15;
+
+/// builds the reduced levels of detail, each one simplified from the previous
+pub fn generate_lod_meshes(mesh: &Mesh) -> Result<Vec<Mesh>, String>{
+    let mut lods: Vec<Mesh> = vec![];
+
+    if mesh.face_count() < LOD_MIN_FACE_COUNT{
+        return Ok(lods);
+    }
+
+    for ratio in LOD_FACE_RATIOS{
+        let target_face_count = (mesh.face_count() as f32 * ratio) as usize;
+        let source = lods.last().unwrap_or(mesh);
+        let source_face_count = source.face_count();
+
+        let lod = source.simplify(target_face_count)?;
+        if lod.face_count() >= source_face_count{ // simplification got stuck, coarser levels won't help
+            break;
+        }
+        lods.push(lod);
+    }
+
+    return Ok(lods);
+}
+
+/// picks the level for the projected size, 0 being full detail and `level_count` including it.
+/// Levels past the last switch size are never picked.
+pub fn select_lod(current: usize, level_count: usize, screen_size: f32) -> usize{
+    let level_count = level_count.min(LOD_SWITCH_SIZES.len() + 1);
+    if level_count <= 1{
+        return 0;
+    }
+
+    let mut level = current.min(level_count - 1);
+
+    while level + 1 < level_count && screen_size < LOD_SWITCH_SIZES[level] * (1.0 - LOD_HYSTERESIS){
+        level += 1;
+    }
+    while level > 0 && screen_size > LOD_SWITCH_SIZES[level - 1] * (1.0 + LOD_HYSTERESIS){
+        level -= 1;
+    }
+
+    return level;
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+
+    #[test]
+    fn level_holds_inside_the_hysteresis_band(){
+        let switch = LOD_SWITCH_SIZES[0];
+        let mut level = 0;
+        // jitter around the switch size doesn't flip the level either way
+        for size in [switch * 1.05, switch * 0.95, switch * 1.1, switch * 0.9]{
+            level = select_lod(level, 4, size);
+            assert_eq!(level, 0);
+        }
+
+        level = select_lod(level, 4, switch * 0.8);
+        assert_eq!(level, 1);
+        for size in [switch * 0.95, switch * 1.05, switch * 0.9, switch * 1.1]{
+            level = select_lod(level, 4, size);
+            assert_eq!(level, 1);
+        }
+
+        assert_eq!(select_lod(level, 4, switch * 1.2), 0);
+    }
+
+    #[test]
+    fn far_away_picks_the_coarsest_level(){
+        assert_eq!(select_lod(0, 4, 1.0), 3);
+        assert_eq!(select_lod(3, 4, 10000.0), 0);
+        assert_eq!(select_lod(2, 1, 1.0), 0);
+        assert_eq!(select_lod(0, 10, 1.0), LOD_SWITCH_SIZES.len());
+        assert_eq!(select_lod(9, 10, 1.0), LOD_SWITCH_SIZES.len());
+    }
+}
@@ -0,0 +1,279 @@
+use nalgebra::{Matrix3, Matrix4};
+
+use crate::viewer::RenderedMesh;
+
+/// Node of the scene tree, addressed by its id from JS. Groups have no mesh and only place their children.
+pub struct SceneNode{
+    pub id: u32,
+    pub name: String,
+    pub parent: Option<u32>,
+    pub children: Vec<u32>,
+    pub local: Matrix4<f32>, // relative to the parent
+    pub world: Matrix4<f32>, // model matrix, parent world * local
+    pub normal_matrix: Matrix3<f32>, // inverse transpose of the world rotation and scale
+    pub rendered_mesh: Option<RenderedMesh>
+}
+
+/// Tree of nodes drawn together, each mesh with its own shading and color.
+/// One mesh is active, that's the one editing, picking and measuring work on.
+pub struct Scene{
+    nodes: Vec<SceneNode>, // parents before their children, in drawing order otherwise
+    active: Option<u32>,
+    next_id: u32
+}
+
+impl Scene{
+    pub fn new() -> Scene{
+        return Scene{nodes: vec![], active: None, next_id: 0};
+    }
+
+    /// true without any mesh to draw
+    pub fn is_empty(&self) -> bool{
+        return self.nodes.iter().all(|node| node.rendered_mesh.is_none());
+    }
+
+    /// Adds a node under `parent`, or as a root, placed by `local`. A mesh node becomes active.
+    pub fn add(&mut self, name: String, parent: Option<u32>, local: Matrix4<f32>, rendered_mesh: Option<RenderedMesh>) -> Result<u32, String>{
+        let parent_world = match parent{
+            Some(parent) => self.get(parent)?.world,
+            None => Matrix4::identity()
+        };
+
+        let id = self.next_id;
+        self.next_id += 1;
+        if rendered_mesh.is_some(){
+            self.active = Some(id);
+        }
+
+        let mut node = SceneNode{id: id, name: name, parent: parent, children: vec![], local: local, world: Matrix4::identity(),
+            normal_matrix: Matrix3::identity(), rendered_mesh: rendered_mesh};
+        node.update_world(&parent_world);
+        if let Some(parent) = parent{
+            self.get_mut(parent)?.children.push(id);
+        }
+        self.nodes.push(node);
+        return Ok(id);
+    }
+
+    /// ids of the node and everything below it, parents first
+    pub fn subtree(&self, id: u32) -> Result<Vec<u32>, String>{
+        let mut ids = vec![id];
+        let mut i = 0;
+        while i < ids.len(){
+            ids.extend(self.get(ids[i])?.children.iter().cloned());
+            i += 1;
+        }
+        return Ok(ids);
+    }
+
+    /// Takes the node out with everything below it. When the active mesh goes
+    /// the last mesh left becomes active.
+    pub fn remove(&mut self, id: u32) -> Result<Vec<SceneNode>, String>{
+        let ids = self.subtree(id)?;
+        if let Some(parent) = self.get(id)?.parent{
+            self.get_mut(parent)?.children.retain(|child| *child != id);
+        }
+
+        let (removed, kept): (Vec<SceneNode>, Vec<SceneNode>) = self.nodes.drain(..).partition(|node| ids.contains(&node.id));
+        self.nodes = kept;
+        if self.active.is_some_and(|active| ids.contains(&active)){
+            self.active = self.nodes.iter().rev().find(|node| node.rendered_mesh.is_some()).map(|node| node.id);
+        }
+        return Ok(removed);
+    }
+
+    pub fn get(&self, id: u32) -> Result<&SceneNode, String>{
+        return self.nodes.iter().find(|node| node.id == id).ok_or(format!("No object with id {}", id));
+    }
+
+    pub fn get_mut(&mut self, id: u32) -> Result<&mut SceneNode, String>{
+        return self.nodes.iter_mut().find(|node| node.id == id).ok_or(format!("No object with id {}", id));
+    }
+
+    /// Moves the node under `parent`, or to the roots, keeping its world placement.
+    pub fn set_parent(&mut self, id: u32, parent: Option<u32>) -> Result<(), String>{
+        let world = self.get(id)?.world;
+        if let Some(parent) = parent{
+            if self.subtree(id)?.contains(&parent){
+                return Err("A node can't be moved below itself".to_string());
+            }
+            self.get(parent)?;
+        }
+
+        if let Some(old_parent) = self.get(id)?.parent{
+            self.get_mut(old_parent)?.children.retain(|child| *child != id);
+        }
+        self.get_mut(id)?.parent = parent;
+        if let Some(parent) = parent{
+            self.get_mut(parent)?.children.push(id);
+        }
+
+        // keep parents ahead of their children
+        let ids = self.subtree(id)?;
+        let (mut moved, kept): (Vec<SceneNode>, Vec<SceneNode>) = self.nodes.drain(..).partition(|node| ids.contains(&node.id));
+        moved.sort_by_key(|node| ids.iter().position(|moved_id| *moved_id == node.id));
+        self.nodes = kept;
+        self.nodes.append(&mut moved);
+
+        return self.set_world(id, &world);
+    }
+
+    pub fn set_local(&mut self, id: u32, local: &Matrix4<f32>) -> Result<(), String>{
+        self.get_mut(id)?.local = *local;
+        return self.update_world_transforms(id);
+    }
+
+    /// places the node in the world by changing its local transform
+    pub fn set_world(&mut self, id: u32, world: &Matrix4<f32>) -> Result<(), String>{
+        let parent_world = match self.get(id)?.parent{
+            Some(parent) => self.get(parent)?.world,
+            None => Matrix4::identity()
+        };
+        let inverse_parent = parent_world.try_inverse().ok_or("Parent transform is not invertible")?;
+        return self.set_local(id, &(inverse_parent * world));
+    }
+
+    /// Makes the node's local transform the identity, handing it to its children so they keep their place.
+    /// The caller moves the node's mesh by it.
+    pub fn take_local(&mut self, id: u32) -> Result<(), String>{
+        let local = self.get(id)?.local;
+        for child in self.get(id)?.children.clone(){
+            let child = self.get_mut(child)?;
+            child.local = local * child.local;
+        }
+        return self.set_local(id, &Matrix4::identity());
+    }
+
+    /// recomputes the world matrices below and including `id` after its local transform changed
+    fn update_world_transforms(&mut self, id: u32) -> Result<(), String>{
+        for id in self.subtree(id)?{
+            let parent_world = match self.get(id)?.parent{
+                Some(parent) => self.get(parent)?.world,
+                None => Matrix4::identity()
+            };
+            self.get_mut(id)?.update_world(&parent_world);
+        }
+        return Ok(());
+    }
+
+    pub fn active_id(&self) -> Option<u32>{
+        return self.active;
+    }
+
+    pub fn set_active(&mut self, id: u32) -> Result<(), String>{
+        if self.get(id)?.rendered_mesh.is_none(){
+            return Err(format!("Object {} has no mesh", id));
+        }
+        self.active = Some(id);
+        return Ok(());
+    }
+
+    pub fn active_node(&self) -> Option<&SceneNode>{
+        let id = self.active?;
+        return self.nodes.iter().find(|node| node.id == id);
+    }
+
+    pub fn active(&self) -> Option<&RenderedMesh>{
+        return self.active_node()?.rendered_mesh.as_ref();
+    }
+
+    pub fn active_mut(&mut self) -> Option<&mut RenderedMesh>{
+        let id = self.active?;
+        return self.nodes.iter_mut().find(|node| node.id == id)?.rendered_mesh.as_mut();
+    }
+
+    pub fn nodes(&self) -> &[SceneNode]{
+        return &self.nodes;
+    }
+
+    pub fn nodes_mut(&mut self) -> &mut [SceneNode]{
+        return &mut self.nodes;
+    }
+}
+
+impl Default for Scene{
+    fn default() -> Scene{
+        return Scene::new();
+    }
+}
+
+impl SceneNode{
+    fn update_world(&mut self, parent_world: &Matrix4<f32>){
+        self.world = parent_world * self.local;
+        self.normal_matrix = self.world.fixed_view::<3, 3>(0, 0).try_inverse().unwrap_or(Matrix3::identity()).transpose();
+    }
+}
+
+#[cfg(test)]
+mod tests{
+    use nalgebra::{Matrix4, Vector3};
+
+    use super::Scene;
+    use crate::mesh::Mesh;
+    use crate::viewer::backend::SoftwareBackend;
+    use crate::viewer::{FalseColor, RenderedMesh, ShadingType};
+
+    const TRIANGLE: &str = "v 0 0 0\nv 1 0 0\nv 0 1 0\nf 1 2 3\n";
+
+    fn rendered_triangle() -> Option<RenderedMesh>{
+        let gl = SoftwareBackend::new(1, 1);
+        let mesh = Mesh::load_obj(TRIANGLE).unwrap();
+        return Some(RenderedMesh::new(&gl, mesh, ShadingType::Flat, FalseColor::new()).unwrap());
+    }
+
+    fn translation(x: f32, y: f32, z: f32) -> Matrix4<f32>{
+        return Matrix4::new_translation(&Vector3::new(x, y, z));
+    }
+
+    #[test]
+    fn reparenting_refuses_cycles_and_keeps_the_world_placement(){
+        let mut scene = Scene::new();
+        let group = scene.add("group".to_string(), None, translation(1.0, 0.0, 0.0), None).unwrap();
+        let child = scene.add("child".to_string(), Some(group), translation(0.0, 2.0, 0.0), None).unwrap();
+        let other = scene.add("other".to_string(), None, Matrix4::new_scaling(2.0), None).unwrap();
+
+        assert!(scene.set_parent(group, Some(child)).is_err());
+        assert!(scene.set_parent(group, Some(group)).is_err());
+        assert_eq!(scene.get(child).unwrap().parent, Some(group));
+
+        let world = scene.get(child).unwrap().world;
+        scene.set_parent(child, Some(other)).unwrap();
+        assert!((scene.get(child).unwrap().world - world).abs().max() < 1e-6);
+        assert_eq!(scene.get(other).unwrap().children, vec![child]);
+        assert!(scene.get(group).unwrap().children.is_empty());
+
+        // the new parent comes first in drawing order
+        let position = |id: u32| scene.nodes().iter().position(|node| node.id == id).unwrap();
+        assert!(position(other) < position(child));
+    }
+
+    #[test]
+    fn removing_drops_the_subtree_and_picks_another_active_mesh(){
+        let mut scene = Scene::new();
+        let first = scene.add("first".to_string(), None, Matrix4::identity(), rendered_triangle()).unwrap();
+        let group = scene.add("group".to_string(), None, Matrix4::identity(), None).unwrap();
+        let child = scene.add("child".to_string(), Some(group), Matrix4::identity(), rendered_triangle()).unwrap();
+        assert_eq!(scene.active_id(), Some(child));
+
+        let removed: Vec<u32> = scene.remove(group).unwrap().iter().map(|node| node.id).collect();
+        assert_eq!(removed, vec![group, child]);
+        assert!(scene.get(child).is_err());
+        assert_eq!(scene.active_id(), Some(first));
+
+        scene.remove(first).unwrap();
+        assert_eq!(scene.active_id(), None);
+        assert!(scene.is_empty());
+    }
+
+    #[test]
+    fn taking_the_local_transform_keeps_the_children_in_place(){
+        let mut scene = Scene::new();
+        let parent = scene.add("parent".to_string(), None, translation(1.0, 2.0, 3.0) * Matrix4::new_scaling(2.0), None).unwrap();
+        let child = scene.add("child".to_string(), Some(parent), translation(0.0, 1.0, 0.0), None).unwrap();
+        let world = scene.get(child).unwrap().world;
+
+        scene.take_local(parent).unwrap();
+        assert_eq!(scene.get(parent).unwrap().local, Matrix4::identity());
+        assert!((scene.get(child).unwrap().world - world).abs().max() < 1e-6);
+    }
+}
@@ -0,0 +1,193 @@
+use std::collections::BTreeSet;
+
+use nalgebra::{Matrix4, Point2};
+
+use crate::mesh::{Mesh, SurfacePoint};
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SelectionMode{
+    Faces,
+    Vertices,
+    Parts // whole connected parts, stored as their faces
+}
+
+/// Selected faces or vertices of the displayed mesh, by index.
+/// Vertex ids include the copies split off by normals.
+pub struct Selection{
+    mode: SelectionMode,
+    indices: BTreeSet<usize>,
+    face_count: usize, // of the mesh the indices refer to
+    vert_count: usize
+}
+
+impl Selection{
+    pub fn new(mode: SelectionMode, mesh: &Mesh) -> Selection{
+        return Selection{mode: mode, indices: BTreeSet::new(), face_count: mesh.face_count(), vert_count: mesh.vert_count()};
+    }
+
+    pub fn mode(&self) -> SelectionMode{
+        return self.mode;
+    }
+
+    /// switching between face and vertex selection clears it
+    pub fn set_mode(&mut self, mode: SelectionMode){
+        let selects_faces = |mode: SelectionMode| mode != SelectionMode::Vertices;
+        if selects_faces(mode) != selects_faces(self.mode){
+            self.indices.clear();
+        }
+        self.mode = mode;
+    }
+
+    pub fn clear(&mut self){
+        self.indices.clear();
+    }
+
+    pub fn indices(&self) -> Vec<usize>{
+        return self.indices.iter().cloned().collect();
+    }
+
+    /// faces or vertices under a pick, depending on the mode
+    pub fn items_at(&self, mesh: &Mesh, hit: &SurfacePoint) -> Vec<usize>{
+        return match self.mode{
+            SelectionMode::Faces => vec![hit.face],
+            SelectionMode::Vertices => mesh.coincident_vertices(mesh.nearest_face_corner(hit.face, &hit.barycentric)),
+            SelectionMode::Parts => mesh.part_faces(&[hit.face])
+        };
+    }
+
+    /// faces or vertices projecting into a rectangle in normalized device coordinates
+    pub fn items_in_rect(&self, mesh: &Mesh, model_view_projection: &Matrix4<f32>, rect_min: &Point2<f32>, rect_max: &Point2<f32>) -> Vec<usize>{
+        return match self.mode{
+            SelectionMode::Faces => mesh.faces_in_screen_rect(model_view_projection, rect_min, rect_max),
+            SelectionMode::Vertices => mesh.vertices_in_screen_rect(model_view_projection, rect_min, rect_max),
+            SelectionMode::Parts => mesh.part_faces(&mesh.faces_in_screen_rect(model_view_projection, rect_min, rect_max))
+        };
+    }
+
+    /// Click: replaces the selection, or with `toggle` (shift-click) flips the items in or out.
+    pub fn click(&mut self, items: Vec<usize>, toggle: bool){
+        if !toggle{
+            self.indices = items.into_iter().collect();
+            return;
+        }
+
+        // a part counts as selected as a whole
+        if items.iter().all(|item| self.indices.contains(item)){
+            for item in items{
+                self.indices.remove(&item);
+            }
+        }else{
+            self.indices.extend(items);
+        }
+    }
+
+    /// Box: replaces the selection, or with `add` extends it.
+    pub fn select_box(&mut self, items: Vec<usize>, add: bool){
+        if !add{
+            self.indices.clear();
+        }
+        self.indices.extend(items);
+    }
+
+    /// Drops the selection when the mesh was rebuilt with other faces or vertices,
+    /// edits moving vertices keep it. Returns whether it was dropped.
+    pub fn mesh_changed(&mut self, mesh: &Mesh) -> bool{
+        if mesh.face_count() == self.face_count && mesh.vert_count() == self.vert_count{
+            return false;
+        }
+        self.face_count = mesh.face_count();
+        self.vert_count = mesh.vert_count();
+        self.indices.clear();
+        return true;
+    }
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+    use nalgebra::Vector3;
+    use crate::mesh::tests::two_cubes;
+
+    /// a pick on `face`, the selection only reads the face and the weights
+    fn hit(face: usize, barycentric: Vector3<f32>) -> SurfacePoint{
+        return SurfacePoint{face: face, position: Vector3::zeros(), barycentric: barycentric, normal: Vector3::z(), distance: 1.0};
+    }
+
+    #[test]
+    fn click_replaces_or_toggles(){
+        let mut selection = Selection::new(SelectionMode::Faces, &two_cubes());
+        selection.click(vec![1], false);
+        selection.click(vec![3], false);
+        assert_eq!(selection.indices(), vec![3]);
+
+        selection.click(vec![5], true);
+        assert_eq!(selection.indices(), vec![3, 5]);
+        selection.click(vec![3], true);
+        assert_eq!(selection.indices(), vec![5]);
+
+        // a part goes out only when all of it was in
+        selection.click(vec![0, 1, 2], true);
+        assert_eq!(selection.indices(), vec![0, 1, 2, 5]);
+        selection.click(vec![0, 1], true);
+        assert_eq!(selection.indices(), vec![2, 5]);
+        selection.click(vec![2, 4], true);
+        assert_eq!(selection.indices(), vec![2, 4, 5]);
+    }
+
+    #[test]
+    fn box_select_replaces_or_adds(){
+        let mut selection = Selection::new(SelectionMode::Faces, &two_cubes());
+        selection.select_box(vec![1, 2], false);
+        selection.select_box(vec![4, 2], true);
+        assert_eq!(selection.indices(), vec![1, 2, 4]);
+        selection.select_box(vec![0], false);
+        assert_eq!(selection.indices(), vec![0]);
+    }
+
+    #[test]
+    fn parts_select_whole_cubes(){
+        let mesh = two_cubes();
+        let mut selection = Selection::new(SelectionMode::Parts, &mesh);
+        assert_eq!(selection.items_at(&mesh, &hit(14, Vector3::x())), (12..24).collect::<Vec<_>>());
+
+        // the big cube's +x side, at x 3.25 after centering, looking down the z axis
+        let (rect_min, rect_max) = (Point2::new(3.0, -1.5), Point2::new(3.5, 1.5));
+        selection.set_mode(SelectionMode::Faces);
+        let faces = selection.items_in_rect(&mesh, &Matrix4::identity(), &rect_min, &rect_max);
+        assert!(faces.len() == 2 && faces.iter().all(|face| (12..24).contains(face)), "{:?}", faces);
+        selection.set_mode(SelectionMode::Parts);
+        assert_eq!(selection.items_in_rect(&mesh, &Matrix4::identity(), &rect_min, &rect_max), (12..24).collect::<Vec<_>>());
+
+        // in vertex mode the corner with the largest weight, with the copies at its position
+        selection.set_mode(SelectionMode::Vertices);
+        let verts = selection.items_at(&mesh, &hit(3, Vector3::new(0.2, 0.7, 0.1)));
+        assert_eq!(verts, mesh.coincident_vertices(mesh.nearest_face_corner(3, &Vector3::y())));
+        assert_ne!(verts, selection.items_at(&mesh, &hit(3, Vector3::new(0.7, 0.2, 0.1))));
+    }
+
+    #[test]
+    fn switching_between_faces_and_vertices_clears(){
+        let mut selection = Selection::new(SelectionMode::Faces, &two_cubes());
+        selection.click(vec![0, 1], false);
+        selection.set_mode(SelectionMode::Parts);
+        assert_eq!(selection.indices(), vec![0, 1]);
+        selection.set_mode(SelectionMode::Vertices);
+        assert!(selection.indices().is_empty());
+    }
+
+    #[test]
+    fn selection_survives_moved_vertices_only(){
+        let mut mesh = two_cubes();
+        let mut selection = Selection::new(SelectionMode::Faces, &mesh);
+        selection.click(vec![4, 7], false);
+
+        mesh.transform(&Matrix4::new_scaling(2.0)).unwrap();
+        assert!(!selection.mesh_changed(&mesh));
+        assert_eq!(selection.indices(), vec![4, 7]);
+
+        let (subdivided, _) = mesh.subdivide(1, 30.0).unwrap();
+        assert!(selection.mesh_changed(&subdivided));
+        assert!(selection.indices().is_empty());
+        assert!(!selection.mesh_changed(&subdivided));
+    }
+}
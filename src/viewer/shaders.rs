@@ -0,0 +1,197 @@
+pub const VSHADER_FLAT: &str = 
+"#version 300 es
+precision highp float;
+layout(location = 0) in vec3 aPosition;
+layout(location = 1) in vec3 aNormal;
+layout(location = 2) in vec3 aColor;
+layout(location = 3) in mat4 aInstanceModel; // takes locations 3 to 6, identity unless drawn instanced
+layout(location = 7) in vec3 aInstanceColor;
+layout(location = 8) in mat3 aInstanceNormalMatrix; // takes locations 8 to 10, inverse transpose of aInstanceModel
+
+layout(std140) uniform Frame {
+    mat4 projection;
+    mat4 view;
+    float animTime;
+};
+
+uniform mat4 model;
+uniform mat3 normalMatrix;
+
+flat out vec3 Normal;
+out vec3 FragPos;
+out vec3 Color;
+
+float easeOutCubic(float x) {
+    return 1.0 - pow(1.0 - x, 3.0);
+}
+
+void main() {
+    FragPos = vec3(model * aInstanceModel * vec4(aPosition, 1.0));
+    Normal = normalMatrix * aInstanceNormalMatrix * aNormal;
+    Color = aColor * aInstanceColor;
+    gl_Position = projection * view * vec4(FragPos * easeOutCubic(min(animTime, 1.0)), 1.0);
+}";
+
+pub const FSHADER_FLAT: &str = 
+"#version 300 es
+precision highp float;
+
+flat in vec3 Normal;
+in vec3 FragPos;
+in vec3 Color;
+out vec4 outColor;
+
+layout(std140) uniform Light {
+    vec3 lightPos;
+    vec3 lightColor;
+};
+
+uniform vec3 objectColor;
+
+uniform vec4 clipPlanes[6]; // normal and offset, clips where dot(normal, FragPos) > offset
+uniform int clipPlaneCount;
+
+void main() {
+    for (int i = 0; i < clipPlaneCount; i++) {
+        if (dot(clipPlanes[i].xyz, FragPos) > clipPlanes[i].w) {
+            discard;
+        }
+    }
+
+    vec3 baseColor = Color * objectColor;
+
+    float ambientStrength = 0.1;
+    vec3 ambient = baseColor * ambientStrength;
+
+    vec3 lightDir = normalize(lightPos - FragPos);
+    float diff = max(dot(normalize(Normal), lightDir), 0.0);
+    vec3 diffuse = diff * lightColor;
+
+    outColor = vec4((ambient + diffuse) * baseColor, 1.0);
+}";
+
+pub const VSHADER_SMOOTH: &str = 
+"#version 300 es
+precision highp float;
+
+layout(location = 0) in vec3 aPosition;
+layout(location = 1) in vec3 aNormal;
+layout(location = 2) in vec3 aColor;
+layout(location = 3) in mat4 aInstanceModel; // takes locations 3 to 6, identity unless drawn instanced
+layout(location = 7) in vec3 aInstanceColor;
+layout(location = 8) in mat3 aInstanceNormalMatrix; // takes locations 8 to 10, inverse transpose of aInstanceModel
+
+layout(std140) uniform Frame {
+    mat4 projection;
+    mat4 view;
+    float animTime;
+};
+
+uniform mat4 model;
+uniform mat3 normalMatrix;
+
+out vec3 Normal;
+out vec3 FragPos;
+out vec3 Color;
+
+float easeOutCubic(float x) {
+    return 1.0 - pow(1.0 - x, 3.0);
+}
+
+void main() {
+    FragPos = vec3(model * aInstanceModel * vec4(aPosition, 1.0));
+    Normal = normalMatrix * aInstanceNormalMatrix * aNormal;
+    Color = aColor * aInstanceColor;
+    gl_Position = projection * view * vec4(FragPos * easeOutCubic(min(animTime, 1.0)), 1.0);
+}";
+
+pub const FSHADER_SMOOTH: &str = 
+"#version 300 es
+precision highp float;
+
+in vec3 Normal;
+in vec3 FragPos;
+in vec3 Color;
+out vec4 outColor;
+
+layout(std140) uniform Light {
+    vec3 lightPos;
+    vec3 lightColor;
+};
+
+uniform vec3 objectColor;
+
+uniform vec4 clipPlanes[6]; // normal and offset, clips where dot(normal, FragPos) > offset
+uniform int clipPlaneCount;
+
+void main() {
+    for (int i = 0; i < clipPlaneCount; i++) {
+        if (dot(clipPlanes[i].xyz, FragPos) > clipPlanes[i].w) {
+            discard;
+        }
+    }
+
+    vec3 baseColor = Color * objectColor;
+
+    float ambientStrength = 0.1;
+    vec3 ambient = baseColor * ambientStrength;
+
+    vec3 lightDir = normalize(lightPos - FragPos);
+    float diff = max(dot(normalize(Normal), lightDir), 0.0);
+    vec3 diffuse = diff * lightColor;
+
+    outColor = vec4((ambient + diffuse) * baseColor, 1.0);
+}";
+
+pub const VSHADER_LINE: &str = 
+"#version 300 es
+precision highp float;
+
+layout(location = 0) in vec3 aPosition;
+layout(location = 3) in mat4 aInstanceModel; // takes locations 3 to 6, identity unless drawn instanced
+layout(location = 7) in vec3 aInstanceColor;
+
+layout(std140) uniform Frame {
+    mat4 projection;
+    mat4 view;
+    float animTime;
+};
+
+uniform mat4 model;
+
+out vec3 FragPos;
+out vec3 InstanceColor;
+
+float easeOutCubic(float x) {
+    return 1.0 - pow(1.0 - x, 3.0);
+}
+
+void main() {
+    FragPos = vec3(model * aInstanceModel * vec4(aPosition, 1.0));
+    InstanceColor = aInstanceColor;
+    gl_Position = projection * view * vec4(FragPos * easeOutCubic(min(animTime, 1.0)), 1.0);
+    gl_PointSize = 6.0; // for drawing selected vertices
+}";
+
+pub const FSHADER_LINE: &str = 
+"#version 300 es
+precision highp float;
+
+in vec3 FragPos;
+in vec3 InstanceColor;
+out vec4 outColor;
+
+uniform vec3 objectColor;
+
+uniform vec4 clipPlanes[6]; // normal and offset, clips where dot(normal, FragPos) > offset
+uniform int clipPlaneCount;
+
+void main() {
+    for (int i = 0; i < clipPlaneCount; i++) {
+        if (dot(clipPlanes[i].xyz, FragPos) > clipPlanes[i].w) {
+            discard;
+        }
+    }
+
+    outColor = vec4(objectColor * InstanceColor, 1.0);
+}";
\ No newline at end of file
@@ -0,0 +1,169 @@
+use nalgebra::{Matrix4, Vector3};
+
+/// member types the uniform blocks use, laid out by the std140 rules
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Std140Type{
+    Float,
+    Vec3,
+    Mat4 // four vec4 columns
+}
+
+impl Std140Type{
+    /// in bytes
+    pub fn alignment(&self) -> usize{
+        return match self{
+            Std140Type::Float => 4,
+            Std140Type::Vec3 | Std140Type::Mat4 => 16
+        };
+    }
+
+    /// in bytes, a vec3 leaves its last 4 bytes to a following float
+    pub fn size(&self) -> usize{
+        return match self{
+            Std140Type::Float => 4,
+            Std140Type::Vec3 => 12,
+            Std140Type::Mat4 => 64
+        };
+    }
+
+    #[cfg(test)]
+    fn glsl_name(&self) -> &'static str{
+        return match self{
+            Std140Type::Float => "float",
+            Std140Type::Vec3 => "vec3",
+            Std140Type::Mat4 => "mat4"
+        };
+    }
+}
+
+/// Rust side of a `layout(std140)` uniform block the shaders declare without an instance name.
+/// The members are listed once, the offsets are derived from them and the tests check the shaders' declarations.
+pub trait UniformBlock{
+    const NAME: &'static str;
+    /// binding point the block's buffer is bound to for every program
+    const BINDING: u32;
+    const MEMBERS: &'static [(&'static str, Std140Type)];
+
+    /// values of the members in order, matrices column major
+    fn member_values(&self) -> Vec<&[f32]>;
+}
+
+/// byte offsets of the members and the size of the whole block, rounded up to a vec4
+pub fn std140_layout(members: &[(&str, Std140Type)]) -> (Vec<usize>, usize){
+    let mut offsets = vec![];
+    let mut offset: usize = 0;
+    for (_, member_type) in members{
+        offset = offset.next_multiple_of(member_type.alignment());
+        offsets.push(offset);
+        offset += member_type.size();
+    }
+    return (offsets, offset.next_multiple_of(16));
+}
+
+/// the block's contents as uploaded to its buffer, padding zeroed
+pub fn std140_values<B: UniformBlock>(block: &B) -> Vec<f32>{
+    let (offsets, size) = std140_layout(B::MEMBERS);
+    let mut values = vec![0.0; size / 4];
+    for ((offset, (name, member_type)), member_values) in offsets.iter().zip(B::MEMBERS).zip(block.member_values()){
+        assert_eq!(member_values.len() * 4, member_type.size(), "Wrong value count for uniform {}", name);
+        values[offset / 4..offset / 4 + member_values.len()].copy_from_slice(member_values);
+    }
+    return values;
+}
+
+/// `layout(std140) uniform` declaration of the block, as it appears in the shaders
+#[cfg(test)]
+pub fn glsl_declaration<B: UniformBlock>() -> String{
+    let members: String = B::MEMBERS.iter()
+        .map(|(name, member_type)| format!("    {} {};\n", member_type.glsl_name(), name))
+        .collect();
+    return format!("layout(std140) uniform {} {{\n{}}};", B::NAME, members);
+}
+
+/// camera and animation state, the same for everything drawn in a frame
+pub struct FrameUniforms{
+    pub projection: Matrix4<f32>,
+    pub view: Matrix4<f32>,
+    pub anim_time: f32
+}
+
+impl UniformBlock for FrameUniforms{
+    const NAME: &'static str = "Frame";
+    const BINDING: u32 = 0;
+    const MEMBERS: &'static [(&'static str, Std140Type)] = &[
+        ("projection", Std140Type::Mat4),
+        ("view", Std140Type::Mat4),
+        ("animTime", Std140Type::Float)
+    ];
+
+    fn member_values(&self) -> Vec<&[f32]>{
+        return vec![self.projection.as_slice(), self.view.as_slice(), std::slice::from_ref(&self.anim_time)];
+    }
+}
+
+/// the point light the shaded programs are lit by
+pub struct LightUniforms{
+    pub position: Vector3<f32>,
+    pub color: Vector3<f32>
+}
+
+impl UniformBlock for LightUniforms{
+    const NAME: &'static str = "Light";
+    const BINDING: u32 = 1;
+    const MEMBERS: &'static [(&'static str, Std140Type)] = &[
+        ("lightPos", Std140Type::Vec3),
+        ("lightColor", Std140Type::Vec3)
+    ];
+
+    fn member_values(&self) -> Vec<&[f32]>{
+        return vec![self.position.as_slice(), self.color.as_slice()];
+    }
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+    use crate::viewer::shaders::{FSHADER_FLAT, FSHADER_LINE, FSHADER_SMOOTH, VSHADER_FLAT, VSHADER_LINE, VSHADER_SMOOTH};
+
+    #[test]
+    fn std140_rules(){
+        // a float fills the end of a vec3, a vec3 after a float starts at the next vec4
+        let members = [("a", Std140Type::Vec3), ("b", Std140Type::Float), ("c", Std140Type::Float),
+            ("d", Std140Type::Vec3), ("e", Std140Type::Mat4), ("f", Std140Type::Float)];
+        assert_eq!(std140_layout(&members), (vec![0, 12, 16, 32, 48, 112], 128));
+        assert_eq!(std140_layout(&[("a", Std140Type::Float)]), (vec![0], 16));
+    }
+
+    #[test]
+    fn frame_layout(){
+        assert_eq!(std140_layout(FrameUniforms::MEMBERS), (vec![0, 64, 128], 144));
+
+        let frame = FrameUniforms{projection: Matrix4::from_fn(|i, j| (i + 4 * j) as f32), view: Matrix4::identity() * 2.0, anim_time: 0.5};
+        let values = std140_values(&frame);
+        assert_eq!(values.len(), 36);
+        assert_eq!(&values[0..16], frame.projection.as_slice());
+        assert_eq!(&values[16..32], frame.view.as_slice());
+        assert_eq!(&values[32..36], &[0.5, 0.0, 0.0, 0.0]);
+    }
+
+    #[test]
+    fn light_layout(){
+        assert_eq!(std140_layout(LightUniforms::MEMBERS), (vec![0, 16], 32));
+
+        let light = LightUniforms{position: Vector3::new(1.0, 2.0, 3.0), color: Vector3::new(0.25, 0.5, 0.75)};
+        assert_eq!(std140_values(&light), vec![1.0, 2.0, 3.0, 0.0, 0.25, 0.5, 0.75, 0.0]);
+    }
+
+    #[test]
+    fn shaders_declare_the_blocks(){
+        let frame = glsl_declaration::<FrameUniforms>();
+        let light = glsl_declaration::<LightUniforms>();
+        for source in [VSHADER_FLAT, VSHADER_SMOOTH, VSHADER_LINE]{
+            assert!(source.contains(&frame), "missing {} in {}", frame, source);
+        }
+        for source in [FSHADER_FLAT, FSHADER_SMOOTH]{
+            assert!(source.contains(&light), "missing {} in {}", light, source);
+        }
+        assert!(!FSHADER_LINE.contains("uniform Light"));
+    }
+}